@@ -51,6 +51,7 @@ fn benchmark_profile5(c: &mut Criterion) {
         data_id: 0x123,
         max_delta_counter: 1,
         offset: 0,
+        ..Default::default()
     };
 
     let mut sender = Profile5::new(config.clone()).unwrap();
@@ -85,6 +86,7 @@ fn benchmark_profile6(c: &mut Criterion) {
         min_data_length: 32 * 8,
         max_data_length: 256 * 8,
         max_delta_counter: 1,
+        ..Default::default()
     };
 
     let mut sender = Profile6::new(config.clone()).unwrap();
@@ -122,6 +124,7 @@ fn benchmark_profile7(c: &mut Criterion) {
         min_data_length: 20 * 8,
         max_data_length: 4096 * 8,
         max_delta_counter: 5,
+        ..Default::default()
     };
 
     let mut sender = Profile7::new(config.clone()).unwrap();
@@ -159,6 +162,7 @@ fn benchmark_profile8(c: &mut Criterion) {
         min_data_length: 256,
         max_data_length: 32768,
         max_delta_counter: 10,
+        ..Default::default()
     };
 
     let mut sender = Profile8::new(config.clone()).unwrap();
@@ -281,6 +285,7 @@ fn benchmark_all_profiles_comparison(c: &mut Criterion) {
         data_id: 0x123,
         max_delta_counter: 1,
         offset: 0,
+        ..Default::default()
     };
     let mut profile5 = Profile5::new(config5).unwrap();
     let data5 = vec![0u8; 8];
@@ -299,6 +304,7 @@ fn benchmark_all_profiles_comparison(c: &mut Criterion) {
         min_data_length: 20 * 8,
         max_data_length: 4096 * 8,
         max_delta_counter: 5,
+        ..Default::default()
     };
     let mut profile7 = Profile7::new(config7).unwrap();
     let data7 = vec![0u8; 64];
@@ -73,11 +73,19 @@ run_step "Building in debug mode" \
 run_step "Building in release mode" \
     cargo build --release --verbose
 
-# 7. Run benchmarks
+# 7. Build without std for a Cortex-M target (optional - requires the target)
+if rustup target list --installed 2>/dev/null | grep -q thumbv7em-none-eabihf; then
+    run_step "Building without std for thumbv7em-none-eabihf" \
+        cargo build --no-default-features --target thumbv7em-none-eabihf
+else
+    print_warning "thumbv7em-none-eabihf not installed. Run 'rustup target add thumbv7em-none-eabihf' to enable the no_std build"
+fi
+
+# 8. Run benchmarks
 run_step "Running benchmarks" \
     cargo bench --verbose
 
-# 8. Security audit (optional - check if cargo-audit is installed)
+# 9. Security audit (optional - check if cargo-audit is installed)
 if command -v cargo-audit >/dev/null 2>&1; then
     run_step "Running security audit" \
         cargo audit
@@ -85,7 +93,7 @@ else
     print_warning "cargo-audit not installed. Run 'cargo install cargo-audit' to enable security checks"
 fi
 
-# 9. Coverage (optional - check if cargo-tarpaulin is installed)
+# 10. Coverage (optional - check if cargo-tarpaulin is installed)
 if command -v cargo-tarpaulin >/dev/null 2>&1; then
     print_step "Running coverage analysis (this may take a while...)"
     if cargo tarpaulin --verbose --all-features --workspace --timeout 120 --out xml; then
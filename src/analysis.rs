@@ -0,0 +1,612 @@
+//! # Offline Analysis of Recorded Traffic
+//!
+//! Replays recorded CAN frames (see [`crate::can_log`]) or SOME/IP messages
+//! (see [`crate::pcap`]) through one E2E receiver per CAN identifier or
+//! SOME/IP event and collects per-identifier statistics: the number of each
+//! `E2EStatus`, the longest run of `WrongSequence`, counter gaps and the
+//! timestamp of the first occurrence of each failure.
+//!
+//! Any profile can be mapped to an identifier; on CAN these are typically
+//! Profile 5, 11 and 22, on SOME/IP Profile 4, 7 and 8 and their M variants.
+//!
+//! ```rust
+//! use std::collections::BTreeMap;
+//! use autosar_e2e::analysis::CanAnalyzer;
+//! use autosar_e2e::any::AnyProfileConfig;
+//! use autosar_e2e::can_log::read_log;
+//! use autosar_e2e::profile11::Profile11Config;
+//!
+//! let log = "(0.010) can0 123#C0000000000000\n(0.020) can0 123#C0000000000000\n";
+//! let mut configs = BTreeMap::new();
+//! configs.insert(0x123, AnyProfileConfig::Profile11(Profile11Config::default()));
+//!
+//! let mut analyzer = CanAnalyzer::new(configs).unwrap();
+//! analyzer.process_all(&read_log(log).unwrap());
+//! let statistics = analyzer.statistics(0x123).unwrap();
+//! assert_eq!(statistics.frames, 2);
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::any::{AnyProfile, AnyProfileConfig};
+use crate::can_log::CanFrame;
+use crate::pcap::{udp_datagram, PcapPacket, LINKTYPE_ETHERNET};
+use crate::someip::{read_messages, SomeIpMessage, SomeIpMessageId};
+use crate::{E2ECheckReport, E2EError, E2EHeaderFields, E2EProfile, E2EResult, E2EStatus};
+
+// Constants
+const SOMEIP_HEADER_LENGTH: usize = 16;
+const BITS_PER_BYTE: usize = 8;
+
+/// Timestamp of the first occurrence of a failure status
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FirstFailure {
+    /// Failure status
+    pub status: E2EStatus,
+    /// Timestamp of the first frame with this status, in seconds
+    pub timestamp: f64,
+}
+
+/// Statistics of the frames of one CAN identifier or SOME/IP event
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IdStatistics {
+    /// Name of the profile, e.g. "Profile 5"
+    pub profile: &'static str,
+    /// Number of frames received
+    pub frames: usize,
+    /// Number of frames per check status
+    pub status_counts: HashMap<E2EStatus, usize>,
+    /// Frames that could not be checked, e.g. because they are too short
+    pub invalid_frames: usize,
+    /// Longest run of consecutive `WrongSequence` results
+    pub longest_wrong_sequence_run: usize,
+    /// Number of jumps of the received counter by more than one
+    pub counter_gaps: usize,
+    /// Number of counter values skipped by all counter gaps
+    pub lost_counters: u64,
+    /// First occurrence of each failure status, in order of occurrence
+    pub first_failures: Vec<FirstFailure>,
+    /// Timestamp of the first frame that could not be checked
+    pub first_invalid_frame: Option<f64>,
+    /// Timestamp of the first frame
+    pub first_timestamp: Option<f64>,
+    /// Timestamp of the last frame
+    pub last_timestamp: Option<f64>,
+}
+
+impl IdStatistics {
+    /// Number of frames checked with the given status
+    pub fn count(&self, status: E2EStatus) -> usize {
+        self.status_counts.get(&status).copied().unwrap_or(0)
+    }
+}
+
+/// Receiver of one CAN identifier or SOME/IP event
+struct Receiver {
+    profile: AnyProfile,
+    statistics: IdStatistics,
+    wrong_sequence_run: usize,
+    last_counter: Option<u32>,
+}
+
+impl Receiver {
+    fn new(profile: AnyProfile) -> Self {
+        let statistics = IdStatistics {
+            profile: profile.name(),
+            ..Default::default()
+        };
+        Self {
+            profile,
+            statistics,
+            wrong_sequence_run: 0,
+            last_counter: None,
+        }
+    }
+
+    fn process(&mut self, timestamp: f64, data: &[u8]) -> Option<(E2EHeaderFields, E2EStatus)> {
+        let statistics = &mut self.statistics;
+        statistics.frames += 1;
+        statistics.first_timestamp.get_or_insert(timestamp);
+        statistics.last_timestamp = Some(timestamp);
+
+        let Ok(E2ECheckReport { header, status, .. }) = self.profile.check_detailed(data) else {
+            statistics.invalid_frames += 1;
+            statistics.first_invalid_frame.get_or_insert(timestamp);
+            return None;
+        };
+
+        *statistics.status_counts.entry(status).or_insert(0) += 1;
+        if !matches!(status, E2EStatus::Ok | E2EStatus::NoNewData)
+            && !statistics.first_failures.iter().any(|f| f.status == status)
+        {
+            statistics
+                .first_failures
+                .push(FirstFailure { status, timestamp });
+        }
+
+        if status == E2EStatus::WrongSequence {
+            self.wrong_sequence_run += 1;
+            statistics.longest_wrong_sequence_run = statistics
+                .longest_wrong_sequence_run
+                .max(self.wrong_sequence_run);
+        } else {
+            self.wrong_sequence_run = 0;
+        }
+
+        // the counter of corrupted frames is not trusted
+        if header.received_crc == header.computed_crc {
+            let modulus = u64::from(self.profile.max_counter()) + 1;
+            if let Some(last) = self.last_counter {
+                let delta = (u64::from(header.counter) + modulus - u64::from(last)) % modulus;
+                if delta > 1 {
+                    statistics.counter_gaps += 1;
+                    statistics.lost_counters += delta - 1;
+                }
+            }
+            self.last_counter = Some(header.counter);
+        }
+        Some((header, status))
+    }
+}
+
+/// Analyzer checking CAN frames with one E2E receiver per CAN identifier
+pub struct CanAnalyzer {
+    receivers: BTreeMap<u32, Receiver>,
+}
+
+impl CanAnalyzer {
+    /// Create an analyzer from the profile configuration of each CAN identifier
+    ///
+    /// # Errors
+    /// Returns an `E2EError` if a configuration is invalid
+    pub fn new(configs: BTreeMap<u32, AnyProfileConfig>) -> E2EResult<Self> {
+        let receivers = configs
+            .into_iter()
+            .map(|(id, config)| Ok((id, Receiver::new(AnyProfile::new(config)?))))
+            .collect::<E2EResult<_>>()?;
+        Ok(Self { receivers })
+    }
+
+    /// Check a frame with the receiver of its identifier
+    ///
+    /// # Returns
+    /// * `Some(E2EStatus)` with the check result
+    /// * `None` if the identifier is not configured or the frame could not be checked
+    pub fn process(&mut self, frame: &CanFrame) -> Option<E2EStatus> {
+        let receiver = self.receivers.get_mut(&frame.id)?;
+        receiver
+            .process(frame.timestamp, &frame.data)
+            .map(|(_, status)| status)
+    }
+
+    /// Check all frames in order
+    pub fn process_all(&mut self, frames: &[CanFrame]) {
+        for frame in frames {
+            self.process(frame);
+        }
+    }
+
+    /// Statistics of a CAN identifier, `None` if it is not configured
+    pub fn statistics(&self, id: u32) -> Option<&IdStatistics> {
+        self.receivers.get(&id).map(|receiver| &receiver.statistics)
+    }
+
+    /// Statistics of all configured CAN identifiers, ordered by identifier
+    pub fn report(&self) -> BTreeMap<u32, IdStatistics> {
+        self.receivers
+            .iter()
+            .map(|(id, receiver)| (*id, receiver.statistics.clone()))
+            .collect()
+    }
+}
+
+/// E2E configuration of a SOME/IP event
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SomeIpEventConfig {
+    /// Profile configuration, its offset is relative to the protected area
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub profile: AnyProfileConfig,
+    /// Number of SOME/IP header bits in front of the payload that are protected,
+    /// as for the E2E transformer
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub upper_header_bits_to_shift: usize,
+    /// Expected Source ID, for M variants
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub source_id: u32,
+}
+
+impl SomeIpEventConfig {
+    /// Configuration protecting the payload only, without SOME/IP header bits
+    pub fn new(profile: AnyProfileConfig) -> Self {
+        Self {
+            profile,
+            upper_header_bits_to_shift: 0,
+            source_id: 0,
+        }
+    }
+}
+
+/// Check result of one SOME/IP message
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SomeIpVerdict {
+    /// Timestamp of the packet, in seconds
+    pub timestamp: f64,
+    /// Service and method or event ID
+    pub message_id: SomeIpMessageId,
+    /// Session ID
+    pub session_id: u16,
+    /// Header fields, `None` if the message could not be checked
+    pub header: Option<E2EHeaderFields>,
+    /// Check result, `None` if the message could not be checked
+    pub status: Option<E2EStatus>,
+}
+
+/// Receiver of one SOME/IP event
+struct SomeIpReceiver {
+    receiver: Receiver,
+    protected_start: usize,
+}
+
+/// Analyzer checking SOME/IP messages with one E2E receiver per method or event
+///
+/// For M variants the expected message type and result are taken from the
+/// SOME/IP header of each message: responses and errors are message type 1,
+/// errors and messages with a return code other than E_OK are message result 1.
+pub struct SomeIpAnalyzer {
+    receivers: BTreeMap<SomeIpMessageId, SomeIpReceiver>,
+}
+
+impl SomeIpAnalyzer {
+    /// Create an analyzer from the E2E configuration of each SOME/IP method or event
+    ///
+    /// # Errors
+    /// Returns an `E2EError` if a configuration is invalid
+    pub fn new(configs: BTreeMap<SomeIpMessageId, SomeIpEventConfig>) -> E2EResult<Self> {
+        let receivers = configs
+            .into_iter()
+            .map(|(id, config)| {
+                let shift = config.upper_header_bits_to_shift;
+                if shift % BITS_PER_BYTE != 0 || shift > SOMEIP_HEADER_LENGTH * BITS_PER_BYTE {
+                    return Err(E2EError::UpperHeaderBitsInvalid {
+                        max: SOMEIP_HEADER_LENGTH * BITS_PER_BYTE,
+                    });
+                }
+                let mut profile = AnyProfile::new(config.profile)?;
+                match &mut profile {
+                    AnyProfile::Profile4m(p) => p.source_id = config.source_id,
+                    AnyProfile::Profile44m(p) => p.source_id = config.source_id,
+                    AnyProfile::Profile7m(p) => p.source_id = config.source_id,
+                    AnyProfile::Profile8m(p) => p.source_id = config.source_id,
+                    _ => {}
+                }
+                let receiver = SomeIpReceiver {
+                    receiver: Receiver::new(profile),
+                    protected_start: SOMEIP_HEADER_LENGTH - shift / BITS_PER_BYTE,
+                };
+                Ok((id, receiver))
+            })
+            .collect::<E2EResult<_>>()?;
+        Ok(Self { receivers })
+    }
+
+    /// Check a SOME/IP message with the receiver of its method or event
+    ///
+    /// # Returns
+    /// * `Some(SomeIpVerdict)` with the check result
+    /// * `None` if the method or event is not configured
+    pub fn process(&mut self, timestamp: f64, message: &SomeIpMessage) -> Option<SomeIpVerdict> {
+        let SomeIpReceiver {
+            receiver,
+            protected_start,
+        } = self.receivers.get_mut(&message.message_id)?;
+        let message_type = u8::from(message.is_response());
+        let message_result = u8::from(message.is_error());
+        macro_rules! set_metadata {
+            ($profile:expr) => {{
+                $profile.message_type = message_type;
+                $profile.message_result = message_result;
+            }};
+        }
+        match &mut receiver.profile {
+            AnyProfile::Profile4m(p) => set_metadata!(p),
+            AnyProfile::Profile44m(p) => set_metadata!(p),
+            AnyProfile::Profile7m(p) => set_metadata!(p),
+            AnyProfile::Profile8m(p) => set_metadata!(p),
+            _ => {}
+        }
+        let result = receiver.process(timestamp, &message.data[*protected_start..]);
+        Some(SomeIpVerdict {
+            timestamp,
+            message_id: message.message_id,
+            session_id: message.session_id,
+            header: result.as_ref().map(|(header, _)| header.clone()),
+            status: result.map(|(_, status)| status),
+        })
+    }
+
+    /// Check the SOME/IP messages of all UDP datagrams in captured Ethernet frames
+    ///
+    /// # Returns
+    /// The verdicts of all messages of configured methods and events, in order
+    pub fn process_capture(&mut self, packets: &[PcapPacket]) -> Vec<SomeIpVerdict> {
+        let mut verdicts = Vec::new();
+        for packet in packets {
+            if packet.link_type != LINKTYPE_ETHERNET {
+                continue;
+            }
+            let Some(datagram) = udp_datagram(&packet.data) else {
+                continue;
+            };
+            for message in read_messages(datagram.payload) {
+                verdicts.extend(self.process(packet.timestamp, &message));
+            }
+        }
+        verdicts
+    }
+
+    /// Statistics of a SOME/IP method or event, `None` if it is not configured
+    pub fn statistics(&self, id: SomeIpMessageId) -> Option<&IdStatistics> {
+        self.receivers
+            .get(&id)
+            .map(|receiver| &receiver.receiver.statistics)
+    }
+
+    /// Statistics of all configured SOME/IP methods and events, ordered by ID
+    pub fn report(&self) -> BTreeMap<SomeIpMessageId, IdStatistics> {
+        self.receivers
+            .iter()
+            .map(|(id, receiver)| (*id, receiver.receiver.statistics.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcap::{build_udp_frame, read_capture, write_pcapng};
+    use crate::profile4::{Profile4, Profile4Config};
+    use crate::profile5::{Profile5, Profile5Config};
+    use crate::profile7::Profile7Config;
+    use crate::profile7m::Profile7m;
+    use crate::transformer::{E2ETransformer, E2ETransformerConfig};
+
+    fn frame(timestamp: f64, id: u32, data: &[u8]) -> CanFrame {
+        CanFrame {
+            timestamp,
+            channel: "can0".into(),
+            id,
+            extended: false,
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_analyze_profile5() {
+        let config = Profile5Config {
+            data_length: 64,
+            max_delta_counter: 2,
+            ..Default::default()
+        };
+        let mut sender = Profile5::new(config.clone()).unwrap();
+        let mut frames = Vec::new();
+        for i in 0..12u8 {
+            let mut data = [0, 0, 0, i, 0, 0, 0, 0];
+            sender.protect(&mut data).unwrap();
+            // counters 3, 6, 7 and 8 are lost, 10 is corrupted, so the gap
+            // to 11 is counted from 9
+            if matches!(i, 3 | 6 | 7 | 8) {
+                continue;
+            }
+            if i == 10 {
+                data[7] ^= 0xff;
+            }
+            frames.push(frame(f64::from(i) / 100.0, 0x100, &data));
+        }
+        frames.push(frame(0.2, 0x100, &[0x00; 4]));
+        frames.push(frame(0.3, 0x200, &[0x00; 8]));
+
+        let mut configs = BTreeMap::new();
+        configs.insert(0x100, AnyProfileConfig::Profile5(config));
+        let mut analyzer = CanAnalyzer::new(configs).unwrap();
+        analyzer.process_all(&frames);
+        assert!(analyzer.statistics(0x200).is_none());
+
+        let statistics = analyzer.statistics(0x100).unwrap();
+        assert_eq!(statistics.profile, "Profile 5");
+        assert_eq!(statistics.frames, 9);
+        assert_eq!(statistics.count(E2EStatus::Ok), 4);
+        assert_eq!(statistics.count(E2EStatus::OkSomeLost), 2);
+        assert_eq!(statistics.count(E2EStatus::WrongSequence), 1);
+        assert_eq!(statistics.count(E2EStatus::CrcError), 1);
+        assert_eq!(statistics.invalid_frames, 1);
+        assert_eq!(statistics.longest_wrong_sequence_run, 1);
+        assert_eq!(statistics.counter_gaps, 3);
+        assert_eq!(statistics.lost_counters, 5);
+        assert_eq!(
+            statistics.first_failures,
+            vec![
+                FirstFailure {
+                    status: E2EStatus::OkSomeLost,
+                    timestamp: 0.04
+                },
+                FirstFailure {
+                    status: E2EStatus::WrongSequence,
+                    timestamp: 0.09
+                },
+                FirstFailure {
+                    status: E2EStatus::CrcError,
+                    timestamp: 0.1
+                },
+            ]
+        );
+        assert_eq!(statistics.first_invalid_frame, Some(0.2));
+        assert_eq!(statistics.first_timestamp, Some(0.0));
+        assert_eq!(statistics.last_timestamp, Some(0.2));
+        assert_eq!(analyzer.report().len(), 1);
+    }
+
+    fn someip_message(method_id: u16, session_id: u16) -> Vec<u8> {
+        let mut message = vec![0x12, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0c];
+        message[2..4].copy_from_slice(&method_id.to_be_bytes());
+        message.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0x02, 0x00]);
+        message[10..12].copy_from_slice(&session_id.to_be_bytes());
+        message.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        message
+    }
+
+    #[test]
+    fn test_analyze_someip_capture() {
+        let event_4 = SomeIpMessageId {
+            service_id: 0x1234,
+            method_id: 0x8001,
+        };
+        let event_7m = SomeIpMessageId {
+            service_id: 0x1234,
+            method_id: 0x8002,
+        };
+        // Profile 4 protecting the upper SOME/IP header, Profile 7M the payload only
+        let config_4 = Profile4Config {
+            offset: 64,
+            min_data_length: 160,
+            max_delta_counter: 2,
+            ..Default::default()
+        };
+        let config_7m = Profile7Config {
+            min_data_length: 192,
+            max_delta_counter: 2,
+            ..Default::default()
+        };
+        let mut sender_4 = E2ETransformer::new(
+            Profile4::new(config_4.clone()).unwrap(),
+            E2ETransformerConfig {
+                header_offset: 64,
+                header_length: 96,
+                upper_header_bits_to_shift: 64,
+            },
+        )
+        .unwrap();
+        let mut sender_7m = E2ETransformer::new(
+            Profile7m::new(config_7m.clone()).unwrap(),
+            E2ETransformerConfig {
+                header_offset: 0,
+                header_length: 192,
+                upper_header_bits_to_shift: 0,
+            },
+        )
+        .unwrap();
+        sender_7m.profile_mut().source_id = 0x0abc;
+
+        // golden capture recorded from the senders, message 2 of Profile 4
+        // is lost and message 3 of Profile 7M is corrupted
+        let mut packets = Vec::new();
+        for i in 0..5u16 {
+            let mut payload = Vec::new();
+            let mut buffer = [0u8; 64];
+            let length = sender_4
+                .protect_someip_out_of_place(&someip_message(0x8001, i + 1), &mut buffer)
+                .unwrap();
+            if i != 2 {
+                payload.extend_from_slice(&buffer[..length]);
+            }
+            let length = sender_7m
+                .protect_someip_out_of_place(&someip_message(0x8002, i + 1), &mut buffer)
+                .unwrap();
+            if i == 3 {
+                buffer[length - 1] ^= 0xff;
+            }
+            payload.extend_from_slice(&buffer[..length]);
+            // message of an unmapped event
+            payload.extend_from_slice(&someip_message(0x8003, i + 1));
+            let (source, destination) = if i % 2 == 0 {
+                ("10.0.0.1:30501", "239.0.0.1:30490")
+            } else {
+                ("[fd00::1]:30501", "[ff14::1]:30490")
+            };
+            let frame = build_udp_frame(
+                source.parse().unwrap(),
+                destination.parse().unwrap(),
+                &payload,
+            )
+            .unwrap();
+            packets.push(PcapPacket {
+                timestamp: 10.0 + f64::from(i) / 10.0,
+                link_type: LINKTYPE_ETHERNET,
+                data: frame,
+            });
+        }
+        let capture = read_capture(&write_pcapng(&packets)).unwrap();
+
+        let mut configs = BTreeMap::new();
+        configs.insert(
+            event_4,
+            SomeIpEventConfig {
+                upper_header_bits_to_shift: 64,
+                ..SomeIpEventConfig::new(AnyProfileConfig::Profile4(config_4))
+            },
+        );
+        configs.insert(
+            event_7m,
+            SomeIpEventConfig {
+                source_id: 0x0abc,
+                ..SomeIpEventConfig::new(AnyProfileConfig::Profile7m(config_7m))
+            },
+        );
+        let mut analyzer = SomeIpAnalyzer::new(configs).unwrap();
+        let verdicts = analyzer.process_capture(&capture);
+        assert_eq!(verdicts.len(), 9);
+        let statuses = |id: SomeIpMessageId| -> Vec<Option<E2EStatus>> {
+            verdicts
+                .iter()
+                .filter(|verdict| verdict.message_id == id)
+                .map(|verdict| verdict.status)
+                .collect()
+        };
+        assert_eq!(
+            statuses(event_4),
+            vec![
+                Some(E2EStatus::Ok),
+                Some(E2EStatus::Ok),
+                Some(E2EStatus::OkSomeLost),
+                Some(E2EStatus::Ok),
+            ]
+        );
+        assert_eq!(
+            statuses(event_7m),
+            vec![
+                Some(E2EStatus::Ok),
+                Some(E2EStatus::Ok),
+                Some(E2EStatus::Ok),
+                Some(E2EStatus::CrcError),
+                // the counter of the corrupted message is missing
+                Some(E2EStatus::OkSomeLost),
+            ]
+        );
+        assert_eq!(verdicts[0].session_id, 1);
+        assert_eq!(verdicts[0].header.as_ref().unwrap().counter, 0);
+        assert_eq!(verdicts[1].header.as_ref().unwrap().source_id, Some(0x0abc));
+
+        let statistics = analyzer.statistics(event_4).unwrap();
+        assert_eq!(statistics.profile, "Profile 4");
+        assert_eq!(statistics.counter_gaps, 1);
+        assert_eq!(statistics.lost_counters, 1);
+        let statistics = analyzer.statistics(event_7m).unwrap();
+        assert_eq!(statistics.count(E2EStatus::CrcError), 1);
+        assert_eq!(statistics.first_failures[0].timestamp, 10.3);
+        assert_eq!(analyzer.report().len(), 2);
+
+        // invalid upper header shift
+        let mut configs = BTreeMap::new();
+        configs.insert(
+            event_4,
+            SomeIpEventConfig {
+                upper_header_bits_to_shift: 12,
+                ..SomeIpEventConfig::new(AnyProfileConfig::Profile4(Profile4Config::default()))
+            },
+        );
+        assert!(SomeIpAnalyzer::new(configs).is_err());
+    }
+}
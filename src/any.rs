@@ -0,0 +1,868 @@
+//! # Runtime-selectable E2E Profile
+//!
+//! `AnyProfile` wraps every E2E profile of this crate in a single type, so
+//! that profiles selected at runtime (e.g. from a gateway routing table) can
+//! be stored side by side and used through one `protect`/`check` dispatch.
+//! The profile is selected by the variant of `AnyProfileConfig`.
+
+use crate::profile1::{Profile1, Profile1Config};
+use crate::profile11::{Profile11, Profile11Config};
+use crate::profile2::{Profile2, Profile2Config};
+use crate::profile22::{Profile22, Profile22Config};
+use crate::profile4::{Profile4, Profile4Config};
+use crate::profile44::{Profile44, Profile44Config};
+use crate::profile44m::Profile44m;
+use crate::profile4m::Profile4m;
+use crate::profile5::{Profile5, Profile5Config};
+use crate::profile6::{Profile6, Profile6Config};
+use crate::profile7::{Profile7, Profile7Config};
+use crate::profile7m::Profile7m;
+use crate::profile8::{Profile8, Profile8Config};
+use crate::profile8m::Profile8m;
+use crate::{
+    E2ECheckReport, E2EError, E2EForward, E2EHeaderFields, E2EHeaderLayout, E2EProfile, E2EResult,
+    E2ESnapshot, E2EStatus, E2ESyncPolicy,
+};
+
+/// Configuration of any E2E profile, the variant selects the profile
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "profile"))]
+pub enum AnyProfileConfig {
+    Profile1(Profile1Config),
+    Profile2(Profile2Config),
+    Profile4(Profile4Config),
+    Profile4m(Profile4Config),
+    Profile44(Profile44Config),
+    Profile44m(Profile44Config),
+    Profile5(Profile5Config),
+    Profile6(Profile6Config),
+    Profile7(Profile7Config),
+    Profile7m(Profile7Config),
+    Profile8(Profile8Config),
+    Profile8m(Profile8Config),
+    Profile11(Profile11Config),
+    Profile22(Profile22Config),
+}
+
+impl AnyProfileConfig {
+    /// Set the receiver behaviour at startup and after a loss of
+    /// synchronization, see `E2ESyncPolicy`
+    pub fn set_sync_policy(&mut self, policy: E2ESyncPolicy) {
+        match self {
+            AnyProfileConfig::Profile1(config) => config.sync_policy = policy,
+            AnyProfileConfig::Profile2(config) => config.sync_policy = policy,
+            AnyProfileConfig::Profile4(config) | AnyProfileConfig::Profile4m(config) => {
+                config.sync_policy = policy
+            }
+            AnyProfileConfig::Profile44(config) | AnyProfileConfig::Profile44m(config) => {
+                config.sync_policy = policy
+            }
+            AnyProfileConfig::Profile5(config) => config.sync_policy = policy,
+            AnyProfileConfig::Profile6(config) => config.sync_policy = policy,
+            AnyProfileConfig::Profile7(config) | AnyProfileConfig::Profile7m(config) => {
+                config.sync_policy = policy
+            }
+            AnyProfileConfig::Profile8(config) | AnyProfileConfig::Profile8m(config) => {
+                config.sync_policy = policy
+            }
+            AnyProfileConfig::Profile11(config) => config.sync_policy = policy,
+            AnyProfileConfig::Profile22(config) => config.sync_policy = policy,
+        }
+    }
+}
+
+/// Any E2E profile, selected at runtime
+#[derive(Clone)]
+pub enum AnyProfile {
+    Profile1(Profile1),
+    Profile2(Profile2),
+    Profile4(Profile4),
+    Profile4m(Profile4m),
+    Profile44(Profile44),
+    Profile44m(Profile44m),
+    Profile5(Profile5),
+    Profile6(Profile6),
+    Profile7(Profile7),
+    Profile7m(Profile7m),
+    Profile8(Profile8),
+    Profile8m(Profile8m),
+    Profile11(Profile11),
+    Profile22(Profile22),
+}
+
+/// Call the same function on the wrapped profile of every variant
+macro_rules! dispatch {
+    ($self:expr, $profile:ident => $call:expr) => {
+        match $self {
+            AnyProfile::Profile1($profile) => $call,
+            AnyProfile::Profile2($profile) => $call,
+            AnyProfile::Profile4($profile) => $call,
+            AnyProfile::Profile4m($profile) => $call,
+            AnyProfile::Profile44($profile) => $call,
+            AnyProfile::Profile44m($profile) => $call,
+            AnyProfile::Profile5($profile) => $call,
+            AnyProfile::Profile6($profile) => $call,
+            AnyProfile::Profile7($profile) => $call,
+            AnyProfile::Profile7m($profile) => $call,
+            AnyProfile::Profile8($profile) => $call,
+            AnyProfile::Profile8m($profile) => $call,
+            AnyProfile::Profile11($profile) => $call,
+            AnyProfile::Profile22($profile) => $call,
+        }
+    };
+}
+
+impl AnyProfile {
+    /// Name of the wrapped profile, e.g. "Profile 4M"
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnyProfile::Profile1(_) => "Profile 1",
+            AnyProfile::Profile2(_) => "Profile 2",
+            AnyProfile::Profile4(_) => "Profile 4",
+            AnyProfile::Profile4m(_) => "Profile 4M",
+            AnyProfile::Profile44(_) => "Profile 44",
+            AnyProfile::Profile44m(_) => "Profile 44M",
+            AnyProfile::Profile5(_) => "Profile 5",
+            AnyProfile::Profile6(_) => "Profile 6",
+            AnyProfile::Profile7(_) => "Profile 7",
+            AnyProfile::Profile7m(_) => "Profile 7M",
+            AnyProfile::Profile8(_) => "Profile 8",
+            AnyProfile::Profile8m(_) => "Profile 8M",
+            AnyProfile::Profile11(_) => "Profile 11",
+            AnyProfile::Profile22(_) => "Profile 22",
+        }
+    }
+
+    /// Width of the sequence counter in bits
+    pub fn counter_width(&self) -> u32 {
+        match self {
+            AnyProfile::Profile1(_)
+            | AnyProfile::Profile2(_)
+            | AnyProfile::Profile11(_)
+            | AnyProfile::Profile22(_) => 4,
+            AnyProfile::Profile5(_) | AnyProfile::Profile6(_) => 8,
+            AnyProfile::Profile4(_)
+            | AnyProfile::Profile4m(_)
+            | AnyProfile::Profile44(_)
+            | AnyProfile::Profile44m(_) => 16,
+            AnyProfile::Profile7(_)
+            | AnyProfile::Profile7m(_)
+            | AnyProfile::Profile8(_)
+            | AnyProfile::Profile8m(_) => 32,
+        }
+    }
+
+    /// Largest counter value before the counter wraps around
+    pub fn max_counter(&self) -> u32 {
+        match self {
+            // Profile 1 and 11 skip the value 15
+            AnyProfile::Profile1(_) | AnyProfile::Profile11(_) => 14,
+            _ => ((1u64 << self.counter_width()) - 1) as u32,
+        }
+    }
+
+    /// Read the header fields of the protected data without checking them
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn decode(&self, data: &[u8]) -> E2EResult<E2EHeaderFields> {
+        dispatch!(self, profile => profile.decode(data))
+    }
+
+    /// Check the protected data like `check` and report the decoded header,
+    /// the counter delta and the first failed check
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn check_detailed(&mut self, data: &[u8]) -> E2EResult<E2ECheckReport> {
+        dispatch!(self, profile => profile.check_detailed(data))
+    }
+}
+
+impl E2EProfile for AnyProfile {
+    type Config = AnyProfileConfig;
+
+    fn new(config: Self::Config) -> E2EResult<Self> {
+        Ok(match config {
+            AnyProfileConfig::Profile1(config) => AnyProfile::Profile1(Profile1::new(config)?),
+            AnyProfileConfig::Profile2(config) => AnyProfile::Profile2(Profile2::new(config)?),
+            AnyProfileConfig::Profile4(config) => AnyProfile::Profile4(Profile4::new(config)?),
+            AnyProfileConfig::Profile4m(config) => AnyProfile::Profile4m(Profile4m::new(config)?),
+            AnyProfileConfig::Profile44(config) => AnyProfile::Profile44(Profile44::new(config)?),
+            AnyProfileConfig::Profile44m(config) => {
+                AnyProfile::Profile44m(Profile44m::new(config)?)
+            }
+            AnyProfileConfig::Profile5(config) => AnyProfile::Profile5(Profile5::new(config)?),
+            AnyProfileConfig::Profile6(config) => AnyProfile::Profile6(Profile6::new(config)?),
+            AnyProfileConfig::Profile7(config) => AnyProfile::Profile7(Profile7::new(config)?),
+            AnyProfileConfig::Profile7m(config) => AnyProfile::Profile7m(Profile7m::new(config)?),
+            AnyProfileConfig::Profile8(config) => AnyProfile::Profile8(Profile8::new(config)?),
+            AnyProfileConfig::Profile8m(config) => AnyProfile::Profile8m(Profile8m::new(config)?),
+            AnyProfileConfig::Profile11(config) => AnyProfile::Profile11(Profile11::new(config)?),
+            AnyProfileConfig::Profile22(config) => AnyProfile::Profile22(Profile22::new(config)?),
+        })
+    }
+
+    fn protect(&mut self, data: &mut [u8]) -> E2EResult<()> {
+        dispatch!(self, profile => profile.protect(data))
+    }
+
+    fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus> {
+        dispatch!(self, profile => profile.check(data))
+    }
+
+    fn check_no_data(&mut self) -> E2EResult<E2EStatus> {
+        dispatch!(self, profile => profile.check_no_data())
+    }
+
+    fn protect_init(&mut self) {
+        dispatch!(self, profile => profile.protect_init())
+    }
+
+    fn check_init(&mut self) {
+        dispatch!(self, profile => profile.check_init())
+    }
+
+    fn snapshot(&self) -> E2ESnapshot {
+        dispatch!(self, profile => profile.snapshot())
+    }
+
+    fn restore(&mut self, snapshot: &E2ESnapshot) -> E2EResult<()> {
+        dispatch!(self, profile => profile.restore(snapshot))
+    }
+}
+
+impl E2EHeaderLayout for AnyProfile {
+    fn header_offset(&self) -> usize {
+        dispatch!(self, profile => profile.header_offset())
+    }
+
+    fn header_length(&self) -> usize {
+        dispatch!(self, profile => profile.header_length())
+    }
+}
+
+impl E2EForward for AnyProfile {
+    fn forward(&mut self, data: &mut [u8], status: E2EStatus) -> E2EResult<()> {
+        match self {
+            AnyProfile::Profile1(_) | AnyProfile::Profile2(_) => {
+                Err(E2EError::ForwardingNotSupported {
+                    profile: self.name(),
+                })
+            }
+            AnyProfile::Profile4(profile) => profile.forward(data, status),
+            AnyProfile::Profile4m(profile) => profile.forward(data, status),
+            AnyProfile::Profile44(profile) => profile.forward(data, status),
+            AnyProfile::Profile44m(profile) => profile.forward(data, status),
+            AnyProfile::Profile5(profile) => profile.forward(data, status),
+            AnyProfile::Profile6(profile) => profile.forward(data, status),
+            AnyProfile::Profile7(profile) => profile.forward(data, status),
+            AnyProfile::Profile7m(profile) => profile.forward(data, status),
+            AnyProfile::Profile8(profile) => profile.forward(data, status),
+            AnyProfile::Profile8m(profile) => profile.forward(data, status),
+            AnyProfile::Profile11(profile) => profile.forward(data, status),
+            AnyProfile::Profile22(profile) => profile.forward(data, status),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_profiles() -> Vec<(AnyProfileConfig, usize)> {
+        vec![
+            (AnyProfileConfig::Profile1(Profile1Config::default()), 8),
+            (AnyProfileConfig::Profile2(Profile2Config::default()), 8),
+            (AnyProfileConfig::Profile4(Profile4Config::default()), 20),
+            (
+                AnyProfileConfig::Profile4m(Profile4Config {
+                    min_data_length: 160,
+                    ..Default::default()
+                }),
+                20,
+            ),
+            (AnyProfileConfig::Profile44(Profile44Config::default()), 20),
+            (
+                AnyProfileConfig::Profile44m(Profile44Config {
+                    min_data_length: 160,
+                    ..Default::default()
+                }),
+                20,
+            ),
+            (AnyProfileConfig::Profile5(Profile5Config::default()), 3),
+            (AnyProfileConfig::Profile6(Profile6Config::default()), 8),
+            (AnyProfileConfig::Profile7(Profile7Config::default()), 24),
+            (
+                AnyProfileConfig::Profile7m(Profile7Config {
+                    min_data_length: 192,
+                    ..Default::default()
+                }),
+                24,
+            ),
+            (AnyProfileConfig::Profile8(Profile8Config::default()), 24),
+            (
+                AnyProfileConfig::Profile8m(Profile8Config {
+                    min_data_length: 192,
+                    ..Default::default()
+                }),
+                24,
+            ),
+            (AnyProfileConfig::Profile11(Profile11Config::default()), 8),
+            (AnyProfileConfig::Profile22(Profile22Config::default()), 8),
+        ]
+    }
+
+    #[test]
+    fn test_any_profile_dispatch() {
+        for (config, length) in all_profiles() {
+            let mut profile_tx = AnyProfile::new(config.clone()).unwrap();
+            let mut profile_rx = AnyProfile::new(config).unwrap();
+            let mut data = vec![0x00; length];
+            for _ in 0..3 {
+                profile_tx.protect(&mut data).unwrap();
+                assert_eq!(
+                    profile_rx.check(&data).unwrap(),
+                    E2EStatus::Ok,
+                    "{}",
+                    profile_rx.name()
+                );
+            }
+            data[length - 1] ^= 0x01;
+            assert_eq!(
+                profile_rx.check(&data).unwrap(),
+                E2EStatus::CrcError,
+                "{}",
+                profile_rx.name()
+            );
+        }
+    }
+
+    #[test]
+    fn test_any_profile_check_detailed() {
+        for (config, length) in all_profiles() {
+            let mut profile_tx = AnyProfile::new(config.clone()).unwrap();
+            let mut profile_rx = AnyProfile::new(config.clone()).unwrap();
+            let mut profile_detailed = AnyProfile::new(config).unwrap();
+            let mut data = vec![0x00; length];
+            for cycle in 0..6 {
+                if cycle != 2 {
+                    profile_tx.protect(&mut data).unwrap();
+                }
+                if cycle == 4 {
+                    data[length - 1] ^= 0x01;
+                }
+                let header = profile_detailed.decode(&data).unwrap();
+                let report = profile_detailed.check_detailed(&data).unwrap();
+                let status = profile_rx.check(&data).unwrap();
+                assert_eq!(report.status, status, "{}", profile_rx.name());
+                assert_eq!(report.header, header, "{}", profile_rx.name());
+                assert_eq!(
+                    report.failed_check.is_none(),
+                    matches!(status, E2EStatus::Ok | E2EStatus::OkSomeLost)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_any_profile_snapshot_restore() {
+        let profiles = all_profiles();
+        for (index, (config, length)) in profiles.iter().enumerate() {
+            let mut profile_tx = AnyProfile::new(config.clone()).unwrap();
+            let mut profile_rx = AnyProfile::new(config.clone()).unwrap();
+            let mut data = vec![0x00; *length];
+            for _ in 0..3 {
+                profile_tx.protect(&mut data).unwrap();
+                profile_rx.check(&data).unwrap();
+            }
+            // warm restart from the stored snapshots
+            let tx_bytes = profile_tx.snapshot().to_bytes();
+            let rx_bytes = profile_rx.snapshot().to_bytes();
+            let mut restored_tx = AnyProfile::new(config.clone()).unwrap();
+            let mut restored_rx = AnyProfile::new(config.clone()).unwrap();
+            restored_tx
+                .restore(&E2ESnapshot::from_bytes(&tx_bytes).unwrap())
+                .unwrap();
+            restored_rx
+                .restore(&E2ESnapshot::from_bytes(&rx_bytes).unwrap())
+                .unwrap();
+            let mut restored_data = data.clone();
+            profile_tx.protect(&mut data).unwrap();
+            restored_tx.protect(&mut restored_data).unwrap();
+            assert_eq!(restored_data, data, "{}", profile_tx.name());
+            assert_eq!(
+                restored_rx.check(&data).unwrap(),
+                E2EStatus::Ok,
+                "{}",
+                profile_rx.name()
+            );
+
+            let (other_config, _) = &profiles[(index + 1) % profiles.len()];
+            let mut other = AnyProfile::new(other_config.clone()).unwrap();
+            assert!(
+                matches!(
+                    other.restore(&profile_rx.snapshot()),
+                    Err(E2EError::SnapshotConfigMismatch { .. })
+                ),
+                "{}",
+                other.name()
+            );
+        }
+    }
+
+    /// Statuses of a fresh receiver checking the given frames of a sender
+    fn sync_statuses(
+        mut config: AnyProfileConfig,
+        length: usize,
+        policy: E2ESyncPolicy,
+        frames: &[usize],
+    ) -> Vec<E2EStatus> {
+        config.set_sync_policy(policy);
+        let mut profile_tx = AnyProfile::new(config.clone()).unwrap();
+        let mut profile_rx = AnyProfile::new(config).unwrap();
+        let mut data = vec![0x00; length];
+        let mut statuses = Vec::new();
+        for frame in 0..=*frames.iter().max().unwrap() {
+            profile_tx.protect(&mut data).unwrap();
+            if frames.contains(&frame) {
+                statuses.push(profile_rx.check(&data).unwrap());
+            }
+        }
+        statuses
+    }
+
+    #[test]
+    fn test_any_profile_sync_policy() {
+        use E2EStatus::*;
+        for (config, length) in all_profiles() {
+            let name = AnyProfile::new(config.clone()).unwrap().name();
+            // Profiles 1 and 2 widen the accepted delta on every check cycle
+            // and accept any first message by default
+            let has_window = matches!(name, "Profile 1" | "Profile 2");
+            let default_policy = match &config {
+                AnyProfileConfig::Profile1(config) => config.sync_policy,
+                AnyProfileConfig::Profile2(config) => config.sync_policy,
+                _ => E2ESyncPolicy::default(),
+            };
+            let statuses =
+                |policy, frames: &[usize]| sync_statuses(config.clone(), length, policy, frames);
+
+            // a first frame with counter 0 is accepted by every profile
+            let mut profile_tx = AnyProfile::new(config.clone()).unwrap();
+            let mut data = vec![0x00; length];
+            profile_tx.protect(&mut data).unwrap();
+            while profile_tx.decode(&data).unwrap().counter != 0 {
+                profile_tx.protect(&mut data).unwrap();
+            }
+            let mut profile_rx = AnyProfile::new(config.clone()).unwrap();
+            assert_eq!(profile_rx.check(&data).unwrap(), Ok, "{name}");
+
+            // a far first counter is only accepted with accept-any-first,
+            // which is the default of Profiles 1 and 2
+            let expected = if has_window { Ok } else { WrongSequence };
+            assert_eq!(statuses(default_policy, &[4]), [expected], "{name}");
+            let policy = E2ESyncPolicy::accept_any_first();
+            assert_eq!(statuses(policy, &[4]), [Ok], "{name}");
+
+            // require 3 consecutive valid frames after startup and after a
+            // counter discontinuity
+            let policy = E2ESyncPolicy {
+                min_consecutive: 3,
+                ..Default::default()
+            };
+            let expected: &[E2EStatus] = if has_window {
+                // the receiver keeps its counter on WrongSequence
+                &[Sync, Sync, Ok, Ok, WrongSequence, WrongSequence]
+            } else {
+                &[Sync, Sync, Ok, Ok, WrongSequence, Sync, Sync, Ok]
+            };
+            let frames: &[usize] = if has_window {
+                &[0, 1, 2, 3, 7, 12]
+            } else {
+                &[0, 1, 2, 3, 7, 8, 9, 10]
+            };
+            assert_eq!(statuses(policy, frames), expected, "{name}");
+
+            // resynchronize after 2 consecutive WrongSequence results
+            let frames = &[0, 3, 6, 9];
+            assert_eq!(
+                statuses(default_policy, frames),
+                [Ok, WrongSequence, WrongSequence, WrongSequence],
+                "{name}"
+            );
+            let policy = E2ESyncPolicy {
+                accept_any_first: true,
+                resync_after_errors: 2,
+                ..Default::default()
+            };
+            assert_eq!(
+                statuses(policy, frames),
+                [Ok, WrongSequence, WrongSequence, Ok],
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_any_profile_no_new_data() {
+        use E2EStatus::*;
+        for (config, length) in all_profiles() {
+            let mut profile_tx = AnyProfile::new(config.clone()).unwrap();
+            let mut profile_rx = AnyProfile::new(config).unwrap();
+            let name = profile_rx.name();
+            // Profiles 1 and 2 widen the accepted delta on every check cycle
+            let has_window = matches!(name, "Profile 1" | "Profile 2");
+            let mut data = vec![0x00; length];
+            profile_tx.protect(&mut data).unwrap();
+            assert_eq!(profile_rx.check(&data).unwrap(), Ok, "{name}");
+
+            // cycles without data keep the last valid counter
+            for _ in 0..2 {
+                assert_eq!(profile_rx.check_no_data().unwrap(), NoNewData, "{name}");
+            }
+            profile_tx.protect(&mut data).unwrap();
+            assert_eq!(profile_rx.check(&data).unwrap(), Ok, "{name}");
+
+            // two messages lost during two cycles without data
+            for _ in 0..2 {
+                assert_eq!(profile_rx.check_no_data().unwrap(), NoNewData, "{name}");
+            }
+            for _ in 0..3 {
+                profile_tx.protect(&mut data).unwrap();
+            }
+            let expected = if has_window {
+                OkSomeLost
+            } else {
+                WrongSequence
+            };
+            assert_eq!(profile_rx.check(&data).unwrap(), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_any_profile_init() {
+        let policies = [
+            E2ESyncPolicy::default(),
+            E2ESyncPolicy {
+                min_consecutive: 2,
+                resync_after_errors: 3,
+                ..Default::default()
+            },
+        ];
+        for (mut config, length) in all_profiles() {
+            for policy in policies {
+                config.set_sync_policy(policy);
+                let mut profile_tx = AnyProfile::new(config.clone()).unwrap();
+                let mut profile_rx = AnyProfile::new(config.clone()).unwrap();
+                let mut data = vec![0x00; length];
+                for frame in 0..8 {
+                    profile_tx.protect(&mut data).unwrap();
+                    if frame != 3 {
+                        profile_rx.check(&data).unwrap();
+                    }
+                }
+                profile_rx.check_no_data().unwrap();
+
+                profile_tx.protect_init();
+                profile_rx.check_init();
+                let mut fresh_tx = AnyProfile::new(config.clone()).unwrap();
+                let mut fresh_rx = AnyProfile::new(config.clone()).unwrap();
+                assert_eq!(profile_tx.snapshot(), fresh_tx.snapshot());
+                assert_eq!(profile_rx.snapshot(), fresh_rx.snapshot());
+                // the reset instances behave exactly like fresh ones
+                let mut fresh_data = vec![0x00; length];
+                for frame in 0..8 {
+                    profile_tx.protect(&mut data).unwrap();
+                    fresh_tx.protect(&mut fresh_data).unwrap();
+                    assert_eq!(data, fresh_data, "{}", profile_tx.name());
+                    if frame % 3 != 1 {
+                        assert_eq!(
+                            profile_rx.check(&data).unwrap(),
+                            fresh_rx.check(&data).unwrap(),
+                            "{}",
+                            profile_rx.name()
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_any_profile_metadata() {
+        let config = Profile4Config {
+            min_data_length: 128,
+            ..Default::default()
+        };
+        let profile = AnyProfile::new(AnyProfileConfig::Profile4m(config)).unwrap();
+        assert_eq!(profile.name(), "Profile 4M");
+        assert_eq!(profile.header_length(), 128);
+        assert_eq!(profile.counter_width(), 16);
+        assert_eq!(profile.max_counter(), 0xFFFF);
+
+        let profile =
+            AnyProfile::new(AnyProfileConfig::Profile11(Profile11Config::default())).unwrap();
+        assert_eq!(profile.header_offset(), 0);
+        assert_eq!(profile.header_length(), 16);
+        assert_eq!(profile.counter_width(), 4);
+        assert_eq!(profile.max_counter(), 14);
+
+        let profile =
+            AnyProfile::new(AnyProfileConfig::Profile8(Profile8Config::default())).unwrap();
+        assert_eq!(profile.max_counter(), 0xFFFFFFFF);
+    }
+
+    /// Configurations of every profile with the header at `offset` and a
+    /// (minimal) data length of `length`, both in bits
+    fn layout_configs(offset: usize, length: usize) -> Vec<AnyProfileConfig> {
+        let max_length = length + 64;
+        let profile1 = Profile1Config {
+            crc_offset: (offset & !0x07) as u8,
+            counter_offset: offset as u8,
+            nibble_offset: offset as u8,
+            mode: crate::profile1::Profile1IdMode::Nibble,
+            data_length: length as u8,
+            ..Default::default()
+        };
+        let profile11 = Profile11Config {
+            crc_offset: (offset & !0x07) as u8,
+            counter_offset: offset as u8,
+            nibble_offset: offset as u8,
+            data_length: length as u8,
+            ..Default::default()
+        };
+        let profile4 = Profile4Config {
+            offset: offset as u16,
+            min_data_length: length as u16,
+            max_data_length: max_length as u16,
+            ..Default::default()
+        };
+        let profile44 = Profile44Config {
+            offset: offset as u32,
+            min_data_length: length as u32,
+            max_data_length: max_length as u32,
+            ..Default::default()
+        };
+        let profile7 = Profile7Config {
+            offset: offset as u32,
+            min_data_length: length as u32,
+            max_data_length: max_length as u32,
+            ..Default::default()
+        };
+        let profile8 = Profile8Config {
+            offset: offset as u32,
+            min_data_length: length as u32,
+            max_data_length: max_length as u32,
+            ..Default::default()
+        };
+        vec![
+            AnyProfileConfig::Profile1(profile1),
+            AnyProfileConfig::Profile2(Profile2Config {
+                data_length: length,
+                ..Default::default()
+            }),
+            AnyProfileConfig::Profile4(profile4.clone()),
+            AnyProfileConfig::Profile4m(profile4),
+            AnyProfileConfig::Profile44(profile44.clone()),
+            AnyProfileConfig::Profile44m(profile44),
+            AnyProfileConfig::Profile5(Profile5Config {
+                offset: offset as u16,
+                data_length: length as u16,
+                ..Default::default()
+            }),
+            AnyProfileConfig::Profile6(Profile6Config {
+                offset: offset as u16,
+                min_data_length: length as u16,
+                max_data_length: max_length as u16,
+                ..Default::default()
+            }),
+            AnyProfileConfig::Profile7(profile7.clone()),
+            AnyProfileConfig::Profile7m(profile7),
+            AnyProfileConfig::Profile8(profile8.clone()),
+            AnyProfileConfig::Profile8m(profile8),
+            AnyProfileConfig::Profile11(profile11),
+            AnyProfileConfig::Profile22(Profile22Config {
+                offset,
+                data_length: length,
+                ..Default::default()
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_any_profile_offset_bounds() {
+        // Every accepted offset/length combination up to 240 bits is usable,
+        // and no data length makes protect, check or decode panic
+        for offset in (0..=240).step_by(4) {
+            for length in (0..=240).step_by(8) {
+                for config in layout_configs(offset, length) {
+                    let Ok(mut profile_tx) = AnyProfile::new(config.clone()) else {
+                        continue;
+                    };
+                    let mut profile_rx = AnyProfile::new(config).unwrap();
+                    let mut usable = false;
+                    for data_length in 0..=length / 8 + 10 {
+                        let mut data = vec![0x00; data_length];
+                        let protected = profile_tx.protect(&mut data).is_ok();
+                        let checked = profile_rx.check(&data).is_ok();
+                        assert_eq!(
+                            protected,
+                            checked,
+                            "{} offset {} length {} data length {}",
+                            profile_rx.name(),
+                            offset,
+                            length,
+                            data_length
+                        );
+                        assert_eq!(profile_rx.decode(&data).is_ok(), checked);
+                        let _ = profile_tx.forward(&mut data, E2EStatus::Ok);
+                        usable |= protected;
+                    }
+                    assert!(
+                        usable,
+                        "{} offset {} length {}",
+                        profile_rx.name(),
+                        offset,
+                        length
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_any_profile_large_offset_bounds() {
+        // Offsets and lengths at the limits of the configuration fields are
+        // either rejected or usable, and never make protect, check or decode
+        // panic
+        let limits = [
+            32000,
+            u16::MAX as usize - 7,
+            u16::MAX as usize,
+            u32::MAX as usize - 7,
+            u32::MAX as usize,
+        ];
+        for offset in [0].into_iter().chain(limits) {
+            for length in [256].into_iter().chain(limits) {
+                for config in layout_configs(offset, length) {
+                    let Ok(mut profile_tx) = AnyProfile::new(config.clone()) else {
+                        continue;
+                    };
+                    let mut profile_rx = AnyProfile::new(config).unwrap();
+                    let mut data_lengths = vec![0, 1, 16];
+                    // data of the configured length if it can be allocated
+                    let allocatable = length <= u16::MAX as usize;
+                    if allocatable {
+                        data_lengths.extend([length / 8 - 1, length / 8, length / 8 + 1]);
+                    }
+                    let mut usable = false;
+                    for data_length in data_lengths {
+                        let mut data = vec![0x00; data_length];
+                        let protected = profile_tx.protect(&mut data).is_ok();
+                        let checked = profile_rx.check(&data).is_ok();
+                        assert_eq!(
+                            protected,
+                            checked,
+                            "{} offset {} length {} data length {}",
+                            profile_rx.name(),
+                            offset,
+                            length,
+                            data_length
+                        );
+                        assert_eq!(profile_rx.decode(&data).is_ok(), checked);
+                        let _ = profile_tx.forward(&mut data, E2EStatus::Ok);
+                        usable |= protected;
+                    }
+                    assert!(
+                        usable || !allocatable,
+                        "{} offset {} length {}",
+                        profile_rx.name(),
+                        offset,
+                        length
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_any_profile_offset_rejected() {
+        let config = Profile4Config {
+            offset: 32000,
+            ..Default::default()
+        };
+        assert_eq!(
+            AnyProfile::new(AnyProfileConfig::Profile4(config)).err(),
+            Some(E2EError::OffsetBeyondHeader {
+                offset: 32000,
+                header_length: 12,
+            })
+        );
+        let config = Profile7Config {
+            offset: 32,
+            min_data_length: 192,
+            max_data_length: 192,
+            ..Default::default()
+        };
+        assert!(AnyProfile::new(AnyProfileConfig::Profile7(config.clone())).is_ok());
+        assert_eq!(
+            AnyProfile::new(AnyProfileConfig::Profile7m(config)).err(),
+            Some(E2EError::OffsetBeyondHeader {
+                offset: 32,
+                header_length: 24,
+            })
+        );
+        // The metadata of Profile 4M does not fit the default minimum data
+        // length of Profile 4
+        assert_eq!(
+            AnyProfile::new(AnyProfileConfig::Profile4m(Profile4Config::default())).err(),
+            Some(E2EError::MinDataLengthOutOfRange {
+                min: 16,
+                max: 4096,
+                actual: 96,
+            })
+        );
+    }
+
+    #[test]
+    fn test_any_profile_forward() {
+        let mut profile =
+            AnyProfile::new(AnyProfileConfig::Profile1(Profile1Config::default())).unwrap();
+        let mut data = vec![0x00; 8];
+        assert!(profile.forward(&mut data, E2EStatus::Ok).is_err());
+
+        let mut profile_tx =
+            AnyProfile::new(AnyProfileConfig::Profile22(Profile22Config::default())).unwrap();
+        let mut profile_rx =
+            AnyProfile::new(AnyProfileConfig::Profile22(Profile22Config::default())).unwrap();
+        profile_tx.forward(&mut data, E2EStatus::Ok).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::Repeated).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_any_profile_config_serde() {
+        use crate::profile11::Profile11IdMode;
+
+        let config: AnyProfileConfig =
+            serde_json::from_str(r#"{"profile": "Profile11", "mode": "Nibble", "data_id": 291}"#)
+                .unwrap();
+        let AnyProfileConfig::Profile11(config) = config else {
+            panic!("expected Profile 11");
+        };
+        assert_eq!(config.mode, Profile11IdMode::Nibble);
+        assert_eq!(config.data_id, 0x123);
+        assert!(serde_json::from_str::<AnyProfileConfig>(
+            r#"{"profile": "Profile11", "max_delta_counter": 0}"#
+        )
+        .is_err());
+        assert_eq!(
+            serde_json::to_string(&E2EStatus::OkSomeLost).unwrap(),
+            r#""OkSomeLost""#
+        );
+    }
+}
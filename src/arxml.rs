@@ -0,0 +1,741 @@
+//! # ARXML Import of E2E Profile Configurations
+//!
+//! Reads E2E profile configurations from AUTOSAR ARXML and converts them into
+//! `AnyProfileConfig` values. The following sources are supported:
+//! - `END-TO-END-PROTECTION` in an `END-TO-END-PROTECTION-SET`, keyed by the
+//!   referenced I-SIGNAL-I-PDU (or I-SIGNAL-GROUP, or the protection itself)
+//! - `END-TO-END-TRANSFORMATION-I-SIGNAL-PROPS` of an I-SIGNAL-GROUP or
+//!   I-SIGNAL, combined with the `END-TO-END-TRANSFORMATION-DESCRIPTION` of
+//!   the referenced transformer, keyed by the I-SIGNAL-GROUP/I-SIGNAL
+//! - `E2E-PROFILE-CONFIGURATION`, keyed by its short name
+//!
+//! The configurations are not validated; pass them to `AnyProfile::new` to do so.
+//! Parameters without a counterpart in the profile configuration, e.g. the
+//! state machine settings, are reported in `ArxmlProfileConfig::unmapped`.
+//!
+//! This module is available with the `arxml` feature.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use roxmltree::{Document, Node};
+use thiserror::Error;
+
+use crate::any::AnyProfileConfig;
+use crate::profile1::{Profile1Config, Profile1IdMode};
+use crate::profile11::{Profile11Config, Profile11IdMode};
+use crate::profile2::Profile2Config;
+use crate::profile22::Profile22Config;
+use crate::profile4::Profile4Config;
+use crate::profile44::Profile44Config;
+use crate::profile44m::Profile44m;
+use crate::profile4m::Profile4m;
+use crate::profile5::Profile5Config;
+use crate::profile6::Profile6Config;
+use crate::profile7::Profile7Config;
+use crate::profile7m::Profile7m;
+use crate::profile8::Profile8Config;
+use crate::profile8m::Profile8m;
+
+// Constants
+const DATA_ID_LIST_LENGTH: usize = 16;
+/// Elements whose content never holds profile parameters
+const SKIPPED_ELEMENTS: [&str; 6] = [
+    "DESC",
+    "LONG-NAME",
+    "INTRODUCTION",
+    "ADMIN-DATA",
+    "ANNOTATIONS",
+    "VARIATION-POINT",
+];
+/// Elements that identify or link a configuration rather than parameterize it
+const STRUCTURAL_ELEMENTS: [&str; 6] = [
+    "SHORT-NAME",
+    "CATEGORY",
+    "PROFILE-NAME",
+    "TRANSFORMER-REF",
+    "I-SIGNAL-GROUP-REF",
+    "I-SIGNAL-I-PDU-REF",
+];
+
+/// ARXML import error types
+#[derive(Debug, Error)]
+pub enum ArxmlError {
+    /// The ARXML file could not be read
+    #[error("Failed to read ARXML file: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The ARXML file is not well-formed XML
+    #[error("Failed to parse ARXML: {0}")]
+    Xml(#[from] roxmltree::Error),
+
+    /// No profile is given for a configuration
+    #[error("{path}: no E2E profile given")]
+    MissingProfile { path: String },
+
+    /// The profile is not implemented by this crate
+    #[error("{path}: unsupported E2E profile '{profile}'")]
+    UnsupportedProfile { path: String, profile: String },
+
+    /// A parameter value does not fit the profile configuration
+    #[error("{path}: invalid value '{value}' for {parameter}")]
+    InvalidValue {
+        path: String,
+        parameter: String,
+        value: String,
+    },
+
+    /// A reference does not point to an element of the ARXML
+    #[error("{path}: unresolved reference '{reference}'")]
+    UnresolvedReference { path: String, reference: String },
+
+    /// Two configurations share the same name
+    #[error("Duplicate configuration name '{name}' at {first} and {second}")]
+    DuplicateName {
+        name: String,
+        first: String,
+        second: String,
+    },
+}
+
+/// Parameter read from ARXML
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ArxmlParameter {
+    /// ARXML path of the element containing the parameter
+    pub path: String,
+    /// Element name of the parameter, e.g. "WINDOW-SIZE"
+    pub name: String,
+    /// Value of the parameter as written in the ARXML
+    pub value: String,
+}
+
+/// Profile configuration read from ARXML
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ArxmlProfileConfig {
+    /// ARXML path of the element the configuration was read from
+    pub path: String,
+    /// Profile configuration, not yet validated
+    pub config: AnyProfileConfig,
+    /// Parameters that have no counterpart in the profile configuration
+    pub unmapped: Vec<ArxmlParameter>,
+}
+
+/// Parameters collected for one configuration
+#[derive(Default)]
+struct ParameterSet {
+    data_ids: Vec<ArxmlParameter>,
+    parameters: Vec<ArxmlParameter>,
+}
+
+/// Read E2E profile configurations from an ARXML file
+///
+/// # Errors
+/// Returns `ArxmlError` if the file cannot be read or a configuration cannot be converted
+pub fn load_arxml_file(
+    path: impl AsRef<Path>,
+) -> Result<BTreeMap<String, ArxmlProfileConfig>, ArxmlError> {
+    let text = std::fs::read_to_string(path)?;
+    load_arxml(&text)
+}
+
+/// Read E2E profile configurations from ARXML text
+///
+/// # Returns
+/// * `Ok(BTreeMap)` configurations keyed by PDU, I-SIGNAL-GROUP or configuration name
+/// * `Err(ArxmlError)` if the ARXML is malformed or a configuration cannot be converted
+pub fn load_arxml(text: &str) -> Result<BTreeMap<String, ArxmlProfileConfig>, ArxmlError> {
+    let document = Document::parse(text)?;
+    let mut configs = BTreeMap::new();
+
+    // END-TO-END-PROTECTION-SET
+    for protection in elements(&document, "END-TO-END-PROTECTION") {
+        let path = arxml_path(protection);
+        let Some(profile) = child(protection, "END-TO-END-PROFILE") else {
+            return Err(ArxmlError::MissingProfile { path });
+        };
+        let mut parameters = ParameterSet::default();
+        collect_parameters(profile, &path, &mut parameters);
+        let profile_name = child_text(profile, "CATEGORY");
+        let pdus: Vec<Node> = protection
+            .descendants()
+            .filter(|node| node.has_tag_name("END-TO-END-PROTECTION-I-SIGNAL-I-PDU"))
+            .collect();
+        if pdus.is_empty() {
+            let name = short_name(protection).unwrap_or_default().to_string();
+            let config = build_config(&path, profile_name, &parameters)?;
+            insert(&mut configs, name, config)?;
+        }
+        for pdu in pdus {
+            let mut pdu_parameters = ParameterSet {
+                data_ids: parameters.data_ids.clone(),
+                parameters: parameters.parameters.clone(),
+            };
+            collect_parameters(pdu, &path, &mut pdu_parameters);
+            let name = child_text(pdu, "I-SIGNAL-I-PDU-REF")
+                .or_else(|| child_text(pdu, "I-SIGNAL-GROUP-REF"))
+                .and_then(|reference| reference.rsplit('/').next())
+                .or_else(|| short_name(protection))
+                .unwrap_or_default()
+                .to_string();
+            let config = build_config(&path, profile_name, &pdu_parameters)?;
+            insert(&mut configs, name, config)?;
+        }
+    }
+
+    // END-TO-END-TRANSFORMATION-DESCRIPTION referenced by I-SIGNAL(-GROUP) props
+    let technologies: HashMap<String, Node> = elements(&document, "TRANSFORMATION-TECHNOLOGY")
+        .map(|technology| (arxml_path(technology), technology))
+        .collect();
+    for props in elements(&document, "END-TO-END-TRANSFORMATION-I-SIGNAL-PROPS") {
+        let owner = props
+            .ancestors()
+            .find(|node| node.has_tag_name("I-SIGNAL-GROUP") || node.has_tag_name("I-SIGNAL"))
+            .unwrap_or(props);
+        let path = arxml_path(owner);
+        let Some(reference) = props
+            .descendants()
+            .find(|node| node.has_tag_name("TRANSFORMER-REF"))
+            .and_then(|node| node.text())
+            .map(str::trim)
+        else {
+            return Err(ArxmlError::MissingProfile { path });
+        };
+        let Some(description) = technologies.get(reference).and_then(|technology| {
+            technology
+                .descendants()
+                .find(|node| node.has_tag_name("END-TO-END-TRANSFORMATION-DESCRIPTION"))
+        }) else {
+            return Err(ArxmlError::UnresolvedReference {
+                path,
+                reference: reference.to_string(),
+            });
+        };
+        let mut parameters = ParameterSet::default();
+        collect_parameters(description, reference, &mut parameters);
+        // parameters of the props take precedence over those of the description
+        collect_parameters(props, &path, &mut parameters);
+        let name = short_name(owner).unwrap_or_default().to_string();
+        let profile_name = child_text(description, "PROFILE-NAME");
+        let config = build_config(&path, profile_name, &parameters)?;
+        insert(&mut configs, name, config)?;
+    }
+
+    // E2E-PROFILE-CONFIGURATION
+    for configuration in elements(&document, "E2E-PROFILE-CONFIGURATION") {
+        let path = arxml_path(configuration);
+        let mut parameters = ParameterSet::default();
+        collect_parameters(configuration, &path, &mut parameters);
+        let name = short_name(configuration).unwrap_or_default().to_string();
+        let profile_name = child_text(configuration, "PROFILE-NAME");
+        let config = build_config(&path, profile_name, &parameters)?;
+        insert(&mut configs, name, config)?;
+    }
+
+    Ok(configs)
+}
+
+fn elements<'a, 'input>(
+    document: &'a Document<'input>,
+    tag_name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    document
+        .descendants()
+        .filter(move |node| node.has_tag_name(tag_name))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, tag_name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(tag_name))
+}
+
+fn child_text<'a>(node: Node<'a, '_>, tag_name: &str) -> Option<&'a str> {
+    child(node, tag_name)
+        .and_then(|child| child.text())
+        .map(str::trim)
+}
+
+fn short_name<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+    child_text(node, "SHORT-NAME")
+}
+
+/// AUTOSAR path built from the short names of the node and its ancestors
+fn arxml_path(node: Node) -> String {
+    let mut names: Vec<&str> = node.ancestors().filter_map(short_name).collect();
+    names.reverse();
+    format!("/{}", names.join("/"))
+}
+
+/// Collect all leaf elements below `node` as parameters
+fn collect_parameters(node: Node, path: &str, parameters: &mut ParameterSet) {
+    for element in node.children().filter(Node::is_element) {
+        let name = element.tag_name().name();
+        if SKIPPED_ELEMENTS.contains(&name) || STRUCTURAL_ELEMENTS.contains(&name) {
+            continue;
+        }
+        // nested protections and PDUs are handled on their own
+        if name == "END-TO-END-PROTECTION-I-SIGNAL-I-PDUS" {
+            continue;
+        }
+        if element.children().any(|child| child.is_element()) {
+            if name == "DATA-IDS" {
+                // a new list replaces the data IDs of the transformation description
+                parameters.data_ids.clear();
+            }
+            collect_parameters(element, path, parameters);
+            continue;
+        }
+        let parameter = ArxmlParameter {
+            path: path.to_string(),
+            name: name.to_string(),
+            value: element.text().unwrap_or_default().trim().to_string(),
+        };
+        if name == "DATA-ID" {
+            parameters.data_ids.push(parameter);
+        } else {
+            parameters
+                .parameters
+                .retain(|existing| existing.name != name);
+            parameters.parameters.push(parameter);
+        }
+    }
+}
+
+fn insert(
+    configs: &mut BTreeMap<String, ArxmlProfileConfig>,
+    name: String,
+    config: ArxmlProfileConfig,
+) -> Result<(), ArxmlError> {
+    if let Some(existing) = configs.get(&name) {
+        return Err(ArxmlError::DuplicateName {
+            name,
+            first: existing.path.clone(),
+            second: config.path,
+        });
+    }
+    configs.insert(name, config);
+    Ok(())
+}
+
+/// Default configuration of the profile given by an ARXML profile name, e.g. "PROFILE_04m"
+fn default_config(path: &str, profile_name: Option<&str>) -> Result<AnyProfileConfig, ArxmlError> {
+    let Some(profile_name) = profile_name else {
+        return Err(ArxmlError::MissingProfile {
+            path: path.to_string(),
+        });
+    };
+    let id = profile_name
+        .strip_prefix("PROFILE_")
+        .or_else(|| profile_name.strip_prefix("PROFILE-"))
+        .unwrap_or(profile_name)
+        .trim_start_matches('0')
+        .to_ascii_lowercase();
+    Ok(match id.as_str() {
+        "1" => AnyProfileConfig::Profile1(Profile1Config::default()),
+        "2" => AnyProfileConfig::Profile2(Profile2Config::default()),
+        "4" => AnyProfileConfig::Profile4(Profile4Config::default()),
+        "4m" => AnyProfileConfig::Profile4m(Profile4m::default_config()),
+        "44" => AnyProfileConfig::Profile44(Profile44Config::default()),
+        "44m" => AnyProfileConfig::Profile44m(Profile44m::default_config()),
+        "5" => AnyProfileConfig::Profile5(Profile5Config::default()),
+        "6" => AnyProfileConfig::Profile6(Profile6Config::default()),
+        "7" => AnyProfileConfig::Profile7(Profile7Config::default()),
+        "7m" => AnyProfileConfig::Profile7m(Profile7m::default_config()),
+        "8" => AnyProfileConfig::Profile8(Profile8Config::default()),
+        "8m" => AnyProfileConfig::Profile8m(Profile8m::default_config()),
+        "11" => AnyProfileConfig::Profile11(Profile11Config::default()),
+        "22" => AnyProfileConfig::Profile22(Profile22Config::default()),
+        _ => {
+            return Err(ArxmlError::UnsupportedProfile {
+                path: path.to_string(),
+                profile: profile_name.to_string(),
+            })
+        }
+    })
+}
+
+fn invalid_value(parameter: &ArxmlParameter) -> ArxmlError {
+    ArxmlError::InvalidValue {
+        path: parameter.path.clone(),
+        parameter: parameter.name.clone(),
+        value: parameter.value.clone(),
+    }
+}
+
+/// Parse a decimal or hexadecimal (0x) integer into the type of the config field
+fn parse_value<T: TryFrom<u64>>(parameter: &ArxmlParameter) -> Result<T, ArxmlError> {
+    let value = parameter.value.as_str();
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse::<u64>(),
+    };
+    parsed
+        .ok()
+        .and_then(|parsed| T::try_from(parsed).ok())
+        .ok_or_else(|| invalid_value(parameter))
+}
+
+fn parse_id_mode_1(parameter: &ArxmlParameter) -> Result<Profile1IdMode, ArxmlError> {
+    match parameter.value.as_str() {
+        "ALL-16-BIT" => Ok(Profile1IdMode::Both),
+        "ALTERNATING-8-BIT" => Ok(Profile1IdMode::Alt),
+        "LOWER-8-BIT" => Ok(Profile1IdMode::Low),
+        "LOWER-12-BIT" => Ok(Profile1IdMode::Nibble),
+        _ => Err(invalid_value(parameter)),
+    }
+}
+
+fn parse_id_mode_11(parameter: &ArxmlParameter) -> Result<Profile11IdMode, ArxmlError> {
+    match parameter.value.as_str() {
+        "ALL-16-BIT" => Ok(Profile11IdMode::Both),
+        "LOWER-12-BIT" => Ok(Profile11IdMode::Nibble),
+        _ => Err(invalid_value(parameter)),
+    }
+}
+
+/// Apply the data IDs, a list of 16 for Profile 2/22 and a single one otherwise
+fn apply_data_ids(
+    config: &mut AnyProfileConfig,
+    path: &str,
+    data_ids: &[ArxmlParameter],
+) -> Result<(), ArxmlError> {
+    if data_ids.is_empty() {
+        return Ok(());
+    }
+    let expected = match config {
+        AnyProfileConfig::Profile2(_) | AnyProfileConfig::Profile22(_) => DATA_ID_LIST_LENGTH,
+        _ => 1,
+    };
+    if data_ids.len() != expected {
+        return Err(ArxmlError::InvalidValue {
+            path: path.to_string(),
+            parameter: "DATA-IDS".to_string(),
+            value: format!("{} data IDs, expected {}", data_ids.len(), expected),
+        });
+    }
+    let data_id = &data_ids[0];
+    match config {
+        AnyProfileConfig::Profile1(c) => c.data_id = parse_value(data_id)?,
+        AnyProfileConfig::Profile11(c) => c.data_id = parse_value(data_id)?,
+        AnyProfileConfig::Profile2(Profile2Config { data_id_list, .. })
+        | AnyProfileConfig::Profile22(Profile22Config { data_id_list, .. }) => {
+            for (entry, data_id) in data_id_list.iter_mut().zip(data_ids) {
+                *entry = parse_value(data_id)?;
+            }
+        }
+        AnyProfileConfig::Profile4(c) | AnyProfileConfig::Profile4m(c) => {
+            c.data_id = parse_value(data_id)?
+        }
+        AnyProfileConfig::Profile44(c) | AnyProfileConfig::Profile44m(c) => {
+            c.data_id = parse_value(data_id)?
+        }
+        AnyProfileConfig::Profile5(c) => c.data_id = parse_value(data_id)?,
+        AnyProfileConfig::Profile6(c) => c.data_id = parse_value(data_id)?,
+        AnyProfileConfig::Profile7(c) | AnyProfileConfig::Profile7m(c) => {
+            c.data_id = parse_value(data_id)?
+        }
+        AnyProfileConfig::Profile8(c) | AnyProfileConfig::Profile8m(c) => {
+            c.data_id = parse_value(data_id)?
+        }
+    }
+    Ok(())
+}
+
+/// Apply a parameter to the configuration
+///
+/// # Returns
+/// * `Ok(true)` if the parameter was applied
+/// * `Ok(false)` if the configuration has no counterpart for the parameter
+fn apply_parameter(
+    config: &mut AnyProfileConfig,
+    parameter: &ArxmlParameter,
+) -> Result<bool, ArxmlError> {
+    let p = parameter;
+    match (config, p.name.as_str()) {
+        (AnyProfileConfig::Profile1(c), "COUNTER-OFFSET") => c.counter_offset = parse_value(p)?,
+        (AnyProfileConfig::Profile1(c), "CRC-OFFSET") => c.crc_offset = parse_value(p)?,
+        (AnyProfileConfig::Profile1(c), "DATA-ID-MODE") => c.mode = parse_id_mode_1(p)?,
+        (AnyProfileConfig::Profile1(c), "DATA-ID-NIBBLE-OFFSET") => {
+            c.nibble_offset = parse_value(p)?
+        }
+        (AnyProfileConfig::Profile1(c), "MAX-DELTA-COUNTER" | "MAX-DELTA-COUNTER-INIT") => {
+            c.max_delta_counter = parse_value(p)?
+        }
+        (AnyProfileConfig::Profile1(c), "DATA-LENGTH") => c.data_length = parse_value(p)?,
+        (AnyProfileConfig::Profile1(c), "MAX-NO-NEW-OR-REPEATED-DATA") => {
+            c.max_no_new_or_repeated_data = parse_value(p)?
+        }
+        (AnyProfileConfig::Profile1(c), "SYNC-COUNTER-INIT") => c.sync_counter = parse_value(p)?,
+
+        (AnyProfileConfig::Profile11(c), "COUNTER-OFFSET") => c.counter_offset = parse_value(p)?,
+        (AnyProfileConfig::Profile11(c), "CRC-OFFSET") => c.crc_offset = parse_value(p)?,
+        (AnyProfileConfig::Profile11(c), "DATA-ID-MODE") => c.mode = parse_id_mode_11(p)?,
+        (AnyProfileConfig::Profile11(c), "DATA-ID-NIBBLE-OFFSET") => {
+            c.nibble_offset = parse_value(p)?
+        }
+        (AnyProfileConfig::Profile11(c), "MAX-DELTA-COUNTER") => {
+            c.max_delta_counter = parse_value(p)?
+        }
+        (AnyProfileConfig::Profile11(c), "DATA-LENGTH") => c.data_length = parse_value(p)?,
+
+        (AnyProfileConfig::Profile2(c), "DATA-LENGTH") => c.data_length = parse_value(p)?,
+        (AnyProfileConfig::Profile2(c), "MAX-DELTA-COUNTER" | "MAX-DELTA-COUNTER-INIT") => {
+            c.max_delta_counter = parse_value(p)?
+        }
+        (AnyProfileConfig::Profile2(c), "MAX-NO-NEW-OR-REPEATED-DATA") => {
+            c.max_no_new_or_repeated_data = parse_value(p)?
+        }
+        (AnyProfileConfig::Profile2(c), "SYNC-COUNTER-INIT") => c.sync_counter = parse_value(p)?,
+
+        (AnyProfileConfig::Profile22(c), "DATA-LENGTH") => c.data_length = parse_value(p)?,
+        (AnyProfileConfig::Profile22(c), "MAX-DELTA-COUNTER") => {
+            c.max_delta_counter = parse_value(p)?
+        }
+        (AnyProfileConfig::Profile22(c), "OFFSET") => c.offset = parse_value(p)?,
+
+        (AnyProfileConfig::Profile4(c) | AnyProfileConfig::Profile4m(c), name) => match name {
+            "OFFSET" => c.offset = parse_value(p)?,
+            "MIN-DATA-LENGTH" => c.min_data_length = parse_value(p)?,
+            "MAX-DATA-LENGTH" => c.max_data_length = parse_value(p)?,
+            "MAX-DELTA-COUNTER" => c.max_delta_counter = parse_value(p)?,
+            _ => return Ok(false),
+        },
+        (AnyProfileConfig::Profile44(c) | AnyProfileConfig::Profile44m(c), name) => match name {
+            "OFFSET" => c.offset = parse_value(p)?,
+            "MIN-DATA-LENGTH" => c.min_data_length = parse_value(p)?,
+            "MAX-DATA-LENGTH" => c.max_data_length = parse_value(p)?,
+            "MAX-DELTA-COUNTER" => c.max_delta_counter = parse_value(p)?,
+            _ => return Ok(false),
+        },
+        (AnyProfileConfig::Profile5(c), name) => match name {
+            "OFFSET" => c.offset = parse_value(p)?,
+            "DATA-LENGTH" => c.data_length = parse_value(p)?,
+            "MAX-DELTA-COUNTER" => c.max_delta_counter = parse_value(p)?,
+            _ => return Ok(false),
+        },
+        (AnyProfileConfig::Profile6(c), name) => match name {
+            "OFFSET" => c.offset = parse_value(p)?,
+            "MIN-DATA-LENGTH" => c.min_data_length = parse_value(p)?,
+            "MAX-DATA-LENGTH" => c.max_data_length = parse_value(p)?,
+            "MAX-DELTA-COUNTER" => c.max_delta_counter = parse_value(p)?,
+            _ => return Ok(false),
+        },
+        (AnyProfileConfig::Profile7(c) | AnyProfileConfig::Profile7m(c), name) => match name {
+            "OFFSET" => c.offset = parse_value(p)?,
+            "MIN-DATA-LENGTH" => c.min_data_length = parse_value(p)?,
+            "MAX-DATA-LENGTH" => c.max_data_length = parse_value(p)?,
+            "MAX-DELTA-COUNTER" => c.max_delta_counter = parse_value(p)?,
+            _ => return Ok(false),
+        },
+        (AnyProfileConfig::Profile8(c) | AnyProfileConfig::Profile8m(c), name) => match name {
+            "OFFSET" => c.offset = parse_value(p)?,
+            "MIN-DATA-LENGTH" => c.min_data_length = parse_value(p)?,
+            "MAX-DATA-LENGTH" => c.max_data_length = parse_value(p)?,
+            "MAX-DELTA-COUNTER" => c.max_delta_counter = parse_value(p)?,
+            _ => return Ok(false),
+        },
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn build_config(
+    path: &str,
+    profile_name: Option<&str>,
+    parameters: &ParameterSet,
+) -> Result<ArxmlProfileConfig, ArxmlError> {
+    let mut config = default_config(path, profile_name)?;
+    apply_data_ids(&mut config, path, &parameters.data_ids)?;
+    let mut unmapped = Vec::new();
+    for parameter in &parameters.parameters {
+        if !apply_parameter(&mut config, parameter)? {
+            unmapped.push(parameter.clone());
+        }
+    }
+    Ok(ArxmlProfileConfig {
+        path: path.to_string(),
+        config,
+        unmapped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::any::AnyProfile;
+    use crate::{E2EProfile, E2EStatus};
+
+    const ARXML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<AUTOSAR xmlns="http://autosar.org/schema/r4.0">
+  <AR-PACKAGES>
+    <AR-PACKAGE>
+      <SHORT-NAME>E2E</SHORT-NAME>
+      <ELEMENTS>
+        <END-TO-END-PROTECTION-SET>
+          <SHORT-NAME>ProtectionSet</SHORT-NAME>
+          <END-TO-END-PROTECTIONS>
+            <END-TO-END-PROTECTION>
+              <SHORT-NAME>Prot_Brake</SHORT-NAME>
+              <END-TO-END-PROFILE>
+                <CATEGORY>PROFILE_05</CATEGORY>
+                <DATA-IDS>
+                  <DATA-ID>291</DATA-ID>
+                </DATA-IDS>
+                <DATA-LENGTH>64</DATA-LENGTH>
+                <MAX-DELTA-COUNTER>2</MAX-DELTA-COUNTER>
+                <OFFSET>0</OFFSET>
+                <MAX-ERROR-STATE-VALID>1</MAX-ERROR-STATE-VALID>
+              </END-TO-END-PROFILE>
+              <END-TO-END-PROTECTION-I-SIGNAL-I-PDUS>
+                <END-TO-END-PROTECTION-I-SIGNAL-I-PDU>
+                  <DATA-OFFSET>0</DATA-OFFSET>
+                  <I-SIGNAL-GROUP-REF DEST="I-SIGNAL-GROUP">/Signals/SG_Brake</I-SIGNAL-GROUP-REF>
+                  <I-SIGNAL-I-PDU-REF DEST="I-SIGNAL-I-PDU">/Pdus/Pdu_Brake</I-SIGNAL-I-PDU-REF>
+                </END-TO-END-PROTECTION-I-SIGNAL-I-PDU>
+              </END-TO-END-PROTECTION-I-SIGNAL-I-PDUS>
+            </END-TO-END-PROTECTION>
+            <END-TO-END-PROTECTION>
+              <SHORT-NAME>Prot_Legacy</SHORT-NAME>
+              <END-TO-END-PROFILE>
+                <CATEGORY>PROFILE_01</CATEGORY>
+                <COUNTER-OFFSET>8</COUNTER-OFFSET>
+                <CRC-OFFSET>0</CRC-OFFSET>
+                <DATA-ID-MODE>LOWER-12-BIT</DATA-ID-MODE>
+                <DATA-ID-NIBBLE-OFFSET>12</DATA-ID-NIBBLE-OFFSET>
+                <DATA-IDS>
+                  <DATA-ID>0x123</DATA-ID>
+                </DATA-IDS>
+                <DATA-LENGTH>64</DATA-LENGTH>
+                <MAX-DELTA-COUNTER-INIT>1</MAX-DELTA-COUNTER-INIT>
+              </END-TO-END-PROFILE>
+            </END-TO-END-PROTECTION>
+          </END-TO-END-PROTECTIONS>
+        </END-TO-END-PROTECTION-SET>
+        <DATA-TRANSFORMATION-SET>
+          <SHORT-NAME>Transformations</SHORT-NAME>
+          <TRANSFORMATION-TECHNOLOGYS>
+            <TRANSFORMATION-TECHNOLOGY>
+              <SHORT-NAME>E2E_P04</SHORT-NAME>
+              <TRANSFORMER-CLASS>SAFETY</TRANSFORMER-CLASS>
+              <TRANSFORMATION-DESCRIPTIONS>
+                <END-TO-END-TRANSFORMATION-DESCRIPTION>
+                  <PROFILE-NAME>PROFILE_04</PROFILE-NAME>
+                  <MAX-DELTA-COUNTER>3</MAX-DELTA-COUNTER>
+                  <UPPER-HEADER-BITS-TO-SHIFT>0</UPPER-HEADER-BITS-TO-SHIFT>
+                  <WINDOW-SIZE>5</WINDOW-SIZE>
+                </END-TO-END-TRANSFORMATION-DESCRIPTION>
+              </TRANSFORMATION-DESCRIPTIONS>
+            </TRANSFORMATION-TECHNOLOGY>
+          </TRANSFORMATION-TECHNOLOGYS>
+        </DATA-TRANSFORMATION-SET>
+        <I-SIGNAL-GROUP>
+          <SHORT-NAME>SG_Speed</SHORT-NAME>
+          <TRANSFORMATION-I-SIGNAL-PROPSS>
+            <END-TO-END-TRANSFORMATION-I-SIGNAL-PROPS>
+              <END-TO-END-TRANSFORMATION-I-SIGNAL-PROPS-VARIANTS>
+                <END-TO-END-TRANSFORMATION-I-SIGNAL-PROPS-CONDITIONAL>
+                  <TRANSFORMER-REF DEST="TRANSFORMATION-TECHNOLOGY">/E2E/Transformations/E2E_P04</TRANSFORMER-REF>
+                  <DATA-IDS>
+                    <DATA-ID>0x0a0b0c0d</DATA-ID>
+                  </DATA-IDS>
+                  <MAX-DATA-LENGTH>256</MAX-DATA-LENGTH>
+                  <MIN-DATA-LENGTH>128</MIN-DATA-LENGTH>
+                </END-TO-END-TRANSFORMATION-I-SIGNAL-PROPS-CONDITIONAL>
+              </END-TO-END-TRANSFORMATION-I-SIGNAL-PROPS-VARIANTS>
+            </END-TO-END-TRANSFORMATION-I-SIGNAL-PROPS>
+          </TRANSFORMATION-I-SIGNAL-PROPSS>
+        </I-SIGNAL-GROUP>
+      </ELEMENTS>
+    </AR-PACKAGE>
+  </AR-PACKAGES>
+</AUTOSAR>
+"#;
+
+    #[test]
+    fn test_arxml_protection_set() {
+        let configs = load_arxml(ARXML).unwrap();
+        assert_eq!(configs.len(), 3);
+
+        let brake = &configs["Pdu_Brake"];
+        assert_eq!(brake.path, "/E2E/ProtectionSet/Prot_Brake");
+        let AnyProfileConfig::Profile5(config) = &brake.config else {
+            panic!("expected Profile 5");
+        };
+        assert_eq!(config.data_id, 291);
+        assert_eq!(config.data_length, 64);
+        assert_eq!(config.max_delta_counter, 2);
+        let unmapped: Vec<&str> = brake.unmapped.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(unmapped, ["MAX-ERROR-STATE-VALID", "DATA-OFFSET"]);
+
+        let legacy = &configs["Prot_Legacy"];
+        let AnyProfileConfig::Profile1(config) = &legacy.config else {
+            panic!("expected Profile 1");
+        };
+        assert_eq!(config.mode, Profile1IdMode::Nibble);
+        assert_eq!(config.data_id, 0x123);
+        assert!(legacy.unmapped.is_empty());
+
+        // the imported configurations are usable right away
+        for config in configs.values() {
+            AnyProfile::new(config.config.clone()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_arxml_transformation_props() {
+        let configs = load_arxml(ARXML).unwrap();
+        let speed = &configs["SG_Speed"];
+        assert_eq!(speed.path, "/E2E/SG_Speed");
+        let AnyProfileConfig::Profile4(config) = &speed.config else {
+            panic!("expected Profile 4");
+        };
+        assert_eq!(config.data_id, 0x0a0b0c0d);
+        assert_eq!(config.max_delta_counter, 3);
+        assert_eq!(config.min_data_length, 128);
+        assert_eq!(config.max_data_length, 256);
+        let unmapped: Vec<(&str, &str)> = speed
+            .unmapped
+            .iter()
+            .map(|p| (p.path.as_str(), p.name.as_str()))
+            .collect();
+        assert_eq!(
+            unmapped,
+            [
+                ("/E2E/Transformations/E2E_P04", "UPPER-HEADER-BITS-TO-SHIFT"),
+                ("/E2E/Transformations/E2E_P04", "WINDOW-SIZE"),
+            ]
+        );
+
+        let mut profile_tx = AnyProfile::new(speed.config.clone()).unwrap();
+        let mut profile_rx = AnyProfile::new(speed.config.clone()).unwrap();
+        let mut data = vec![0x00; 16];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_arxml_errors() {
+        let unsupported = ARXML.replace("PROFILE_05", "PROFILE_03");
+        assert!(matches!(
+            load_arxml(&unsupported),
+            Err(ArxmlError::UnsupportedProfile { profile, .. }) if profile == "PROFILE_03"
+        ));
+        let invalid = ARXML.replace("<DATA-ID>291</DATA-ID>", "<DATA-ID>0x12345</DATA-ID>");
+        assert!(matches!(
+            load_arxml(&invalid),
+            Err(ArxmlError::InvalidValue { parameter, .. }) if parameter == "DATA-ID"
+        ));
+        let unresolved = ARXML.replace(
+            "/E2E/Transformations/E2E_P04</TRANSFORMER-REF>",
+            "/E2E/Transformations/E2E_P05</TRANSFORMER-REF>",
+        );
+        assert!(matches!(
+            load_arxml(&unresolved),
+            Err(ArxmlError::UnresolvedReference { .. })
+        ));
+        assert!(matches!(load_arxml("<AUTOSAR>"), Err(ArxmlError::Xml(_))));
+    }
+}
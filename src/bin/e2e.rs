@@ -0,0 +1,632 @@
+//! # E2E Command-Line Tool
+//!
+//! Protects, checks and decodes E2E frames given as hex strings.
+//!
+//! ```text
+//! e2e check --profile 4 --data-id 0x0a0b0c0d 001000000a0b0c0d862b055600000000
+//! e2e decode --config p4.json --json < frames.txt
+//! ```
+//!
+//! Frames are taken from the arguments or, if none are given, line by line
+//! from stdin. The profile configuration is read from a JSON file (`--config`)
+//! and/or built from the command-line flags, which take precedence.
+//!
+//! This binary is available with the `cli` feature.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead};
+use std::process::ExitCode;
+
+use autosar_e2e::analysis::{CanAnalyzer, IdStatistics, SomeIpAnalyzer, SomeIpEventConfig};
+use autosar_e2e::any::{AnyProfile, AnyProfileConfig};
+use autosar_e2e::can_log;
+use autosar_e2e::pcap;
+use autosar_e2e::profile44m::Profile44m;
+use autosar_e2e::profile4m::Profile4m;
+use autosar_e2e::profile7m::Profile7m;
+use autosar_e2e::profile8m::Profile8m;
+use autosar_e2e::someip::SomeIpMessageId;
+use autosar_e2e::{E2EHeaderFields, E2EProfile, E2EStatus};
+use clap::{Args, Parser, Subcommand};
+use serde_json::{json, Map, Value};
+
+#[derive(Parser)]
+#[command(
+    name = "e2e",
+    version,
+    about = "Protect, check and decode AUTOSAR E2E frames"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Add E2E protection to the frames, in sequence
+    Protect(CommandArgs),
+    /// Check the frames, in sequence, and print the resulting status
+    Check(CommandArgs),
+    /// Print the header fields of the frames without checking the sequence
+    Decode(CommandArgs),
+    /// Check a candump or Vector ASC log and print statistics per CAN ID
+    Analyze(AnalyzeArgs),
+    /// Check the SOME/IP messages of a pcap or pcapng capture and print
+    /// the verdict of each message and statistics per event
+    AnalyzePcap(AnalyzePcapArgs),
+}
+
+#[derive(Args)]
+struct AnalyzePcapArgs {
+    /// JSON file mapping SOME/IP events to configurations,
+    /// e.g. {"0x1234/0x8001": {"profile": "4", "upper_header_bits_to_shift": 64}}
+    #[arg(short, long)]
+    map: String,
+    /// Print the verdicts and statistics as JSON
+    #[arg(long)]
+    json: bool,
+    /// pcap or pcapng capture file
+    capture: String,
+}
+
+#[derive(Args)]
+struct AnalyzeArgs {
+    /// JSON file mapping CAN IDs to configurations, e.g. {"0x123": {"profile": "5"}}
+    #[arg(short, long)]
+    map: String,
+    /// Print the statistics as JSON
+    #[arg(long)]
+    json: bool,
+    /// candump -l or Vector ASC log file
+    log: String,
+}
+
+#[derive(Args)]
+struct CommandArgs {
+    #[command(flatten)]
+    profile: ProfileArgs,
+    /// Print one JSON object per frame
+    #[arg(long)]
+    json: bool,
+    /// Frames as hex strings; read from stdin if omitted
+    frames: Vec<String>,
+}
+
+#[derive(Args)]
+struct ProfileArgs {
+    /// Profile, e.g. 4, 4m, 11 or Profile22
+    #[arg(short, long)]
+    profile: Option<String>,
+    /// JSON configuration file, e.g. {"profile": "Profile4", "data_id": 1}
+    #[arg(short, long)]
+    config: Option<String>,
+    /// Data ID
+    #[arg(long, value_parser = parse_number::<u64>)]
+    data_id: Option<u64>,
+    /// Bit offset of the E2E header
+    #[arg(long, value_parser = parse_number::<u64>)]
+    offset: Option<u64>,
+    /// Data length in bits, for profiles with a fixed length
+    #[arg(long, value_parser = parse_number::<u64>)]
+    data_length: Option<u64>,
+    /// Minimum data length in bits
+    #[arg(long, value_parser = parse_number::<u64>)]
+    min_data_length: Option<u64>,
+    /// Maximum data length in bits
+    #[arg(long, value_parser = parse_number::<u64>)]
+    max_data_length: Option<u64>,
+    /// Maximum allowed counter delta
+    #[arg(long, value_parser = parse_number::<u64>)]
+    max_delta_counter: Option<u64>,
+    /// Data ID mode of Profile 1/11: Both, Alt, Low or Nibble
+    #[arg(long)]
+    mode: Option<String>,
+    /// Bit offset of the counter (Profile 1/11)
+    #[arg(long, value_parser = parse_number::<u64>)]
+    counter_offset: Option<u64>,
+    /// Bit offset of the CRC (Profile 1/11)
+    #[arg(long, value_parser = parse_number::<u64>)]
+    crc_offset: Option<u64>,
+    /// Bit offset of the Data ID nibble (Profile 1/11)
+    #[arg(long, value_parser = parse_number::<u64>)]
+    nibble_offset: Option<u64>,
+    /// Source ID (M variants)
+    #[arg(long, value_parser = parse_number::<u32>)]
+    source_id: Option<u32>,
+    /// Message type (M variants)
+    #[arg(long, value_parser = parse_number::<u8>)]
+    message_type: Option<u8>,
+    /// Message result (M variants)
+    #[arg(long, value_parser = parse_number::<u8>)]
+    message_result: Option<u8>,
+}
+
+/// Parse a decimal or hexadecimal (0x) number
+fn parse_number<T: TryFrom<u64>>(value: &str) -> Result<T, String> {
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse::<u64>(),
+    }
+    .map_err(|e| e.to_string())?;
+    T::try_from(parsed).map_err(|_| format!("{} is out of range", value))
+}
+
+/// Parse a hex frame, ignoring whitespace, ':' and '-' separators
+fn parse_frame(text: &str) -> Result<Vec<u8>, String> {
+    let text = text
+        .trim()
+        .strip_prefix("0x")
+        .unwrap_or(text.trim())
+        .replace(|c: char| c.is_whitespace() || c == ':' || c == '-', "");
+    if !text.len().is_multiple_of(2) {
+        return Err(format!("odd number of hex digits in '{}'", text));
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&text[i..i + 2], 16)
+                .map_err(|_| format!("invalid hex digits '{}'", &text[i..i + 2]))
+        })
+        .collect()
+}
+
+fn format_frame(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Variant name of `AnyProfileConfig` for a profile given as e.g. "4m" or "Profile4M"
+fn profile_variant(name: &str) -> Result<String, String> {
+    let id = name.to_ascii_lowercase().replace(['_', ' ', '-'], "");
+    let id = id
+        .strip_prefix("profile")
+        .or_else(|| id.strip_prefix('p'))
+        .unwrap_or(&id);
+    match id {
+        "1" | "2" | "4" | "4m" | "44" | "44m" | "5" | "6" | "7" | "7m" | "8" | "8m" | "11"
+        | "22" => Ok(format!("Profile{}", id)),
+        _ => Err(format!("unknown profile '{}'", name)),
+    }
+}
+
+/// Default configuration of a profile given by its `AnyProfileConfig` variant name
+///
+/// The M variants share the configuration type of their base profile, whose
+/// default leaves no room for the metadata fields.
+fn default_config(profile: &str) -> Result<AnyProfileConfig, String> {
+    Ok(match profile {
+        "Profile4m" => AnyProfileConfig::Profile4m(Profile4m::default_config()),
+        "Profile44m" => AnyProfileConfig::Profile44m(Profile44m::default_config()),
+        "Profile7m" => AnyProfileConfig::Profile7m(Profile7m::default_config()),
+        "Profile8m" => AnyProfileConfig::Profile8m(Profile8m::default_config()),
+        _ => serde_json::from_value(json!({ "profile": profile })).map_err(|e| e.to_string())?,
+    })
+}
+
+/// Build the profile configuration from the configuration file and the flags
+fn build_config(args: &ProfileArgs) -> Result<AnyProfileConfig, String> {
+    let mut config = match &args.config {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            match serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))? {
+                Value::Object(config) => config,
+                _ => return Err(format!("{}: expected a JSON object", path)),
+            }
+        }
+        None => Map::new(),
+    };
+    if let Some(profile) = &args.profile {
+        config.insert("profile".into(), Value::String(profile_variant(profile)?));
+    }
+    let Some(Value::String(profile)) = config.get("profile").cloned() else {
+        return Err("no profile given, use --profile or a configuration file".into());
+    };
+    let profile = profile_variant(&profile)?;
+    config.insert("profile".into(), Value::String(profile.clone()));
+
+    // fields of the selected profile, to reject flags that do not apply
+    let default = serde_json::to_value(default_config(&profile)?).map_err(|e| e.to_string())?;
+    let flags = [
+        ("data_id", args.data_id.map(Value::from)),
+        ("offset", args.offset.map(Value::from)),
+        ("data_length", args.data_length.map(Value::from)),
+        ("min_data_length", args.min_data_length.map(Value::from)),
+        ("max_data_length", args.max_data_length.map(Value::from)),
+        ("max_delta_counter", args.max_delta_counter.map(Value::from)),
+        ("mode", args.mode.clone().map(Value::from)),
+        ("counter_offset", args.counter_offset.map(Value::from)),
+        ("crc_offset", args.crc_offset.map(Value::from)),
+        ("nibble_offset", args.nibble_offset.map(Value::from)),
+    ];
+    for (field, value) in flags {
+        let Some(value) = value else {
+            continue;
+        };
+        if default.get(field).is_none() {
+            return Err(format!(
+                "--{} is not supported by {}",
+                field.replace('_', "-"),
+                profile
+            ));
+        }
+        config.insert(field.into(), value);
+    }
+    // fields not given take the default of the profile
+    if let Value::Object(default) = default {
+        for (field, value) in default {
+            config.entry(field).or_insert(value);
+        }
+    }
+    serde_json::from_value(Value::Object(config)).map_err(|e| e.to_string())
+}
+
+fn build_profile(args: &ProfileArgs) -> Result<AnyProfile, String> {
+    let mut profile = AnyProfile::new(build_config(args)?).map_err(|e| e.to_string())?;
+    let metadata =
+        args.source_id.is_some() || args.message_type.is_some() || args.message_result.is_some();
+    macro_rules! set_metadata {
+        ($profile:expr) => {{
+            if let Some(source_id) = args.source_id {
+                $profile.source_id = source_id;
+            }
+            if let Some(message_type) = args.message_type {
+                $profile.message_type = message_type;
+            }
+            if let Some(message_result) = args.message_result {
+                $profile.message_result = message_result;
+            }
+        }};
+    }
+    match &mut profile {
+        AnyProfile::Profile4m(p) => set_metadata!(p),
+        AnyProfile::Profile44m(p) => set_metadata!(p),
+        AnyProfile::Profile7m(p) => set_metadata!(p),
+        AnyProfile::Profile8m(p) => set_metadata!(p),
+        p if metadata => {
+            return Err(format!(
+                "--source-id, --message-type and --message-result are not supported by {}",
+                p.name()
+            ))
+        }
+        _ => {}
+    }
+    Ok(profile)
+}
+
+/// Number of hex digits of the CRC of a profile
+fn crc_digits(profile: &AnyProfile) -> usize {
+    match profile {
+        AnyProfile::Profile1(_)
+        | AnyProfile::Profile2(_)
+        | AnyProfile::Profile11(_)
+        | AnyProfile::Profile22(_) => 2,
+        AnyProfile::Profile5(_) | AnyProfile::Profile6(_) => 4,
+        AnyProfile::Profile7(_) | AnyProfile::Profile7m(_) => 16,
+        _ => 8,
+    }
+}
+
+fn print_frame(
+    profile: &AnyProfile,
+    index: usize,
+    data: &[u8],
+    header: &E2EHeaderFields,
+    status: Option<E2EStatus>,
+    json: bool,
+) {
+    if json {
+        let mut output = json!({
+            "frame": index,
+            "data": format_frame(data),
+            "header": header,
+        });
+        if let Some(status) = status {
+            output["status"] = json!(status);
+        }
+        println!("{}", output);
+        return;
+    }
+    let digits = crc_digits(profile);
+    let mut line = format!(
+        "#{} {} counter={}",
+        index,
+        format_frame(data),
+        header.counter
+    );
+    if let Some(length) = header.length {
+        line += &format!(" length={}", length);
+    }
+    if let Some(data_id) = header.data_id {
+        line += &format!(" data_id={:#x}", data_id);
+    }
+    if let Some(source_id) = header.source_id {
+        line += &format!(" source_id={:#x}", source_id);
+    }
+    if let Some(message_type) = header.message_type {
+        line += &format!(" message_type={}", message_type);
+    }
+    if let Some(message_result) = header.message_result {
+        line += &format!(" message_result={}", message_result);
+    }
+    line += &format!(
+        " crc={:#0w$x} computed_crc={:#0w$x}",
+        header.received_crc,
+        header.computed_crc,
+        w = digits + 2
+    );
+    if let Some(status) = status {
+        line += &format!(" status={:?}", status);
+    }
+    println!("{}", line);
+}
+
+/// Parse a SOME/IP event given as "service/method", e.g. "0x1234/0x8001"
+fn parse_message_id(id: &str) -> Result<SomeIpMessageId, String> {
+    let (service_id, method_id) = id
+        .split_once('/')
+        .ok_or_else(|| format!("expected service/method, found '{}'", id))?;
+    Ok(SomeIpMessageId {
+        service_id: parse_number(service_id)?,
+        method_id: parse_number(method_id)?,
+    })
+}
+
+fn format_message_id(id: &SomeIpMessageId) -> String {
+    format!("{:#06x}/{:#06x}", id.service_id, id.method_id)
+}
+
+/// Read a mapping of identifiers (CAN IDs or SOME/IP events) to configurations
+fn read_map<K: Ord, C: serde::de::DeserializeOwned>(
+    path: &str,
+    parse_id: fn(&str) -> Result<K, String>,
+) -> Result<BTreeMap<K, C>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let Value::Object(map) = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))?
+    else {
+        return Err(format!("{}: expected a JSON object", path));
+    };
+    map.into_iter()
+        .map(|(id, mut config)| {
+            let key = parse_id(&id).map_err(|e| format!("{}: {}: {}", path, id, e))?;
+            if let Some(Value::String(profile)) = config.get("profile") {
+                config["profile"] = Value::String(profile_variant(profile)?);
+            }
+            let config =
+                serde_json::from_value(config).map_err(|e| format!("{}: {}: {}", path, id, e))?;
+            Ok((key, config))
+        })
+        .collect()
+}
+
+fn print_statistics(id: &str, statistics: &IdStatistics) {
+    const STATUSES: [E2EStatus; 12] = [
+        E2EStatus::Ok,
+        E2EStatus::OkSomeLost,
+        E2EStatus::Repeated,
+        E2EStatus::WrongSequence,
+        E2EStatus::Sync,
+        E2EStatus::NoNewData,
+        E2EStatus::CrcError,
+        E2EStatus::DataIdError,
+        E2EStatus::DataLengthError,
+        E2EStatus::SourceIdError,
+        E2EStatus::MessageTypeError,
+        E2EStatus::MessageResultError,
+    ];
+    println!(
+        "{} ({}): {} frames",
+        id, statistics.profile, statistics.frames
+    );
+    for status in STATUSES {
+        let count = statistics.count(status);
+        if count > 0 {
+            println!("  {:?}: {}", status, count);
+        }
+    }
+    if statistics.invalid_frames > 0 {
+        println!("  invalid frames: {}", statistics.invalid_frames);
+    }
+    println!(
+        "  longest WrongSequence run: {}",
+        statistics.longest_wrong_sequence_run
+    );
+    println!(
+        "  counter gaps: {} ({} counters lost)",
+        statistics.counter_gaps, statistics.lost_counters
+    );
+    for failure in &statistics.first_failures {
+        println!("  first {:?} at {:.6}", failure.status, failure.timestamp);
+    }
+    if let Some(timestamp) = statistics.first_invalid_frame {
+        println!("  first invalid frame at {:.6}", timestamp);
+    }
+}
+
+fn analyze(args: &AnalyzeArgs) -> Result<(), String> {
+    let mut analyzer =
+        CanAnalyzer::new(read_map(&args.map, parse_number::<u32>)?).map_err(|e| e.to_string())?;
+    let frames = can_log::read_log_file(&args.log).map_err(|e| format!("{}: {}", args.log, e))?;
+    analyzer.process_all(&frames);
+    let report = analyzer.report();
+    if args.json {
+        let report: BTreeMap<String, &IdStatistics> = report
+            .iter()
+            .map(|(id, statistics)| (format!("{:#x}", id), statistics))
+            .collect();
+        println!("{}", json!(report));
+    } else {
+        for (id, statistics) in &report {
+            print_statistics(&format!("{:#x}", id), statistics);
+        }
+    }
+    Ok(())
+}
+
+fn analyze_pcap(args: &AnalyzePcapArgs) -> Result<(), String> {
+    let configs: BTreeMap<SomeIpMessageId, SomeIpEventConfig> =
+        read_map(&args.map, parse_message_id)?;
+    let mut analyzer = SomeIpAnalyzer::new(configs).map_err(|e| e.to_string())?;
+    let packets =
+        pcap::read_capture_file(&args.capture).map_err(|e| format!("{}: {}", args.capture, e))?;
+    let verdicts = analyzer.process_capture(&packets);
+    let report: BTreeMap<String, IdStatistics> = analyzer
+        .report()
+        .into_iter()
+        .map(|(id, statistics)| (format_message_id(&id), statistics))
+        .collect();
+    if args.json {
+        println!("{}", json!({ "messages": verdicts, "events": report }));
+        return Ok(());
+    }
+    for verdict in &verdicts {
+        let mut line = format!(
+            "{:.6} {} session={}",
+            verdict.timestamp,
+            format_message_id(&verdict.message_id),
+            verdict.session_id
+        );
+        if let Some(header) = &verdict.header {
+            line += &format!(" counter={}", header.counter);
+        }
+        match verdict.status {
+            Some(status) => line += &format!(" status={:?}", status),
+            None => line += " invalid",
+        }
+        println!("{}", line);
+    }
+    for (id, statistics) in &report {
+        print_statistics(id, statistics);
+    }
+    Ok(())
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    let (args, command) = match &cli.command {
+        Command::Protect(args) => (args, "protect"),
+        Command::Check(args) => (args, "check"),
+        Command::Decode(args) => (args, "decode"),
+        Command::Analyze(args) => return analyze(args),
+        Command::AnalyzePcap(args) => return analyze_pcap(args),
+    };
+    let mut profile = build_profile(&args.profile)?;
+    let frames: Vec<String> = if args.frames.is_empty() {
+        io::stdin()
+            .lock()
+            .lines()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|line| !line.trim().is_empty())
+            .collect()
+    } else {
+        args.frames.clone()
+    };
+    for (index, frame) in frames.iter().enumerate() {
+        let mut data = parse_frame(frame).map_err(|e| format!("frame #{}: {}", index, e))?;
+        let status = match command {
+            "protect" => {
+                profile.protect(&mut data).map_err(|e| e.to_string())?;
+                None
+            }
+            "check" => Some(profile.check(&data).map_err(|e| e.to_string())?),
+            _ => None,
+        };
+        let header = profile
+            .decode(&data)
+            .map_err(|e| format!("frame #{}: {}", index, e))?;
+        print_frame(&profile, index, &data, &header, status, args.json);
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile_args(arguments: &[&str]) -> ProfileArgs {
+        let mut command = vec!["e2e", "decode"];
+        command.extend_from_slice(arguments);
+        match Cli::parse_from(command).command {
+            Command::Decode(args) => args.profile,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_frame() {
+        assert_eq!(parse_frame("0x00ff10").unwrap(), vec![0x00, 0xff, 0x10]);
+        assert_eq!(
+            parse_frame("00 ff:10-2A").unwrap(),
+            vec![0x00, 0xff, 0x10, 0x2a]
+        );
+        assert!(parse_frame("0f0").is_err());
+        assert!(parse_frame("zz").is_err());
+    }
+
+    #[test]
+    fn test_profile_variant() {
+        assert_eq!(profile_variant("4m").unwrap(), "Profile4m");
+        assert_eq!(profile_variant("Profile4M").unwrap(), "Profile4m");
+        assert_eq!(profile_variant("P22").unwrap(), "Profile22");
+        assert!(profile_variant("3").is_err());
+    }
+
+    #[test]
+    fn test_build_config_from_flags() {
+        let args = profile_args(&[
+            "--profile",
+            "5",
+            "--data-id",
+            "0x1234",
+            "--data-length",
+            "64",
+        ]);
+        let AnyProfileConfig::Profile5(config) = build_config(&args).unwrap() else {
+            panic!("expected Profile 5");
+        };
+        assert_eq!(config.data_id, 0x1234);
+        assert_eq!(config.data_length, 64);
+
+        // flags of other profiles and invalid values are rejected
+        let args = profile_args(&["--profile", "4", "--mode", "Nibble"]);
+        assert!(build_config(&args).is_err());
+        let args = profile_args(&["--profile", "4", "--max-delta-counter", "0"]);
+        assert!(build_config(&args).is_err());
+        let args = profile_args(&["--profile", "4", "--source-id", "1"]);
+        assert!(build_profile(&args).is_err());
+
+        // the M variants default to a minimum data length holding the metadata
+        for profile in ["4m", "44m", "7m", "8m"] {
+            let args = profile_args(&["--profile", profile, "--source-id", "1"]);
+            assert!(build_profile(&args).is_ok(), "{profile}");
+        }
+        let args = profile_args(&["--profile", "4m", "--min-data-length", "96"]);
+        assert!(build_profile(&args).is_err());
+    }
+
+    #[test]
+    fn test_decode_frame() {
+        let args = profile_args(&["--profile", "4"]);
+        let mut profile = build_profile(&args).unwrap();
+        let mut data = vec![0x00; 16];
+        profile.protect(&mut data).unwrap();
+        assert_eq!(format_frame(&data), "001000000a0b0c0d862b055600000000");
+        let header = profile.decode(&data).unwrap();
+        assert_eq!(header.length, Some(16));
+        assert_eq!(header.data_id, Some(0x0a0b0c0d));
+        assert_eq!(header.received_crc, 0x862b0556);
+        assert_eq!(header.computed_crc, 0x862b0556);
+    }
+}
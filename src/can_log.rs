@@ -0,0 +1,296 @@
+//! # CAN Log Reader
+//!
+//! Reads CAN frames from `candump -l` logs and Vector ASC logs, for the
+//! offline analysis of recorded traffic (see [`crate::analysis`]).
+//!
+//! Supported line formats:
+//! - candump: `(1436509052.249713) can0 123#11223344` and CAN FD
+//!   `(1436509052.249713) can0 123##311223344`
+//! - ASC: `0.010000 1  123x  Rx   d 8 01 02 03 04 05 06 07 08` and CAN FD
+//!   `0.010000 CANFD 1 Rx 123 1 0 a 16 01 02 ...`
+//!
+//! Remote frames, error frames, comments and other events are skipped.
+
+use std::path::Path;
+
+use thiserror::Error;
+
+/// CAN frame read from a log
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CanFrame {
+    /// Timestamp in seconds
+    pub timestamp: f64,
+    /// Channel, e.g. "can0" or "1"
+    pub channel: String,
+    /// CAN identifier, without the extended frame flag
+    pub id: u32,
+    /// Whether the identifier is a 29-bit extended identifier
+    pub extended: bool,
+    /// Payload
+    pub data: Vec<u8>,
+}
+
+/// Format of a CAN log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanLogFormat {
+    /// `candump -l` log file
+    Candump,
+    /// Vector ASC log file
+    Asc,
+}
+
+/// CAN log error types
+#[derive(Debug, Error)]
+pub enum CanLogError {
+    /// The log file could not be read
+    #[error("Failed to read CAN log: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A line looks like a frame but cannot be parsed
+    #[error("Line {line}: invalid CAN frame '{text}'")]
+    InvalidFrame { line: usize, text: String },
+}
+
+/// Detect the format of a CAN log from its first non-empty line
+pub fn detect_format(text: &str) -> CanLogFormat {
+    match text.lines().map(str::trim).find(|line| !line.is_empty()) {
+        Some(line) if line.starts_with('(') => CanLogFormat::Candump,
+        _ => CanLogFormat::Asc,
+    }
+}
+
+/// Read all frames of a CAN log, detecting its format
+pub fn read_log(text: &str) -> Result<Vec<CanFrame>, CanLogError> {
+    match detect_format(text) {
+        CanLogFormat::Candump => read_candump(text),
+        CanLogFormat::Asc => read_asc(text),
+    }
+}
+
+/// Read all frames of a CAN log file, detecting its format
+pub fn read_log_file<P: AsRef<Path>>(path: P) -> Result<Vec<CanFrame>, CanLogError> {
+    read_log(&std::fs::read_to_string(path)?)
+}
+
+/// Read all frames of a `candump -l` log
+pub fn read_candump(text: &str) -> Result<Vec<CanFrame>, CanLogError> {
+    let mut frames = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let invalid = || CanLogError::InvalidFrame {
+            line: index + 1,
+            text: line.to_string(),
+        };
+        if let Some(frame) = parse_candump_line(line).ok_or_else(invalid)? {
+            frames.push(frame);
+        }
+    }
+    Ok(frames)
+}
+
+/// Parse a candump line, `Some(None)` for remote and error frames
+fn parse_candump_line(line: &str) -> Option<Option<CanFrame>> {
+    let mut fields = line.split_whitespace();
+    let timestamp = fields
+        .next()?
+        .strip_prefix('(')?
+        .strip_suffix(')')?
+        .parse::<f64>()
+        .ok()?;
+    let channel = fields.next()?.to_string();
+    let (id, payload) = fields.next()?.split_once('#')?;
+    let payload = match payload.strip_prefix('#') {
+        // CAN FD, the first digit holds the flags
+        Some(payload) => payload.get(1..)?,
+        None if payload.starts_with('R') => return Some(None),
+        None => payload,
+    };
+    let raw_id = u32::from_str_radix(id, 16).ok()?;
+    // error frames have the CAN_ERR_FLAG set in the identifier
+    if raw_id & 0x2000_0000 != 0 {
+        return Some(None);
+    }
+    Some(Some(CanFrame {
+        timestamp,
+        channel,
+        id: raw_id & 0x1fff_ffff,
+        extended: id.len() == 8,
+        data: parse_hex(payload)?,
+    }))
+}
+
+/// Read all frames of a Vector ASC log
+pub fn read_asc(text: &str) -> Result<Vec<CanFrame>, CanLogError> {
+    let mut frames = Vec::new();
+    let mut radix = 16;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let mut fields = line.split_whitespace();
+        let Some(first) = fields.next() else {
+            continue;
+        };
+        if first == "base" {
+            if let Some(base) = fields.next() {
+                radix = if base == "dec" { 10 } else { 16 };
+            }
+            continue;
+        }
+        // header, trigger blocks, comments and other events
+        let Ok(timestamp) = first.parse::<f64>() else {
+            continue;
+        };
+        let invalid = || CanLogError::InvalidFrame {
+            line: index + 1,
+            text: line.to_string(),
+        };
+        let fields: Vec<&str> = fields.collect();
+        let frame = match fields.first() {
+            Some(&"CANFD") => parse_asc_fd(timestamp, &fields[1..], radix),
+            Some(_) => parse_asc_classic(timestamp, &fields, radix),
+            None => None,
+        };
+        if let Some(frame) = frame.ok_or_else(invalid)? {
+            frames.push(frame);
+        }
+    }
+    Ok(frames)
+}
+
+/// Parse an ASC identifier, "x" marks an extended identifier
+fn parse_asc_id(id: &str, radix: u32) -> Option<(u32, bool)> {
+    match id.strip_suffix(['x', 'X']) {
+        Some(id) => Some((u32::from_str_radix(id, radix).ok()?, true)),
+        None => Some((u32::from_str_radix(id, radix).ok()?, false)),
+    }
+}
+
+/// Parse the fields after the timestamp of a classic CAN ASC event
+/// (`<channel> <id> <dir> d <dlc> <data..>`)
+fn parse_asc_classic(timestamp: f64, fields: &[&str], radix: u32) -> Option<Option<CanFrame>> {
+    let channel = fields.first()?;
+    // only numbered channels carry CAN frames, other events are skipped
+    if channel.parse::<u32>().is_err() {
+        return Some(None);
+    }
+    let id = fields.get(1)?;
+    if id.eq_ignore_ascii_case("ErrorFrame") {
+        return Some(None);
+    }
+    let Some((id, extended)) = parse_asc_id(id, radix) else {
+        // statistics and other channel events
+        return Some(None);
+    };
+    match *fields.get(3)? {
+        "d" | "D" => {}
+        "r" | "R" => return Some(None),
+        _ => return None,
+    }
+    let length = usize::from_str_radix(fields.get(4)?, 16).ok()?;
+    Some(Some(CanFrame {
+        timestamp,
+        channel: channel.to_string(),
+        id,
+        extended,
+        data: parse_bytes(fields.get(5..5 + length)?)?,
+    }))
+}
+
+/// Parse the fields after "CANFD" of a CAN FD ASC event
+/// (`<channel> <dir> <id> <brs> <esi> <dlc> <length> <data..>`)
+fn parse_asc_fd(timestamp: f64, fields: &[&str], radix: u32) -> Option<Option<CanFrame>> {
+    let channel = fields.first()?;
+    let id = fields.get(2)?;
+    if id.eq_ignore_ascii_case("ErrorFrame") {
+        return Some(None);
+    }
+    let (id, extended) = parse_asc_id(id, radix)?;
+    let length = fields.get(6)?.parse::<usize>().ok()?;
+    Some(Some(CanFrame {
+        timestamp,
+        channel: channel.to_string(),
+        id,
+        extended,
+        data: parse_bytes(fields.get(7..7 + length)?)?,
+    }))
+}
+
+fn parse_bytes(fields: &[&str]) -> Option<Vec<u8>> {
+    fields
+        .iter()
+        .map(|byte| u8::from_str_radix(byte, 16).ok())
+        .collect()
+}
+
+fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_candump() {
+        let log = "(1436509052.249713) can0 044#2A366C2BBA\n\
+                   (1436509052.449847) can0 12345678#R\n\
+                   (1436509052.650004) can1 18DAF110##10102030405060708090A0B0C\n\
+                   (1436509052.850000) can0 20000004#0000000000000000\n";
+        assert_eq!(detect_format(log), CanLogFormat::Candump);
+        let frames = read_log(log).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].timestamp, 1436509052.249713);
+        assert_eq!(frames[0].channel, "can0");
+        assert_eq!(frames[0].id, 0x044);
+        assert!(!frames[0].extended);
+        assert_eq!(frames[0].data, vec![0x2a, 0x36, 0x6c, 0x2b, 0xba]);
+        assert_eq!(frames[1].id, 0x18daf110);
+        assert!(frames[1].extended);
+        assert_eq!(frames[1].data.len(), 12);
+
+        assert!(matches!(
+            read_log("(0.1) can0 123#123"),
+            Err(CanLogError::InvalidFrame { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_read_asc() {
+        let log = "date Fri Jul 10 08:17:32 am 2015\n\
+                   base hex  timestamps absolute\n\
+                   internal events logged\n\
+                   // version 8.5.0\n\
+                   Begin Triggerblock Fri Jul 10 08:17:32 am 2015\n\
+                   \x20  0.000000 Start of measurement\n\
+                   \x20  0.010000 1  123             Rx   d 3 01 02 03\n\
+                   \x20  0.020000 1  1ABCDEFx        Tx   d 2 AA BB  Length = 0 BitCount = 0\n\
+                   \x20  0.030000 1  ErrorFrame\n\
+                   \x20  0.040000 2  200             Rx   r\n\
+                   \x20  0.050000 CANFD   1 Rx        321  1 0 9 12 00 01 02 03 04 05 06 07 08 09 0a 0b  0 0 1000 0 0 0 0 0\n\
+                   End TriggerBlock\n";
+        assert_eq!(detect_format(log), CanLogFormat::Asc);
+        let frames = read_log(log).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].timestamp, 0.01);
+        assert_eq!(frames[0].channel, "1");
+        assert_eq!(frames[0].id, 0x123);
+        assert_eq!(frames[0].data, vec![0x01, 0x02, 0x03]);
+        assert_eq!(frames[1].id, 0x1abcdef);
+        assert!(frames[1].extended);
+        assert_eq!(frames[2].id, 0x321);
+        assert_eq!(frames[2].data.len(), 12);
+
+        // decimal identifiers
+        let frames = read_asc("base dec timestamps absolute\n0.1 1 291 Rx d 1 ff\n").unwrap();
+        assert_eq!(frames[0].id, 0x123);
+    }
+}
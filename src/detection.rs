@@ -0,0 +1,541 @@
+//! # Profile Auto-Detection
+//!
+//! Proposes the profile configuration of frames with unknown protection.
+//! Every supported profile is tried at every byte-aligned header offset that
+//! fits the frames, and each candidate is scored by:
+//! - CRC consistency: frames whose received CRC matches the computed CRC
+//! - Counter monotonicity: consecutive frames whose counter advances by 1 to 3
+//! - Length agreement: frames whose length field matches their length, for
+//!   profiles with a length field
+//!
+//! Data IDs that are not transmitted are recovered with [`crate::recovery`]
+//! from the first frames. For Profile 2 and 22 only frames whose counter value
+//! occurs at least twice count as CRC consistent, as a single frame matches
+//! some Data ID list entry by chance.
+//!
+//! The M variants compute the CRC of their base profile and are reported as
+//! the base profile. Profile 1 and 11 compute the same CRC for the same
+//! layout and mode, so their frames cannot be told apart, and a Nibble mode
+//! sender also matches Both mode; on equal scores Profile 11 and the modes
+//! transmitting a part of the Data ID are proposed first. Where several Data IDs remain (see
+//! [`crate::recovery`]), the lowest one is proposed.
+//!
+//! Detection needs a few frames in sending order; with less than four frames
+//! the Data IDs of 8-bit CRC profiles are not reliable.
+
+use std::fmt;
+
+use crate::any::{AnyProfile, AnyProfileConfig};
+use crate::profile1::{Profile1Config, Profile1IdMode};
+use crate::profile11::{Profile11Config, Profile11IdMode};
+use crate::profile2::Profile2Config;
+use crate::profile22::Profile22Config;
+use crate::profile4::Profile4Config;
+use crate::profile44::Profile44Config;
+use crate::profile5::Profile5Config;
+use crate::profile6::Profile6Config;
+use crate::profile7::Profile7Config;
+use crate::profile8::Profile8Config;
+use crate::recovery::{recover_data_id, DataIdRecovery};
+use crate::E2EProfile;
+
+// Constants
+const BITS_PER_BYTE: usize = 8;
+const FIT_FRAMES: usize = 4;
+const MAX_COUNTER_STEP: u32 = 3;
+const DATA_ID_NUMBER: usize = 16;
+const MAX_NIBBLE_PROFILE_LENGTH: usize = 30;
+
+/// Proposed configuration with its score
+#[derive(Debug, Clone)]
+pub struct DetectionCandidate {
+    /// Proposed configuration
+    pub config: AnyProfileConfig,
+    /// Number of frames evaluated
+    pub frames: usize,
+    /// Frames with a matching CRC
+    pub crc_matches: usize,
+    /// Consecutive frame pairs whose counter advances by 1 to 3
+    pub counter_steps: usize,
+    /// Frames whose length field matches their length, `None` for profiles
+    /// without a length field
+    pub length_matches: Option<usize>,
+    /// Counter values whose Data ID list entry was not recovered (Profile 2/22)
+    pub unknown_data_id_list_entries: Vec<usize>,
+}
+
+impl DetectionCandidate {
+    /// Score between 0 (no evidence) and 1 (all checks agree for all frames)
+    pub fn score(&self) -> f64 {
+        let pairs = self.frames.saturating_sub(1);
+        let mut checks = self.frames + pairs;
+        let mut agreeing = self.crc_matches + self.counter_steps;
+        if let Some(length_matches) = self.length_matches {
+            checks += self.frames;
+            agreeing += length_matches;
+        }
+        if checks == 0 {
+            return 0.0;
+        }
+        agreeing as f64 / checks as f64
+    }
+}
+
+impl fmt::Display for DetectionCandidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |list: &[u8; DATA_ID_NUMBER]| {
+            let entries: Vec<String> = list
+                .iter()
+                .enumerate()
+                .map(|(counter, id)| {
+                    if self.unknown_data_id_list_entries.contains(&counter) {
+                        "??".to_string()
+                    } else {
+                        format!("{:#04x}", id)
+                    }
+                })
+                .collect();
+            format!("[{}]", entries.join(", "))
+        };
+        match &self.config {
+            AnyProfileConfig::Profile1(c) => write!(
+                f,
+                "Profile 1 {:?}, crc_offset {}, counter_offset {}, nibble_offset {}, data_id {:#06x}",
+                c.mode, c.crc_offset, c.counter_offset, c.nibble_offset, c.data_id
+            ),
+            AnyProfileConfig::Profile11(c) => write!(
+                f,
+                "Profile 11 {:?}, crc_offset {}, counter_offset {}, nibble_offset {}, data_id {:#06x}",
+                c.mode, c.crc_offset, c.counter_offset, c.nibble_offset, c.data_id
+            ),
+            AnyProfileConfig::Profile2(c) => {
+                write!(f, "Profile 2, data_id_list {}", list(&c.data_id_list))
+            }
+            AnyProfileConfig::Profile22(c) => write!(
+                f,
+                "Profile 22, offset {}, data_id_list {}",
+                c.offset,
+                list(&c.data_id_list)
+            ),
+            AnyProfileConfig::Profile5(c) => {
+                write!(f, "Profile 5, offset {}, data_id {:#06x}", c.offset, c.data_id)
+            }
+            AnyProfileConfig::Profile6(c) => {
+                write!(f, "Profile 6, offset {}, data_id {:#06x}", c.offset, c.data_id)
+            }
+            AnyProfileConfig::Profile4(c) | AnyProfileConfig::Profile4m(c) => {
+                write!(f, "Profile 4, offset {}, data_id {:#010x}", c.offset, c.data_id)
+            }
+            AnyProfileConfig::Profile44(c) | AnyProfileConfig::Profile44m(c) => {
+                write!(f, "Profile 44, offset {}, data_id {:#010x}", c.offset, c.data_id)
+            }
+            AnyProfileConfig::Profile7(c) | AnyProfileConfig::Profile7m(c) => {
+                write!(f, "Profile 7, offset {}, data_id {:#010x}", c.offset, c.data_id)
+            }
+            AnyProfileConfig::Profile8(c) | AnyProfileConfig::Profile8m(c) => {
+                write!(f, "Profile 8, offset {}, data_id {:#010x}", c.offset, c.data_id)
+            }
+        }
+    }
+}
+
+/// Checked header fields of one frame
+struct FrameResult {
+    counter: u32,
+    crc_match: bool,
+    length_match: bool,
+}
+
+/// Decode all frames with the given configuration, `None` for frames that do
+/// not fit it
+fn decode_frames(config: &AnyProfileConfig, frames: &[&[u8]]) -> Option<Vec<Option<FrameResult>>> {
+    let profile = AnyProfile::new(config.clone()).ok()?;
+    Some(
+        frames
+            .iter()
+            .map(|frame| {
+                let header = profile.decode(frame).ok()?;
+                Some(FrameResult {
+                    counter: header.counter,
+                    crc_match: header.received_crc == header.computed_crc,
+                    length_match: header.length == Some(frame.len() as u32),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Number of consecutive frame pairs whose counter advances by 1 to 3
+fn counter_steps(config: &AnyProfileConfig, results: &[Option<FrameResult>]) -> usize {
+    let Ok(profile) = AnyProfile::new(config.clone()) else {
+        return 0;
+    };
+    let modulus = u64::from(profile.max_counter()) + 1;
+    results
+        .windows(2)
+        .filter(|pair| match (&pair[0], &pair[1]) {
+            (Some(previous), Some(next)) => {
+                let delta =
+                    (u64::from(next.counter) + modulus - u64::from(previous.counter)) % modulus;
+                (1..=u64::from(MAX_COUNTER_STEP)).contains(&delta)
+            }
+            _ => false,
+        })
+        .count()
+}
+
+/// Whether the counters of the frames advance like those of a sender
+///
+/// Checked before the Data ID search, which is by far the slowest step.
+fn counter_plausible(config: &AnyProfileConfig, frames: &[&[u8]]) -> bool {
+    let Some(results) = decode_frames(config, frames) else {
+        return false;
+    };
+    let pairs = frames.len().saturating_sub(1);
+    counter_steps(config, &results) * 4 >= pairs * 3
+}
+
+/// Score a configuration whose Data IDs are known
+fn evaluate(
+    config: AnyProfileConfig,
+    frames: &[&[u8]],
+    has_length_field: bool,
+) -> Option<DetectionCandidate> {
+    let results = decode_frames(&config, frames)?;
+    let counter_steps = counter_steps(&config, &results);
+    let results: Vec<&FrameResult> = results.iter().flatten().collect();
+    Some(DetectionCandidate {
+        frames: frames.len(),
+        crc_matches: results.iter().filter(|r| r.crc_match).count(),
+        counter_steps,
+        length_matches: has_length_field.then(|| results.iter().filter(|r| r.length_match).count()),
+        unknown_data_id_list_entries: Vec::new(),
+        config,
+    })
+}
+
+/// Recover the 16-bit Data ID from the first frames and score the configuration
+fn evaluate_16bit(
+    config: AnyProfileConfig,
+    frames: &[&[u8]],
+    has_length_field: bool,
+    set_data_id: fn(&mut AnyProfileConfig, u16),
+) -> Option<DetectionCandidate> {
+    if !counter_plausible(&config, frames) {
+        return None;
+    }
+    let fit = &frames[..frames.len().min(FIT_FRAMES)];
+    let Ok(DataIdRecovery::DataId(candidates)) = recover_data_id(&config, fit) else {
+        return None;
+    };
+    let mut config = config;
+    set_data_id(&mut config, *candidates.first()? as u16);
+    evaluate(config, frames, has_length_field)
+}
+
+/// Recover the Data ID list and score the configuration
+fn evaluate_data_id_list(
+    config: AnyProfileConfig,
+    frames: &[&[u8]],
+    set_list: fn(&mut AnyProfileConfig) -> &mut [u8; DATA_ID_NUMBER],
+) -> Option<DetectionCandidate> {
+    if !counter_plausible(&config, frames) {
+        return None;
+    }
+    let Ok(DataIdRecovery::DataIdList(list)) = recover_data_id(&config, frames) else {
+        return None;
+    };
+    let mut config = config;
+    let mut unknown = Vec::new();
+    for (counter, entry) in list.iter().enumerate() {
+        match entry.as_ref().and_then(|candidates| candidates.first()) {
+            Some(&data_id) => set_list(&mut config)[counter] = data_id,
+            None => unknown.push(counter),
+        }
+    }
+    let results = decode_frames(&config, frames)?;
+    // a single frame per counter value matches by chance
+    let mut occurrences = [0usize; DATA_ID_NUMBER];
+    for result in results.iter().flatten() {
+        occurrences[result.counter as usize % DATA_ID_NUMBER] += 1;
+    }
+    let mut candidate = evaluate(config, frames, false)?;
+    candidate.crc_matches = results
+        .iter()
+        .flatten()
+        .filter(|r| r.crc_match && occurrences[r.counter as usize % DATA_ID_NUMBER] >= 2)
+        .count();
+    candidate.unknown_data_id_list_entries = unknown;
+    Some(candidate)
+}
+
+/// Read the transmitted Data ID from the first frame with a matching CRC and
+/// score the configuration
+fn evaluate_transmitted(
+    config: AnyProfileConfig,
+    frames: &[&[u8]],
+    set_data_id: fn(&mut AnyProfileConfig, u32),
+) -> Option<DetectionCandidate> {
+    let profile = AnyProfile::new(config.clone()).ok()?;
+    let data_id = frames
+        .iter()
+        .filter_map(|frame| profile.decode(frame).ok())
+        .find(|header| header.received_crc == header.computed_crc)?
+        .data_id?;
+    let mut config = config;
+    set_data_id(&mut config, data_id);
+    evaluate(config, frames, true)
+}
+
+/// Propose profile configurations for frames of unknown protection
+///
+/// # Arguments
+/// * `frames` - Frames of one sender, in sending order
+///
+/// # Returns
+/// Candidates with at least one CRC match, best score first
+pub fn detect_profile(frames: &[&[u8]]) -> Vec<DetectionCandidate> {
+    let Some(length) = frames.iter().map(|frame| frame.len()).min() else {
+        return Vec::new();
+    };
+    let fixed_bits = frames[0].len() * BITS_PER_BYTE;
+    let mut candidates = Vec::new();
+
+    // Profile 11 and 1, CRC byte followed by the counter nibble. Their
+    // candidates tie, so the more constrained modes are listed first.
+    if frames[0].len() <= MAX_NIBBLE_PROFILE_LENGTH {
+        for byte in 0..length.saturating_sub(1) {
+            let (crc_offset, counter_offset, nibble_offset) = (
+                (byte * 8) as u8,
+                (byte * 8 + 8) as u8,
+                (byte * 8 + 12) as u8,
+            );
+            for mode in [Profile11IdMode::Nibble, Profile11IdMode::Both] {
+                let config = AnyProfileConfig::Profile11(Profile11Config {
+                    crc_offset,
+                    counter_offset,
+                    nibble_offset,
+                    mode,
+                    data_id: 0,
+                    data_length: fixed_bits as u8,
+                    ..Default::default()
+                });
+                candidates.extend(evaluate_16bit(config, frames, false, |c, id| {
+                    if let AnyProfileConfig::Profile11(c) = c {
+                        c.data_id = id;
+                    }
+                }));
+            }
+            for mode in [
+                Profile1IdMode::Nibble,
+                Profile1IdMode::Both,
+                Profile1IdMode::Alt,
+                Profile1IdMode::Low,
+            ] {
+                let config = AnyProfileConfig::Profile1(Profile1Config {
+                    crc_offset,
+                    counter_offset,
+                    nibble_offset,
+                    mode,
+                    data_id: 0,
+                    data_length: fixed_bits as u8,
+                    ..Default::default()
+                });
+                candidates.extend(evaluate_16bit(config, frames, false, |c, id| {
+                    if let AnyProfileConfig::Profile1(c) = c {
+                        c.data_id = id;
+                    }
+                }));
+            }
+        }
+    }
+
+    // Profile 2, header at the start of the data
+    let config = AnyProfileConfig::Profile2(Profile2Config {
+        data_length: fixed_bits,
+        ..Default::default()
+    });
+    candidates.extend(evaluate_data_id_list(config, frames, |c| match c {
+        AnyProfileConfig::Profile2(c) => &mut c.data_id_list,
+        _ => unreachable!(),
+    }));
+
+    for byte in 0..length {
+        let offset = byte * BITS_PER_BYTE;
+        if byte + 2 <= length {
+            let config = AnyProfileConfig::Profile22(Profile22Config {
+                data_length: fixed_bits,
+                offset,
+                ..Default::default()
+            });
+            candidates.extend(evaluate_data_id_list(config, frames, |c| match c {
+                AnyProfileConfig::Profile22(c) => &mut c.data_id_list,
+                _ => unreachable!(),
+            }));
+        }
+        if byte + 3 <= length {
+            let config = AnyProfileConfig::Profile5(Profile5Config {
+                data_length: fixed_bits as u16,
+                offset: offset as u16,
+                data_id: 0,
+                ..Default::default()
+            });
+            candidates.extend(evaluate_16bit(config, frames, false, |c, id| {
+                if let AnyProfileConfig::Profile5(c) = c {
+                    c.data_id = id;
+                }
+            }));
+        }
+        if byte + 5 <= length {
+            let config = AnyProfileConfig::Profile6(Profile6Config {
+                offset: offset as u16,
+                min_data_length: (offset + 40) as u16,
+                data_id: 0,
+                ..Default::default()
+            });
+            candidates.extend(evaluate_16bit(config, frames, true, |c, id| {
+                if let AnyProfileConfig::Profile6(c) = c {
+                    c.data_id = id;
+                }
+            }));
+        }
+        if byte + 12 <= length {
+            let config = AnyProfileConfig::Profile4(Profile4Config {
+                offset: offset as u16,
+                min_data_length: (offset + 96) as u16,
+                ..Default::default()
+            });
+            candidates.extend(evaluate_transmitted(config, frames, |c, id| {
+                if let AnyProfileConfig::Profile4(c) = c {
+                    c.data_id = id;
+                }
+            }));
+        }
+        if byte + 14 <= length {
+            let config = AnyProfileConfig::Profile44(Profile44Config {
+                offset: offset as u32,
+                min_data_length: (offset + 112) as u32,
+                ..Default::default()
+            });
+            candidates.extend(evaluate_transmitted(config, frames, |c, id| {
+                if let AnyProfileConfig::Profile44(c) = c {
+                    c.data_id = id;
+                }
+            }));
+        }
+        if byte + 20 <= length {
+            let config = AnyProfileConfig::Profile7(Profile7Config {
+                offset: offset as u32,
+                min_data_length: (offset + 160) as u32,
+                ..Default::default()
+            });
+            candidates.extend(evaluate_transmitted(config, frames, |c, id| {
+                if let AnyProfileConfig::Profile7(c) = c {
+                    c.data_id = id;
+                }
+            }));
+        }
+        if byte + 16 <= length {
+            let config = AnyProfileConfig::Profile8(Profile8Config {
+                offset: offset as u32,
+                min_data_length: (offset + 128) as u32,
+                ..Default::default()
+            });
+            candidates.extend(evaluate_transmitted(config, frames, |c, id| {
+                if let AnyProfileConfig::Profile8(c) = c {
+                    c.data_id = id;
+                }
+            }));
+        }
+    }
+
+    candidates.retain(|candidate| candidate.crc_matches > 0);
+    candidates.sort_by(|a, b| b.score().total_cmp(&a.score()));
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frames protected by a sender with the given configuration
+    fn frames(config: AnyProfileConfig, length: usize, count: u8) -> Vec<Vec<u8>> {
+        let mut sender = AnyProfile::new(config).unwrap();
+        (0..count)
+            .map(|i| {
+                let mut data: Vec<u8> = (0..length as u32)
+                    .map(|b| ((b * 31 + u32::from(i)).wrapping_mul(2654435761) >> 24) as u8)
+                    .collect();
+                sender.protect(&mut data).unwrap();
+                data
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_profile11() {
+        let config = AnyProfileConfig::Profile11(Profile11Config {
+            crc_offset: 16,
+            counter_offset: 24,
+            nibble_offset: 28,
+            data_id: 0x0123,
+            ..Default::default()
+        });
+        let frames = frames(config, 8, 10);
+        let frames: Vec<&[u8]> = frames.iter().map(Vec::as_slice).collect();
+        let candidates = detect_profile(&frames);
+        let best = &candidates[0];
+        assert_eq!(best.score(), 1.0);
+        let AnyProfileConfig::Profile11(config) = &best.config else {
+            panic!("expected Profile 11, found {}", best);
+        };
+        assert_eq!(config.mode, Profile11IdMode::Nibble);
+        assert_eq!(config.crc_offset, 16);
+        assert_eq!(config.counter_offset, 24);
+        assert_eq!(config.data_id & 0x0f00, 0x0100);
+        assert!(best.to_string().starts_with(
+            "Profile 11 Nibble, crc_offset 16, counter_offset 24, nibble_offset 28, data_id 0x01"
+        ));
+    }
+
+    #[test]
+    fn test_detect_profile4() {
+        let config = AnyProfileConfig::Profile4(Profile4Config {
+            data_id: 0x11223344,
+            offset: 32,
+            min_data_length: 128,
+            ..Default::default()
+        });
+        let frames = frames(config, 16, 10);
+        let frames: Vec<&[u8]> = frames.iter().map(Vec::as_slice).collect();
+        let candidates = detect_profile(&frames);
+        assert_eq!(candidates[0].score(), 1.0);
+        assert_eq!(candidates[0].length_matches, Some(10));
+        assert_eq!(
+            candidates[0].to_string(),
+            "Profile 4, offset 32, data_id 0x11223344"
+        );
+    }
+
+    #[test]
+    fn test_detect_profile22() {
+        let config = AnyProfileConfig::Profile22(Profile22Config {
+            offset: 8,
+            ..Default::default()
+        });
+        let frames = frames(config, 8, 20);
+        let frames: Vec<&[u8]> = frames.iter().map(Vec::as_slice).collect();
+        let candidates = detect_profile(&frames);
+        let best = &candidates[0];
+        let AnyProfileConfig::Profile22(config) = &best.config else {
+            panic!("expected Profile 22, found {}", best);
+        };
+        assert_eq!(config.offset, 8);
+        // counter values 0..=3 are received twice, the others once
+        assert_eq!(best.crc_matches, 8);
+        assert_eq!(best.counter_steps, 19);
+        assert_eq!(config.data_id_list[..4], [0x01, 0x02, 0x03, 0x04]);
+        assert!(best.unknown_data_id_list_entries.is_empty());
+        assert!(detect_profile(&[]).is_empty());
+    }
+}
@@ -39,25 +39,88 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! ## `no_std`
+//!
+//! The profiles, the state machine and the transformer use neither `std` nor
+//! `alloc`. With the default `std` feature disabled the crate is `#![no_std]`;
+//! the capture analysis modules, ARXML import, serde support and the
+//! command-line tool require `std`.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+use core::fmt;
 
 use thiserror::Error;
 
+/// Implement `Serialize` and `Deserialize` for a configuration derived with
+/// `serde(remote = "Self")`, validating it on deserialization
+#[cfg(feature = "serde")]
+macro_rules! impl_validated_serde {
+    ($config:ty, $validate:path) => {
+        impl serde::Serialize for $config {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                <$config>::serialize(self, serializer)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $config {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let config = <$config>::deserialize(deserializer)?;
+                $validate(&config).map_err(serde::de::Error::custom)?;
+                Ok(config)
+            }
+        }
+    };
+}
+
 mod profiles;
+pub use profiles::profile1;
 pub use profiles::profile11;
+pub use profiles::profile2;
 pub use profiles::profile22;
 pub use profiles::profile4;
+pub use profiles::profile44;
+pub use profiles::profile44m;
 pub use profiles::profile4m;
+pub use profiles::profile4x;
 pub use profiles::profile5;
 pub use profiles::profile6;
 pub use profiles::profile7;
+pub use profiles::profile78m;
 pub use profiles::profile7m;
 pub use profiles::profile8;
+pub use profiles::profile8m;
+#[cfg(feature = "std")]
+pub mod analysis;
+pub mod any;
+#[cfg(feature = "arxml")]
+pub mod arxml;
+#[cfg(feature = "std")]
+pub mod can_log;
+#[cfg(feature = "std")]
+pub mod detection;
+#[cfg(feature = "std")]
+pub mod pcap;
+#[cfg(feature = "std")]
+pub mod recovery;
+pub mod sm;
+mod snapshot;
+pub use snapshot::E2ESnapshot;
+#[cfg(feature = "std")]
+pub mod someip;
+pub mod transformer;
 
 /// Result type for E2E operations
 pub type E2EResult<T> = Result<T, E2EError>;
 
 /// E2E Protection status enumeration
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// New statuses may be added in minor releases, so matches outside this
+/// crate need a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum E2EStatus {
     /// The checks of data in this cycle is successful
     Ok,
@@ -79,27 +142,522 @@ pub enum E2EStatus {
     MessageTypeError,
     /// Message Result check failed
     MessageResultError,
+    /// Counter check passed, but the receiver is still resynchronizing
+    Sync,
+    /// No new data has been received in this cycle
+    NoNewData,
+}
+
+/// Receiver behaviour at startup and after a loss of synchronization
+///
+/// The policy is part of the configuration of every profile. The default
+/// evaluates the first message against the initial counter 0, accepting a
+/// repetition of it; Profiles 1 and 2 accept any first message as specified.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct E2ESyncPolicy {
+    /// Accept the first message after startup or a resynchronization with any
+    /// counter
+    pub accept_any_first: bool,
+    /// Consecutive messages with a valid counter required after startup or a
+    /// counter discontinuity before `Ok` is reported again, `Sync` is reported
+    /// until then. 0 and 1 report `Ok` immediately.
+    pub min_consecutive: u8,
+    /// Consecutive `WrongSequence` results after which the receiver
+    /// resynchronizes as at startup, 0 to never resynchronize
+    pub resync_after_errors: u8,
+}
+
+impl E2ESyncPolicy {
+    /// Policy accepting any first message, e.g. for receivers joining a
+    /// running communication
+    pub fn accept_any_first() -> Self {
+        Self {
+            accept_any_first: true,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn to_bytes(self) -> [u8; 3] {
+        [
+            self.accept_any_first.into(),
+            self.min_consecutive,
+            self.resync_after_errors,
+        ]
+    }
+}
+
+/// Header fields of protected data, as read by a profile
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct E2EHeaderFields {
+    /// Sequence counter
+    pub counter: u32,
+    /// Length field, for profiles that transmit the data length
+    pub length: Option<u32>,
+    /// Data ID field, for profiles that transmit the Data ID or a part of it
+    pub data_id: Option<u32>,
+    /// Source ID, for M variants
+    pub source_id: Option<u32>,
+    /// Message type, for M variants
+    pub message_type: Option<u8>,
+    /// Message result, for M variants
+    pub message_result: Option<u8>,
+    /// CRC as received
+    pub received_crc: u64,
+    /// CRC computed over the received data
+    pub computed_crc: u64,
+}
+
+/// Individual check of a profile, in the order the profiles evaluate them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum E2ECheckItem {
+    /// CRC comparison
+    Crc,
+    /// Data ID comparison
+    DataId,
+    /// Comparison of the length field with the data length
+    DataLength,
+    /// Counter delta evaluation
+    Counter,
+    /// Source ID comparison, for M variants
+    SourceId,
+    /// Message result comparison, for M variants
+    MessageResult,
+    /// Message type comparison, for M variants
+    MessageType,
+}
+
+impl E2ECheckItem {
+    /// Check that failed with the given status, `None` if no check failed
+    fn from_status(status: E2EStatus) -> Option<Self> {
+        match status {
+            E2EStatus::CrcError => Some(E2ECheckItem::Crc),
+            E2EStatus::DataIdError => Some(E2ECheckItem::DataId),
+            E2EStatus::DataLengthError => Some(E2ECheckItem::DataLength),
+            E2EStatus::Repeated | E2EStatus::WrongSequence => Some(E2ECheckItem::Counter),
+            E2EStatus::SourceIdError => Some(E2ECheckItem::SourceId),
+            E2EStatus::MessageResultError => Some(E2ECheckItem::MessageResult),
+            E2EStatus::MessageTypeError => Some(E2ECheckItem::MessageType),
+            E2EStatus::Ok | E2EStatus::OkSomeLost | E2EStatus::Sync | E2EStatus::NoNewData => None,
+        }
+    }
+}
+
+/// Result of a check together with the decoded header, e.g. for diagnostics
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct E2ECheckReport {
+    /// Check result
+    pub status: E2EStatus,
+    /// Header fields as received, with the computed CRC
+    pub header: E2EHeaderFields,
+    /// Counter the received counter was compared with
+    pub previous_counter: u32,
+    /// Difference between the received and the previous counter, modulo the
+    /// counter range
+    pub counter_delta: u32,
+    /// First check that failed, `None` if all checks passed
+    pub failed_check: Option<E2ECheckItem>,
+}
+
+impl E2ECheckReport {
+    pub(crate) fn new(
+        status: E2EStatus,
+        header: E2EHeaderFields,
+        previous_counter: u32,
+        counter_modulo: u64,
+    ) -> Self {
+        let counter_delta = ((u64::from(header.counter) + counter_modulo
+            - u64::from(previous_counter))
+            % counter_modulo) as u32;
+        Self {
+            status,
+            header,
+            previous_counter,
+            counter_delta,
+            failed_check: E2ECheckItem::from_status(status),
+        }
+    }
+
+    /// Replace the status, e.g. after the additional checks of an M variant
+    pub(crate) fn with_status(self, status: E2EStatus) -> Self {
+        Self {
+            status,
+            failed_check: E2ECheckItem::from_status(status),
+            ..self
+        }
+    }
+}
+
+/// Offset field of a profile or transformer configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffsetField {
+    /// Offset of the counter
+    Counter,
+    /// Offset of the CRC
+    Crc,
+    /// Offset of the Data ID nibble
+    Nibble,
+    /// Offset of the E2E header
+    Header,
+}
+
+impl fmt::Display for OffsetField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OffsetField::Counter => "Counter",
+            OffsetField::Crc => "Crc",
+            OffsetField::Nibble => "Nibble",
+            OffsetField::Header => "Header",
+        })
+    }
+}
+
+/// Allowed range of a length or offset, displayed as "8", "8 - 16" or
+/// "at least 8" (unbounded if the maximum is `usize::MAX`)
+struct Bounds(usize, usize);
+
+impl fmt::Display for Bounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Bounds(min, max) if min == max => write!(f, "{}", min),
+            Bounds(min, usize::MAX) => write!(f, "at least {}", min),
+            Bounds(min, max) => write!(f, "{} - {}", min, max),
+        }
+    }
+}
+
+/// Allowed range of a data length in bytes, displayed as "between 12B and
+/// 4096B" or "larger than 14B" (unbounded if the maximum is `usize::MAX`)
+struct ByteBounds(usize, usize);
+
+impl fmt::Display for ByteBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ByteBounds(min, usize::MAX) => write!(f, "larger than {}B", min),
+            ByteBounds(min, max) => write!(f, "between {}B and {}B", min, max),
+        }
+    }
+}
+
+/// Allowed range of the maximum data length in bytes, bounded below by the
+/// minimum data length
+struct MaxByteBound(usize);
+
+impl fmt::Display for MaxByteBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            MaxByteBound(usize::MAX) => write!(f, "larger than MinDataLength"),
+            MaxByteBound(max) => write!(f, "between MinDataLength and {}B", max),
+        }
+    }
+}
+
+/// Required alignment of an offset, worded as in the Data ID nibble checks
+/// for `OffsetField::Nibble`
+struct Alignment(OffsetField, usize);
+
+impl fmt::Display for Alignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Alignment(OffsetField::Nibble, alignment) => {
+                write!(f, "Nibble offset must be a multiple of {} bits", alignment)
+            }
+            Alignment(field, alignment) => {
+                write!(f, "{} offset shall be a multiple of {}", field, alignment)
+            }
+        }
+    }
+}
+
+/// Allowed range of a Data ID, displayed with as many hex digits as the
+/// largest Data ID, e.g. "0x000 and 0xFFF"
+struct DataIdRange(u32);
+
+impl fmt::Display for DataIdRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = (u32::BITS - self.0.leading_zeros()).div_ceil(4).max(1) as usize;
+        write!(f, "{:#0width$X} and {:#X}", 0, self.0, width = digits + 2)
+    }
 }
 
 /// E2E Error types
+///
+/// The variants carry the offending values instead of a message, so that
+/// errors on the receive path never allocate. New variants may be added in
+/// minor releases, so matches outside this crate need a wildcard arm.
 #[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum E2EError {
-    /// Invalid configuration provided
-    #[error("Invalid configuration: {0}")]
-    InvalidConfiguration(String),
+    /// Configured data length outside the range supported by the profile
+    #[error("Invalid configuration: Data length shall be {}", ByteBounds(*min, *max))]
+    DataLengthOutOfRange {
+        /// Minimum data length in bytes
+        min: usize,
+        /// Maximum data length in bytes, `usize::MAX` if unbounded
+        max: usize,
+        /// Configured data length in bits
+        actual: usize,
+    },
+
+    /// Configured data length beyond the maximum of the profile (Profiles 1 and 11)
+    #[error("Invalid configuration: Maximum data length for {profile} is {max} bits")]
+    MaxDataLengthExceeded {
+        /// Name of the profile
+        profile: &'static str,
+        /// Maximum data length in bits
+        max: usize,
+        /// Configured data length in bits
+        actual: usize,
+    },
+
+    /// Configured minimum data length outside the range supported by the profile
+    #[error(
+        "Invalid configuration: Minimum Data length shall be {}",
+        ByteBounds(*min, *max)
+    )]
+    MinDataLengthOutOfRange {
+        /// Smallest allowed minimum data length in bytes
+        min: usize,
+        /// Largest allowed minimum data length in bytes, `usize::MAX` if unbounded
+        max: usize,
+        /// Configured minimum data length in bits
+        actual: usize,
+    },
+
+    /// Configured maximum data length below the minimum data length or
+    /// beyond the range supported by the profile
+    #[error("Invalid configuration: Maximum Data length shall be {}", MaxByteBound(*max))]
+    MaxDataLengthOutOfRange {
+        /// Configured minimum data length in bytes
+        min: usize,
+        /// Largest allowed maximum data length in bytes, `usize::MAX` if unbounded
+        max: usize,
+        /// Configured maximum data length in bits
+        actual: usize,
+    },
+
+    /// Configured data length not a multiple of 8 bits
+    #[error("Invalid configuration: Data length shall be a multiple of 8")]
+    UnalignedDataLength,
+
+    /// Configured offset not aligned as required by the profile
+    #[error("Invalid configuration: {}", Alignment(*field, *alignment))]
+    UnalignedOffset {
+        /// Misaligned offset
+        field: OffsetField,
+        /// Required alignment in bits
+        alignment: usize,
+    },
+
+    /// Configured header offset leaving no room for the header within the
+    /// data length
+    #[error("Invalid configuration: Offset shall be between 0 and data length - {header_length}B")]
+    OffsetBeyondHeader {
+        /// Configured offset in bits
+        offset: usize,
+        /// Length of the header in bytes
+        header_length: usize,
+    },
+
+    /// Configured offset outside the data
+    #[error(
+        "Invalid configuration: Offset shall be {} bits, got {offset} bits",
+        Bounds(*min, *max)
+    )]
+    OffsetOutOfBounds {
+        /// Configured offset in bits
+        offset: usize,
+        /// Minimum offset in bits
+        min: usize,
+        /// Maximum offset in bits
+        max: usize,
+    },
+
+    /// Max delta counter of 0 or beyond the counter range
+    #[error("Invalid configuration: Max delta counter must be between 1 and {max}")]
+    MaxDeltaCounterInvalid {
+        /// Largest allowed max delta counter
+        max: u32,
+    },
+
+    /// Max no new or repeated data beyond its range (Profile 2)
+    #[error("Invalid configuration: Max no new or repeated data must be between 0 and {max}")]
+    MaxNoNewOrRepeatedDataInvalid {
+        /// Largest allowed value
+        max: u32,
+    },
+
+    /// Sync counter beyond its range (Profile 2)
+    #[error("Invalid configuration: Sync counter must be between 0 and {max}")]
+    SyncCounterInvalid {
+        /// Largest allowed value
+        max: u32,
+    },
+
+    /// Data ID not allowed by the Data ID mode (Profile 1)
+    #[error(
+        "Invalid configuration: Data ID shall be within {} in {mode} mode",
+        DataIdRange(*max)
+    )]
+    DataIdOutOfRange {
+        /// Data ID mode
+        mode: &'static str,
+        /// Largest allowed Data ID
+        max: u32,
+        /// Configured Data ID
+        actual: u32,
+    },
+
+    /// Transformer header offset in front of the shifted upper header bits
+    #[error(
+        "Invalid configuration: Header offset shall not be smaller than the upper header bits to shift"
+    )]
+    HeaderOffsetBeforeUpperHeader {
+        /// Configured header offset in bits
+        offset: usize,
+        /// Configured upper header bits to shift
+        upper_header_bits: usize,
+    },
+
+    /// Transformer header length of 0 or not a multiple of 8 bits
+    #[error("Invalid configuration: Header length shall be a non-zero multiple of 8")]
+    HeaderLengthInvalid,
+
+    /// Upper header bits to shift not a multiple of 8 or beyond the SOME/IP header
+    #[error(
+        "Invalid configuration: Upper header bits to shift shall be a multiple of 8 and at most {max}"
+    )]
+    UpperHeaderBitsInvalid {
+        /// Largest allowed number of bits
+        max: usize,
+    },
+
+    /// Transformer header placement differing from the wrapped profile
+    #[error(
+        "Invalid configuration: Header shall be {expected_length} bits at offset {expected_offset}, got {actual_length} bits at offset {actual_offset}"
+    )]
+    HeaderMismatch {
+        /// Header offset of the profile in bits
+        expected_offset: usize,
+        /// Header length of the profile in bits
+        expected_length: usize,
+        /// Configured header offset in bits
+        actual_offset: usize,
+        /// Configured header length in bits
+        actual_length: usize,
+    },
+
+    /// State machine window size of 0
+    #[error("Invalid configuration: Window size shall be larger than 0")]
+    WindowSizeInvalid,
 
-    /// Invalid data format
-    #[error("Invalid data format: {0}")]
-    InvalidDataFormat(String),
+    /// State machine min OK threshold of 0
+    #[error("Invalid configuration: Min OK thresholds shall be larger than 0")]
+    MinOkThresholdInvalid,
+
+    /// State machine thresholds larger than the window size
+    #[error(
+        "Invalid configuration: Min OK and max error thresholds shall not exceed the window size"
+    )]
+    ThresholdExceedsWindowSize,
+
+    /// Forwarding requested from a profile without forwarding support
+    #[error("Invalid configuration: {profile} does not support forwarding")]
+    ForwardingNotSupported {
+        /// Name of the profile
+        profile: &'static str,
+    },
+
+    /// Data length outside the range accepted by the configuration
+    #[error(
+        "Invalid data format: Expected {} bytes, got {actual} bytes",
+        Bounds(*expected_min, *expected_max)
+    )]
+    LengthOutOfRange {
+        /// Minimum data length in bytes
+        expected_min: usize,
+        /// Maximum data length in bytes, `usize::MAX` if unbounded
+        expected_max: usize,
+        /// Received data length in bytes
+        actual: usize,
+    },
+
+    /// Output buffer too small for the transformed data
+    #[error(
+        "Invalid data format: Output buffer too small: expected at least {expected_min} bytes, got {actual} bytes"
+    )]
+    BufferTooSmall {
+        /// Required buffer length in bytes
+        expected_min: usize,
+        /// Given buffer length in bytes
+        actual: usize,
+    },
+
+    /// No frames given where at least one is required
+    #[error("Invalid data format: At least one frame is required to recover the Data ID")]
+    MissingFrames,
+
+    /// Status that cannot be reproduced when forwarding data
+    #[error("Invalid forward status: {0:?}")]
+    InvalidForwardStatus(E2EStatus),
+
+    /// Snapshot with an invalid length, CRC or state
+    #[error("Invalid snapshot: Corrupted data or state out of range")]
+    SnapshotInvalid,
+
+    /// Snapshot written in an unsupported format version
+    #[error("Invalid snapshot: Unsupported format version {version}")]
+    SnapshotVersionUnsupported {
+        /// Format version of the snapshot
+        version: u8,
+    },
+
+    /// Snapshot taken from another profile or configuration
+    #[error(
+        "Invalid snapshot: Configuration fingerprint {actual:#010X} does not match {expected:#010X}"
+    )]
+    SnapshotConfigMismatch {
+        /// Fingerprint of the restored profile
+        expected: u32,
+        /// Fingerprint stored in the snapshot
+        actual: u32,
+    },
+
+    /// Profile implementation without snapshot support
+    #[error("Invalid snapshot: Not supported by the profile")]
+    SnapshotUnsupported,
+}
+
+impl E2EError {
+    /// Whether the error is caused by an invalid configuration rather than by
+    /// the data
+    pub fn is_configuration_error(&self) -> bool {
+        !matches!(
+            self,
+            E2EError::LengthOutOfRange { .. }
+                | E2EError::BufferTooSmall { .. }
+                | E2EError::MissingFrames
+                | E2EError::InvalidForwardStatus(_)
+                | E2EError::SnapshotInvalid
+                | E2EError::SnapshotVersionUnsupported { .. }
+                | E2EError::SnapshotConfigMismatch { .. }
+                | E2EError::SnapshotUnsupported
+        )
+    }
 }
 
 // Main trait for E2E Profile implementations
 ///
 /// This trait defines the common interface that all E2E profiles must implement.
-/// Each profile provides three main operations:
+/// Each profile provides two main operations:
 /// - `protect`: Add E2E protection to data
 /// - `check`: Verify E2E protection on received data
-/// - `forward`: Forward protected data (Profile 11 specific)
+///
+/// Profiles that support forwarding additionally implement [`E2EForward`].
 pub trait E2EProfile {
     /// Configuration type for this profile
     type Config;
@@ -107,7 +665,7 @@ pub trait E2EProfile {
     /// Create a new instance with the given configuration
     ///
     /// # Errors
-    /// Returns `E2EError::InvalidConfiguration` if the configuration is invalid
+    /// Returns an `E2EError` if the configuration is invalid
     fn new(config: Self::Config) -> E2EResult<Self>
     where
         Self: Sized;
@@ -141,6 +699,95 @@ pub trait E2EProfile {
     /// * `Ok(E2EStatus)` indicating the check result
     /// * `Err(E2EError)` if an error occurred during checking
     fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus>;
+
+    /// Check cycle in which no new data has been received
+    ///
+    /// This corresponds to calling `E2E_PXXCheck` without a data buffer.
+    /// The last valid counter is kept, so the counter delta of the next
+    /// received data is still evaluated against it. Profiles 1 and 2 widen the
+    /// accepted counter delta and count the cycle as missing data.
+    ///
+    /// # Returns
+    /// * `Ok(E2EStatus::NoNewData)` indicating the check result
+    /// * `Err(E2EError)` if an error occurred during checking
+    fn check_no_data(&mut self) -> E2EResult<E2EStatus> {
+        Ok(E2EStatus::NoNewData)
+    }
+
+    /// Reset the sender state like `E2E_PXXProtectInit`
+    ///
+    /// The next protected data carries the same counter as the first data of
+    /// a new instance. The configuration and, for M variants, the metadata
+    /// fields are kept. The default implementation keeps the state, for
+    /// implementations without a sender state.
+    fn protect_init(&mut self) {}
+
+    /// Reset the receiver state like `E2E_PXXCheckInit`
+    ///
+    /// The receiver then evaluates the next data like a new instance,
+    /// including the `E2ESyncPolicy` for the first message. The configuration
+    /// and, for M variants, the expected metadata fields are kept. The default
+    /// implementation keeps the state, for implementations without a receiver
+    /// state.
+    fn check_init(&mut self) {}
+
+    /// Capture the counter state, e.g. to store it in NVM before a reset
+    ///
+    /// The snapshot holds the counter, the receiver state and the metadata
+    /// of the M variants, together with a fingerprint of the configuration.
+    /// The default implementation returns an empty snapshot, which the
+    /// default `restore` rejects.
+    fn snapshot(&self) -> E2ESnapshot {
+        E2ESnapshot::default()
+    }
+
+    /// Continue from a counter state captured with `snapshot`
+    ///
+    /// # Errors
+    /// Returns `E2EError::SnapshotConfigMismatch` if the snapshot was taken
+    /// from another profile or configuration, and `E2EError::SnapshotInvalid`
+    /// if its state is out of range. The default implementation returns
+    /// `E2EError::SnapshotUnsupported`.
+    fn restore(&mut self, _snapshot: &E2ESnapshot) -> E2EResult<()> {
+        Err(E2EError::SnapshotUnsupported)
+    }
+}
+
+/// Trait for E2E profiles supporting the forward operation (E2E_PXXForward)
+///
+/// A gateway uses `forward` instead of `protect` to re-protect received data
+/// while preserving the status the data had on the incoming side, so that a
+/// downstream receiver detects the same status.
+pub trait E2EForward: E2EProfile {
+    /// Add E2E protection to the given data buffer, replicating the given status
+    ///
+    /// - `Ok`/`OkSomeLost`: the data is protected as with `protect`
+    /// - `Repeated`: the counter of the previous cycle is sent again
+    /// - `WrongSequence`: the counter jumps beyond the allowed delta, which is
+    ///   impossible if the receiver accepts every counter delta
+    /// - Any error status: the CRC is inverted after protection
+    ///
+    /// # Arguments
+    /// * `data` - Mutable reference to the data buffer to protect
+    /// * `status` - Status to be replicated towards the receiver
+    ///
+    /// # Returns
+    /// * `Ok(())` if protection was successfully added
+    /// * `Err(E2EError)` if an error occurred or the status cannot be forwarded
+    fn forward(&mut self, data: &mut [u8], status: E2EStatus) -> E2EResult<()>;
+}
+
+/// Trait for E2E profiles exposing the placement of their E2E header
+///
+/// Used by the `E2ETransformer` to verify that the header it inserts and
+/// strips is the one written by the profile. For Profiles 1 and 11 the
+/// header spans the bytes holding the CRC, the counter and the Data ID nibble.
+pub trait E2EHeaderLayout {
+    /// Bit offset of the E2E header in the protected data
+    fn header_offset(&self) -> usize;
+
+    /// Length of the E2E header in bits
+    fn header_length(&self) -> usize;
 }
 
 #[cfg(test)]
@@ -152,4 +799,354 @@ mod tests {
         assert_eq!(E2EStatus::Ok, E2EStatus::Ok);
         assert_ne!(E2EStatus::Ok, E2EStatus::CrcError);
     }
+
+    #[test]
+    fn test_e2e_error_display() {
+        let config = profile4::Profile4Config {
+            max_delta_counter: 0,
+            ..Default::default()
+        };
+        let error = profile4::Profile4::new(config).err().unwrap();
+        assert_eq!(error, E2EError::MaxDeltaCounterInvalid { max: 0xFFFF });
+        assert!(error.is_configuration_error());
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration: Max delta counter must be between 1 and 65535"
+        );
+
+        let mut profile = profile4::Profile4::new(Default::default()).unwrap();
+        let error = profile.check(&[0; 4]).unwrap_err();
+        assert_eq!(
+            error,
+            E2EError::LengthOutOfRange {
+                expected_min: 12,
+                expected_max: 4096,
+                actual: 4
+            }
+        );
+        assert!(!error.is_configuration_error());
+        assert_eq!(
+            error.to_string(),
+            "Invalid data format: Expected 12 - 4096 bytes, got 4 bytes"
+        );
+
+        let error = E2EError::LengthOutOfRange {
+            expected_min: 8,
+            expected_max: 8,
+            actual: 7,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Invalid data format: Expected 8 bytes, got 7 bytes"
+        );
+        let error = E2EError::OffsetOutOfBounds {
+            offset: 32,
+            min: 64,
+            max: usize::MAX,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration: Offset shall be at least 64 bits, got 32 bits"
+        );
+    }
+
+    /// Implementation outside this crate, providing only the required methods
+    struct Passthrough;
+
+    impl E2EProfile for Passthrough {
+        type Config = ();
+
+        fn new(_config: Self::Config) -> E2EResult<Self> {
+            Ok(Self)
+        }
+
+        fn protect(&mut self, _data: &mut [u8]) -> E2EResult<()> {
+            Ok(())
+        }
+
+        fn check(&mut self, _data: &[u8]) -> E2EResult<E2EStatus> {
+            Ok(E2EStatus::Ok)
+        }
+    }
+
+    #[test]
+    fn test_e2e_profile_default_methods() {
+        let mut profile = Passthrough::new(()).unwrap();
+        profile.protect_init();
+        profile.check_init();
+        assert_eq!(profile.check_no_data(), Ok(E2EStatus::NoNewData));
+        let snapshot = profile.snapshot();
+        assert_eq!(snapshot, E2ESnapshot::default());
+        assert_eq!(
+            profile.restore(&snapshot),
+            Err(E2EError::SnapshotUnsupported)
+        );
+        assert!(!E2EError::SnapshotUnsupported.is_configuration_error());
+        // the empty snapshot does not match any profile of this crate
+        let mut profile4 = profile4::Profile4::new(Default::default()).unwrap();
+        assert!(matches!(
+            profile4.restore(&snapshot),
+            Err(E2EError::SnapshotConfigMismatch { actual: 0, .. })
+        ));
+    }
+
+    fn config_error<P: E2EProfile>(config: P::Config) -> String {
+        P::new(config).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_e2e_error_display_baseline() {
+        use profile1::{Profile1, Profile1Config, Profile1IdMode};
+        use profile11::{Profile11, Profile11Config, Profile11IdMode};
+        use profile2::{Profile2, Profile2Config};
+        use profile4::{Profile4, Profile4Config};
+        use profile44::{Profile44, Profile44Config};
+        use profile5::{Profile5, Profile5Config};
+        use profile6::{Profile6, Profile6Config};
+        use profile7::{Profile7, Profile7Config};
+        use profile8::{Profile8, Profile8Config};
+        use transformer::{E2ETransformer, E2ETransformerConfig};
+
+        // MinDataLengthOutOfRange
+        let cases = [
+            (
+                config_error::<Profile4>(Profile4Config {
+                    min_data_length: 88,
+                    ..Default::default()
+                }),
+                "12B and 4096B",
+            ),
+            (
+                config_error::<Profile5>(Profile5Config {
+                    data_length: 16,
+                    ..Default::default()
+                }),
+                "3B and 4096B",
+            ),
+            (
+                config_error::<Profile6>(Profile6Config {
+                    min_data_length: 32,
+                    ..Default::default()
+                }),
+                "5B and 4096B",
+            ),
+        ];
+        for (error, bounds) in cases {
+            assert_eq!(
+                error,
+                format!("Invalid configuration: Minimum Data length shall be between {bounds}")
+            );
+        }
+        let cases = [
+            (
+                config_error::<Profile44>(Profile44Config {
+                    min_data_length: 104,
+                    ..Default::default()
+                }),
+                "14B",
+            ),
+            (
+                config_error::<Profile7>(Profile7Config {
+                    min_data_length: 152,
+                    ..Default::default()
+                }),
+                "20B",
+            ),
+            (
+                config_error::<Profile8>(Profile8Config {
+                    min_data_length: 120,
+                    ..Default::default()
+                }),
+                "16B",
+            ),
+        ];
+        for (error, min) in cases {
+            assert_eq!(
+                error,
+                format!("Invalid configuration: Minimum Data length shall be larger than {min}")
+            );
+        }
+
+        // MaxDataLengthOutOfRange
+        for error in [
+            config_error::<Profile4>(Profile4Config {
+                max_data_length: 4097 * 8,
+                ..Default::default()
+            }),
+            config_error::<Profile6>(Profile6Config {
+                max_data_length: 32,
+                ..Default::default()
+            }),
+        ] {
+            assert_eq!(
+                error,
+                "Invalid configuration: Maximum Data length shall be between MinDataLength and 4096B"
+            );
+        }
+        for error in [
+            config_error::<Profile44>(Profile44Config {
+                max_data_length: 104,
+                ..Default::default()
+            }),
+            config_error::<Profile7>(Profile7Config {
+                max_data_length: 152,
+                ..Default::default()
+            }),
+            config_error::<Profile8>(Profile8Config {
+                max_data_length: 120,
+                ..Default::default()
+            }),
+        ] {
+            assert_eq!(
+                error,
+                "Invalid configuration: Maximum Data length shall be larger than MinDataLength"
+            );
+        }
+
+        // DataLengthOutOfRange
+        assert_eq!(
+            config_error::<Profile2>(Profile2Config {
+                data_length: 8,
+                ..Default::default()
+            }),
+            "Invalid configuration: Data length shall be between 2B and 256B"
+        );
+
+        // MaxDataLengthExceeded
+        assert_eq!(
+            config_error::<Profile1>(Profile1Config {
+                data_length: 248,
+                ..Default::default()
+            }),
+            "Invalid configuration: Maximum data length for Profile 1 is 240 bits"
+        );
+        assert_eq!(
+            config_error::<Profile11>(Profile11Config {
+                data_length: 248,
+                ..Default::default()
+            }),
+            "Invalid configuration: Maximum data length for Profile 11 is 240 bits"
+        );
+
+        // UnalignedDataLength
+        assert_eq!(
+            config_error::<Profile11>(Profile11Config {
+                data_length: 36,
+                ..Default::default()
+            }),
+            "Invalid configuration: Data length shall be a multiple of 8"
+        );
+
+        // UnalignedOffset
+        assert_eq!(
+            config_error::<Profile11>(Profile11Config {
+                counter_offset: 10,
+                ..Default::default()
+            }),
+            "Invalid configuration: Counter offset shall be a multiple of 4"
+        );
+        assert_eq!(
+            config_error::<Profile11>(Profile11Config {
+                crc_offset: 4,
+                ..Default::default()
+            }),
+            "Invalid configuration: Crc offset shall be a multiple of 8"
+        );
+        assert_eq!(
+            config_error::<Profile11>(Profile11Config {
+                mode: Profile11IdMode::Nibble,
+                nibble_offset: 14,
+                ..Default::default()
+            }),
+            "Invalid configuration: Nibble offset must be a multiple of 4 bits"
+        );
+
+        // DataIdOutOfRange
+        assert_eq!(
+            config_error::<Profile1>(Profile1Config {
+                mode: Profile1IdMode::Nibble,
+                data_id: 0x1000,
+                ..Default::default()
+            }),
+            "Invalid configuration: Data ID shall be within 0x000 and 0xFFF in nibble mode"
+        );
+        assert_eq!(
+            config_error::<Profile1>(Profile1Config {
+                mode: Profile1IdMode::Low,
+                data_id: 0x100,
+                ..Default::default()
+            }),
+            "Invalid configuration: Data ID shall be within 0x00 and 0xFF in low mode"
+        );
+
+        // OffsetBeyondHeader
+        assert_eq!(
+            config_error::<Profile5>(Profile5Config {
+                data_length: 32,
+                offset: 16,
+                ..Default::default()
+            }),
+            "Invalid configuration: Offset shall be between 0 and data length - 3B"
+        );
+
+        // MaxDeltaCounterInvalid, MaxNoNewOrRepeatedDataInvalid, SyncCounterInvalid
+        assert_eq!(
+            config_error::<Profile2>(Profile2Config {
+                max_delta_counter: 0,
+                ..Default::default()
+            }),
+            "Invalid configuration: Max delta counter must be between 1 and 15"
+        );
+        assert_eq!(
+            config_error::<Profile2>(Profile2Config {
+                max_no_new_or_repeated_data: 15,
+                ..Default::default()
+            }),
+            "Invalid configuration: Max no new or repeated data must be between 0 and 14"
+        );
+        assert_eq!(
+            config_error::<Profile2>(Profile2Config {
+                sync_counter: 16,
+                ..Default::default()
+            }),
+            "Invalid configuration: Sync counter must be between 0 and 15"
+        );
+
+        // Transformer configuration errors
+        let transformer_error = |config| {
+            let profile = Profile4::new(Profile4Config::default()).unwrap();
+            E2ETransformer::new(profile, config)
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            transformer_error(E2ETransformerConfig {
+                header_offset: 4,
+                ..Default::default()
+            }),
+            "Invalid configuration: Header offset shall be a multiple of 8"
+        );
+        assert_eq!(
+            transformer_error(E2ETransformerConfig {
+                header_length: 0,
+                ..Default::default()
+            }),
+            "Invalid configuration: Header length shall be a non-zero multiple of 8"
+        );
+        assert_eq!(
+            transformer_error(E2ETransformerConfig {
+                upper_header_bits_to_shift: 136,
+                ..Default::default()
+            }),
+            "Invalid configuration: Upper header bits to shift shall be a multiple of 8 and at most 128"
+        );
+        assert_eq!(
+            transformer_error(E2ETransformerConfig {
+                upper_header_bits_to_shift: 64,
+                ..Default::default()
+            }),
+            "Invalid configuration: Header offset shall not be smaller than the upper header bits to shift"
+        );
+    }
 }
@@ -0,0 +1,555 @@
+//! # Packet Capture Reader and Writer
+//!
+//! Reads packets from pcap and pcapng captures and extracts UDP datagrams
+//! from Ethernet frames (IPv4 and IPv6, optionally VLAN tagged), for the
+//! offline analysis of recorded SOME/IP traffic (see [`crate::analysis`]).
+//!
+//! Fragmented IP datagrams are not reassembled; only complete datagrams are
+//! returned by [`udp_datagram`].
+//!
+//! The writer functions produce captures of the same formats, e.g. to record
+//! the output of a sender for tests.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::Path;
+
+use thiserror::Error;
+
+// Constants
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+const PCAP_HEADER_LENGTH: usize = 24;
+const PCAP_RECORD_HEADER_LENGTH: usize = 16;
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d_0d0a;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const PCAPNG_SIMPLE_PACKET: u32 = 0x0000_0003;
+const PCAPNG_ENHANCED_PACKET: u32 = 0x0000_0006;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+const PCAPNG_OPTION_TSRESOL: u16 = 9;
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const ETHERTYPE_QINQ: u16 = 0x88a8;
+const IP_PROTOCOL_UDP: u8 = 17;
+const UDP_HEADER_LENGTH: usize = 8;
+
+/// Link type of Ethernet frames
+pub const LINKTYPE_ETHERNET: u16 = 1;
+
+/// Packet read from a capture
+#[derive(Debug, Clone, PartialEq)]
+pub struct PcapPacket {
+    /// Timestamp in seconds
+    pub timestamp: f64,
+    /// Link type of the capturing interface, e.g. `LINKTYPE_ETHERNET`
+    pub link_type: u16,
+    /// Captured bytes of the packet
+    pub data: Vec<u8>,
+}
+
+/// UDP datagram extracted from an Ethernet frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UdpDatagram<'a> {
+    /// Source address and port
+    pub source: SocketAddr,
+    /// Destination address and port
+    pub destination: SocketAddr,
+    /// UDP payload
+    pub payload: &'a [u8],
+}
+
+/// Capture error types
+#[derive(Debug, Error)]
+pub enum PcapError {
+    /// The capture file could not be read
+    #[error("Failed to read capture: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The file is neither a pcap nor a pcapng capture
+    #[error("Unknown capture format")]
+    UnknownFormat,
+
+    /// The capture ends within a header or a packet
+    #[error("Truncated capture at byte {0}")]
+    Truncated(usize),
+
+    /// A pcapng block is malformed
+    #[error("Invalid pcapng block at byte {0}")]
+    InvalidBlock(usize),
+}
+
+/// Capture data with its byte order
+#[derive(Clone, Copy)]
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn u16(&self, position: usize) -> Option<u16> {
+        let bytes = self.data.get(position..position + 2)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, position: usize) -> Option<u32> {
+        let bytes = self.data.get(position..position + 4)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+}
+
+/// Read all packets of a pcap or pcapng capture
+pub fn read_capture(data: &[u8]) -> Result<Vec<PcapPacket>, PcapError> {
+    let magic = data
+        .get(..4)
+        .map(|magic| u32::from_le_bytes([magic[0], magic[1], magic[2], magic[3]]))
+        .ok_or(PcapError::UnknownFormat)?;
+    if magic == PCAPNG_SECTION_HEADER {
+        read_pcapng(data)
+    } else {
+        read_pcap(data)
+    }
+}
+
+/// Read all packets of a pcap or pcapng capture file
+pub fn read_capture_file<P: AsRef<Path>>(path: P) -> Result<Vec<PcapPacket>, PcapError> {
+    read_capture(&std::fs::read(path)?)
+}
+
+/// Read all packets of a pcap capture
+pub fn read_pcap(data: &[u8]) -> Result<Vec<PcapPacket>, PcapError> {
+    let header = data
+        .get(..PCAP_HEADER_LENGTH)
+        .ok_or(PcapError::Truncated(0))?;
+    let (big_endian, nanos) = match [
+        u32::from_le_bytes([header[0], header[1], header[2], header[3]]),
+        u32::from_be_bytes([header[0], header[1], header[2], header[3]]),
+    ] {
+        [PCAP_MAGIC_MICROS, _] => (false, false),
+        [PCAP_MAGIC_NANOS, _] => (false, true),
+        [_, PCAP_MAGIC_MICROS] => (true, false),
+        [_, PCAP_MAGIC_NANOS] => (true, true),
+        _ => return Err(PcapError::UnknownFormat),
+    };
+    let reader = Reader { data, big_endian };
+    let link_type = reader.u32(20).ok_or(PcapError::Truncated(20))? as u16;
+    let units_per_second = if nanos { 1e9 } else { 1e6 };
+
+    let mut packets = Vec::new();
+    let mut position = PCAP_HEADER_LENGTH;
+    while position < data.len() {
+        let truncated = PcapError::Truncated(position);
+        let (Some(seconds), Some(fraction), Some(length)) = (
+            reader.u32(position),
+            reader.u32(position + 4),
+            reader.u32(position + 8),
+        ) else {
+            return Err(truncated);
+        };
+        let start = position + PCAP_RECORD_HEADER_LENGTH;
+        let end = start + length as usize;
+        let packet = data.get(start..end).ok_or(truncated)?;
+        packets.push(PcapPacket {
+            timestamp: f64::from(seconds) + f64::from(fraction) / units_per_second,
+            link_type,
+            data: packet.to_vec(),
+        });
+        position = end;
+    }
+    Ok(packets)
+}
+
+/// Interface of a pcapng section
+struct Interface {
+    link_type: u16,
+    units_per_second: f64,
+}
+
+/// Read the timestamp resolution (units per second) from the options of an
+/// interface description block
+fn read_tsresol(reader: &Reader, mut position: usize, end: usize) -> f64 {
+    while position + 4 <= end {
+        let (Some(code), Some(length)) = (reader.u16(position), reader.u16(position + 2)) else {
+            break;
+        };
+        if code == 0 {
+            break;
+        }
+        if code == PCAPNG_OPTION_TSRESOL {
+            if let Some(&value) = reader.data.get(position + 4) {
+                let exponent = i32::from(value & 0x7f);
+                return if value & 0x80 != 0 {
+                    2f64.powi(exponent)
+                } else {
+                    10f64.powi(exponent)
+                };
+            }
+        }
+        position += 4 + usize::from(length).div_ceil(4) * 4;
+    }
+    1e6
+}
+
+/// Read all packets of a pcapng capture
+pub fn read_pcapng(data: &[u8]) -> Result<Vec<PcapPacket>, PcapError> {
+    let mut reader = Reader {
+        data,
+        big_endian: false,
+    };
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut packets = Vec::new();
+    let mut position = 0;
+    while position < data.len() {
+        let block_type = reader.u32(position).ok_or(PcapError::Truncated(position))?;
+        if block_type == PCAPNG_SECTION_HEADER {
+            // every section defines its byte order and interfaces
+            let magic = data
+                .get(position + 8..position + 12)
+                .ok_or(PcapError::Truncated(position))?;
+            reader.big_endian = match u32::from_le_bytes([magic[0], magic[1], magic[2], magic[3]]) {
+                PCAPNG_BYTE_ORDER_MAGIC => false,
+                magic if magic.swap_bytes() == PCAPNG_BYTE_ORDER_MAGIC => true,
+                _ => return Err(PcapError::InvalidBlock(position)),
+            };
+            interfaces.clear();
+        }
+        let length = reader
+            .u32(position + 4)
+            .ok_or(PcapError::Truncated(position))? as usize;
+        if length < 12 || !length.is_multiple_of(4) {
+            return Err(PcapError::InvalidBlock(position));
+        }
+        let end = position + length;
+        if end > data.len() {
+            return Err(PcapError::Truncated(position));
+        }
+        let body = position + 8;
+        let invalid = || PcapError::InvalidBlock(position);
+        match block_type {
+            PCAPNG_INTERFACE_DESCRIPTION => {
+                let link_type = reader.u16(body).ok_or_else(invalid)?;
+                interfaces.push(Interface {
+                    link_type,
+                    units_per_second: read_tsresol(&reader, body + 8, end - 4),
+                });
+            }
+            PCAPNG_ENHANCED_PACKET => {
+                let (Some(interface), Some(high), Some(low), Some(captured)) = (
+                    reader.u32(body),
+                    reader.u32(body + 4),
+                    reader.u32(body + 8),
+                    reader.u32(body + 12),
+                ) else {
+                    return Err(invalid());
+                };
+                let interface = interfaces.get(interface as usize).ok_or_else(invalid)?;
+                let start = body + 20;
+                let packet = data
+                    .get(start..start + captured as usize)
+                    .filter(|_| start + captured as usize <= end - 4)
+                    .ok_or_else(invalid)?;
+                let ticks = (u64::from(high) << 32) | u64::from(low);
+                packets.push(PcapPacket {
+                    timestamp: ticks as f64 / interface.units_per_second,
+                    link_type: interface.link_type,
+                    data: packet.to_vec(),
+                });
+            }
+            PCAPNG_SIMPLE_PACKET => {
+                // the original packet length follows the block header
+                if length < 16 {
+                    return Err(invalid());
+                }
+                let interface = interfaces.first().ok_or_else(invalid)?;
+                let original = reader.u32(body).ok_or_else(invalid)? as usize;
+                let start = body + 4;
+                let captured = original.min(end - 4 - start);
+                packets.push(PcapPacket {
+                    timestamp: 0.0,
+                    link_type: interface.link_type,
+                    data: data[start..start + captured].to_vec(),
+                });
+            }
+            _ => {}
+        }
+        position = end;
+    }
+    Ok(packets)
+}
+
+/// Write packets into a little-endian pcap capture with microsecond timestamps
+///
+/// The link type of the capture is the one of the first packet.
+pub fn write_pcap(packets: &[PcapPacket]) -> Vec<u8> {
+    let link_type = packets.first().map_or(LINKTYPE_ETHERNET, |p| p.link_type);
+    let mut output = Vec::new();
+    output.extend_from_slice(&PCAP_MAGIC_MICROS.to_le_bytes());
+    output.extend_from_slice(&2u16.to_le_bytes());
+    output.extend_from_slice(&4u16.to_le_bytes());
+    output.extend_from_slice(&[0x00; 8]);
+    output.extend_from_slice(&u32::MAX.to_le_bytes());
+    output.extend_from_slice(&u32::from(link_type).to_le_bytes());
+    for packet in packets {
+        let micros = (packet.timestamp * 1e6).round() as u64;
+        output.extend_from_slice(&((micros / 1_000_000) as u32).to_le_bytes());
+        output.extend_from_slice(&((micros % 1_000_000) as u32).to_le_bytes());
+        output.extend_from_slice(&(packet.data.len() as u32).to_le_bytes());
+        output.extend_from_slice(&(packet.data.len() as u32).to_le_bytes());
+        output.extend_from_slice(&packet.data);
+    }
+    output
+}
+
+/// Write packets into a little-endian pcapng capture with microsecond timestamps
+///
+/// One interface is described per link type, in order of first use.
+pub fn write_pcapng(packets: &[PcapPacket]) -> Vec<u8> {
+    fn block(output: &mut Vec<u8>, block_type: u32, body: &[u8]) {
+        let padding = (4 - body.len() % 4) % 4;
+        let length = (12 + body.len() + padding) as u32;
+        output.extend_from_slice(&block_type.to_le_bytes());
+        output.extend_from_slice(&length.to_le_bytes());
+        output.extend_from_slice(body);
+        output.extend_from_slice(&[0x00; 3][..padding]);
+        output.extend_from_slice(&length.to_le_bytes());
+    }
+
+    let mut output = Vec::new();
+    let mut section = Vec::new();
+    section.extend_from_slice(&PCAPNG_BYTE_ORDER_MAGIC.to_le_bytes());
+    section.extend_from_slice(&1u16.to_le_bytes());
+    section.extend_from_slice(&0u16.to_le_bytes());
+    section.extend_from_slice(&u64::MAX.to_le_bytes());
+    block(&mut output, PCAPNG_SECTION_HEADER, &section);
+
+    let mut link_types: Vec<u16> = Vec::new();
+    for packet in packets {
+        let interface = match link_types.iter().position(|&t| t == packet.link_type) {
+            Some(interface) => interface,
+            None => {
+                let mut description = Vec::new();
+                description.extend_from_slice(&packet.link_type.to_le_bytes());
+                description.extend_from_slice(&0u16.to_le_bytes());
+                description.extend_from_slice(&0u32.to_le_bytes());
+                block(&mut output, PCAPNG_INTERFACE_DESCRIPTION, &description);
+                link_types.push(packet.link_type);
+                link_types.len() - 1
+            }
+        };
+        let micros = (packet.timestamp * 1e6).round() as u64;
+        let mut enhanced = Vec::new();
+        enhanced.extend_from_slice(&(interface as u32).to_le_bytes());
+        enhanced.extend_from_slice(&((micros >> 32) as u32).to_le_bytes());
+        enhanced.extend_from_slice(&(micros as u32).to_le_bytes());
+        enhanced.extend_from_slice(&(packet.data.len() as u32).to_le_bytes());
+        enhanced.extend_from_slice(&(packet.data.len() as u32).to_le_bytes());
+        enhanced.extend_from_slice(&packet.data);
+        block(&mut output, PCAPNG_ENHANCED_PACKET, &enhanced);
+    }
+    output
+}
+
+fn read_u16_be(data: &[u8], position: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(position..position + 2)?.try_into().ok()?,
+    ))
+}
+
+/// Extract the UDP datagram of an Ethernet frame
+///
+/// Returns `None` for other protocols, fragmented IP datagrams and
+/// malformed frames.
+pub fn udp_datagram(frame: &[u8]) -> Option<UdpDatagram<'_>> {
+    let mut position = 12;
+    let mut ethertype = read_u16_be(frame, position)?;
+    while ethertype == ETHERTYPE_VLAN || ethertype == ETHERTYPE_QINQ {
+        position += 4;
+        ethertype = read_u16_be(frame, position)?;
+    }
+    let ip = frame.get(position + 2..)?;
+    let (source, destination, udp) = match ethertype {
+        ETHERTYPE_IPV4 => {
+            let header_length = usize::from(ip.first()? & 0x0f) * 4;
+            let total_length = usize::from(read_u16_be(ip, 2)?);
+            let fragment = read_u16_be(ip, 6)?;
+            // more fragments flag or fragment offset
+            if fragment & 0x3fff != 0 || *ip.get(9)? != IP_PROTOCOL_UDP {
+                return None;
+            }
+            let source: [u8; 4] = ip.get(12..16)?.try_into().ok()?;
+            let destination: [u8; 4] = ip.get(16..20)?.try_into().ok()?;
+            (
+                IpAddr::V4(Ipv4Addr::from(source)),
+                IpAddr::V4(Ipv4Addr::from(destination)),
+                ip.get(header_length..total_length)?,
+            )
+        }
+        ETHERTYPE_IPV6 => {
+            let payload_length = usize::from(read_u16_be(ip, 4)?);
+            let mut next_header = *ip.get(6)?;
+            let source: [u8; 16] = ip.get(8..24)?.try_into().ok()?;
+            let destination: [u8; 16] = ip.get(24..40)?.try_into().ok()?;
+            let mut payload = ip.get(40..40 + payload_length)?;
+            // hop-by-hop, routing and destination options headers
+            while matches!(next_header, 0 | 43 | 60) {
+                let length = (usize::from(*payload.get(1)?) + 1) * 8;
+                next_header = *payload.first()?;
+                payload = payload.get(length..)?;
+            }
+            if next_header != IP_PROTOCOL_UDP {
+                return None;
+            }
+            (
+                IpAddr::V6(Ipv6Addr::from(source)),
+                IpAddr::V6(Ipv6Addr::from(destination)),
+                payload,
+            )
+        }
+        _ => return None,
+    };
+    let length = usize::from(read_u16_be(udp, 4)?);
+    Some(UdpDatagram {
+        source: SocketAddr::new(source, read_u16_be(udp, 0)?),
+        destination: SocketAddr::new(destination, read_u16_be(udp, 2)?),
+        payload: udp.get(UDP_HEADER_LENGTH..length)?,
+    })
+}
+
+/// Build an Ethernet frame carrying a UDP datagram
+///
+/// The UDP checksum is left at zero. Returns `None` if the addresses are of
+/// different IP versions or the payload does not fit into one datagram.
+pub fn build_udp_frame(
+    source: SocketAddr,
+    destination: SocketAddr,
+    payload: &[u8],
+) -> Option<Vec<u8>> {
+    let udp_length = u16::try_from(UDP_HEADER_LENGTH + payload.len()).ok()?;
+    let mut udp = Vec::with_capacity(usize::from(udp_length));
+    udp.extend_from_slice(&source.port().to_be_bytes());
+    udp.extend_from_slice(&destination.port().to_be_bytes());
+    udp.extend_from_slice(&udp_length.to_be_bytes());
+    udp.extend_from_slice(&[0x00; 2]);
+    udp.extend_from_slice(payload);
+
+    let mut frame = vec![
+        0x02, 0x00, 0x00, 0x00, 0x00, 0x02, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01,
+    ];
+    match (source.ip(), destination.ip()) {
+        (IpAddr::V4(source), IpAddr::V4(destination)) => {
+            let total_length = 20u16.checked_add(udp_length)?;
+            let mut header = vec![0x45, 0x00];
+            header.extend_from_slice(&total_length.to_be_bytes());
+            header.extend_from_slice(&[0x00, 0x00, 0x40, 0x00, 0x40, IP_PROTOCOL_UDP, 0x00, 0x00]);
+            header.extend_from_slice(&source.octets());
+            header.extend_from_slice(&destination.octets());
+            let sum = header
+                .chunks(2)
+                .map(|word| u32::from(u16::from_be_bytes([word[0], word[1]])))
+                .sum::<u32>();
+            let checksum = !(((sum & 0xffff) + (sum >> 16)) as u16);
+            header[10..12].copy_from_slice(&checksum.to_be_bytes());
+            frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+            frame.extend_from_slice(&header);
+        }
+        (IpAddr::V6(source), IpAddr::V6(destination)) => {
+            frame.extend_from_slice(&ETHERTYPE_IPV6.to_be_bytes());
+            frame.extend_from_slice(&[0x60, 0x00, 0x00, 0x00]);
+            frame.extend_from_slice(&udp_length.to_be_bytes());
+            frame.extend_from_slice(&[IP_PROTOCOL_UDP, 0x40]);
+            frame.extend_from_slice(&source.octets());
+            frame.extend_from_slice(&destination.octets());
+        }
+        _ => return None,
+    }
+    frame.extend_from_slice(&udp);
+    Some(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packets() -> Vec<PcapPacket> {
+        let v4 = build_udp_frame(
+            "10.0.0.1:30501".parse().unwrap(),
+            "10.0.0.2:30502".parse().unwrap(),
+            &[0x01, 0x02, 0x03],
+        )
+        .unwrap();
+        let v6 = build_udp_frame(
+            "[fd00::1]:30501".parse().unwrap(),
+            "[fd00::2]:30502".parse().unwrap(),
+            &[0x04, 0x05],
+        )
+        .unwrap();
+        vec![
+            PcapPacket {
+                timestamp: 1.5,
+                link_type: LINKTYPE_ETHERNET,
+                data: v4,
+            },
+            PcapPacket {
+                timestamp: 2.25,
+                link_type: LINKTYPE_ETHERNET,
+                data: v6,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_pcap_roundtrip() {
+        let packets = packets();
+        assert_eq!(read_capture(&write_pcap(&packets)).unwrap(), packets);
+        assert_eq!(read_capture(&write_pcapng(&packets)).unwrap(), packets);
+
+        let capture = write_pcapng(&packets);
+        assert!(matches!(
+            read_capture(&capture[..capture.len() - 4]),
+            Err(PcapError::Truncated(_))
+        ));
+        assert!(matches!(
+            read_capture(&[0x00; 24]),
+            Err(PcapError::UnknownFormat)
+        ));
+
+        // simple packet block without the original packet length
+        let mut truncated_spb = capture.clone();
+        for word in [PCAPNG_SIMPLE_PACKET, 12, 12] {
+            truncated_spb.extend_from_slice(&word.to_le_bytes());
+        }
+        assert!(matches!(
+            read_capture(&truncated_spb),
+            Err(PcapError::InvalidBlock(position)) if position == capture.len()
+        ));
+    }
+
+    #[test]
+    fn test_udp_datagram() {
+        let packets = packets();
+        let datagram = udp_datagram(&packets[0].data).unwrap();
+        assert_eq!(datagram.source, "10.0.0.1:30501".parse().unwrap());
+        assert_eq!(datagram.destination, "10.0.0.2:30502".parse().unwrap());
+        assert_eq!(datagram.payload, &[0x01, 0x02, 0x03]);
+        let datagram = udp_datagram(&packets[1].data).unwrap();
+        assert_eq!(datagram.source, "[fd00::1]:30501".parse().unwrap());
+        assert_eq!(datagram.payload, &[0x04, 0x05]);
+
+        // VLAN tagged frame
+        let mut tagged = packets[0].data.clone();
+        tagged.splice(12..12, [0x81, 0x00, 0x00, 0x05]);
+        assert_eq!(udp_datagram(&tagged).unwrap().payload, &[0x01, 0x02, 0x03]);
+
+        // fragmented datagram
+        let mut fragment = packets[0].data.clone();
+        fragment[20] = 0x20;
+        assert!(udp_datagram(&fragment).is_none());
+    }
+}
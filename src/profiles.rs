@@ -1,9 +1,360 @@
+use crate::{E2EError, E2EResult, E2ESnapshot, E2EStatus, E2ESyncPolicy};
+
+pub mod profile1;
 pub mod profile11;
+pub mod profile2;
 pub mod profile22;
 pub mod profile4;
+pub mod profile44;
+pub mod profile44m;
 pub mod profile4m;
+pub mod profile4x;
 pub mod profile5;
 pub mod profile6;
 pub mod profile7;
+pub mod profile78m;
 pub mod profile7m;
 pub mod profile8;
+pub mod profile8m;
+
+const BITS_PER_BYTE: usize = 8;
+
+/// Modification of the protected data requested by a forward status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ForwardAction {
+    /// Protect the data as usual
+    Protect,
+    /// Repeat the counter of the previous cycle
+    Repeat,
+    /// Skip more counter values than the receiver tolerates
+    Skip,
+    /// Protect the data and invert the CRC afterwards
+    CorruptCrc,
+}
+
+impl ForwardAction {
+    pub(crate) fn from_status(status: E2EStatus) -> E2EResult<Self> {
+        match status {
+            E2EStatus::Ok | E2EStatus::OkSomeLost => Ok(ForwardAction::Protect),
+            E2EStatus::Repeated => Ok(ForwardAction::Repeat),
+            E2EStatus::WrongSequence => Ok(ForwardAction::Skip),
+            E2EStatus::CrcError
+            | E2EStatus::DataIdError
+            | E2EStatus::DataLengthError
+            | E2EStatus::SourceIdError
+            | E2EStatus::MessageTypeError
+            | E2EStatus::MessageResultError => Ok(ForwardAction::CorruptCrc),
+            E2EStatus::Sync | E2EStatus::NoNewData => Err(E2EError::InvalidForwardStatus(status)),
+        }
+    }
+
+    /// Like `from_status`, for a receiver accepting counter deltas up to
+    /// `max_delta` out of counters up to `counter_max`
+    ///
+    /// If every delta but 0 is accepted, any skip lands on an accepted or a
+    /// repeated counter, so `WrongSequence` cannot be forwarded.
+    pub(crate) fn from_status_with_delta(
+        status: E2EStatus,
+        max_delta: u32,
+        counter_max: u32,
+    ) -> E2EResult<Self> {
+        match Self::from_status(status)? {
+            ForwardAction::Skip if max_delta >= counter_max => {
+                Err(E2EError::InvalidForwardStatus(status))
+            }
+            action => Ok(action),
+        }
+    }
+}
+
+/// Check that a header field of `width` bits at the bit `offset` lies within
+/// data of `data_length` bits
+pub(crate) fn check_field_bounds(offset: usize, width: usize, data_length: usize) -> E2EResult<()> {
+    if data_length < offset + width {
+        return Err(E2EError::OffsetOutOfBounds {
+            offset,
+            min: 0,
+            max: data_length - width,
+        });
+    }
+    Ok(())
+}
+
+/// Check that an E2E header of `header_length` bytes at the bit `offset`
+/// fits into data of the minimum data length, given in bits
+///
+/// Used by the M variants, whose metadata fields extend the header of the
+/// base profile beyond the length checked by its validation. `max_length` is
+/// the largest data length of the profile in bytes, `usize::MAX` if unbounded.
+pub(crate) fn check_header_fits(
+    offset: usize,
+    header_length: usize,
+    min_data_length: usize,
+    max_length: usize,
+) -> E2EResult<()> {
+    let header_bits = header_length * BITS_PER_BYTE;
+    if min_data_length < header_bits {
+        return Err(E2EError::MinDataLengthOutOfRange {
+            min: header_length,
+            max: max_length,
+            actual: min_data_length,
+        });
+    }
+    if min_data_length - header_bits < offset {
+        return Err(E2EError::OffsetBeyondHeader {
+            offset,
+            header_length,
+        });
+    }
+    Ok(())
+}
+
+/// Receiver state of the `E2ESyncPolicy`, shared by all profiles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct SyncState {
+    /// Whether a message was accepted since startup or the last resync
+    initialized: bool,
+    /// Consecutive messages with a valid counter, up to `min_consecutive`
+    consecutive: u8,
+    /// Consecutive `WrongSequence` results, counted if resyncing is enabled
+    wrong_sequence: u8,
+}
+
+impl SyncState {
+    pub(crate) fn initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// Evaluate the delta between the received and the last counter
+    pub(crate) fn check_delta(
+        &mut self,
+        policy: &E2ESyncPolicy,
+        delta: u32,
+        max_delta: u32,
+    ) -> E2EStatus {
+        let status = match delta {
+            _ if !self.initialized && policy.accept_any_first => E2EStatus::Ok,
+            0 if !self.initialized => E2EStatus::Ok,
+            0 => E2EStatus::Repeated,
+            1 => E2EStatus::Ok,
+            _ if delta <= max_delta => E2EStatus::OkSomeLost,
+            _ => E2EStatus::WrongSequence,
+        };
+        self.apply(policy, status)
+    }
+
+    /// Track a counter status and report `Sync` while the policy requires
+    /// further consecutive messages
+    pub(crate) fn apply(&mut self, policy: &E2ESyncPolicy, status: E2EStatus) -> E2EStatus {
+        match status {
+            E2EStatus::Ok | E2EStatus::OkSomeLost | E2EStatus::Sync => {
+                self.initialized = true;
+                self.wrong_sequence = 0;
+                if self.consecutive < policy.min_consecutive {
+                    self.consecutive += 1;
+                }
+                if self.consecutive < policy.min_consecutive {
+                    E2EStatus::Sync
+                } else {
+                    status
+                }
+            }
+            E2EStatus::WrongSequence => {
+                self.consecutive = 0;
+                if policy.resync_after_errors > 0 {
+                    self.wrong_sequence += 1;
+                    if self.wrong_sequence >= policy.resync_after_errors {
+                        *self = Self::default();
+                    }
+                }
+                status
+            }
+            _ => status,
+        }
+    }
+
+    /// Store the state in a snapshot
+    pub(crate) fn save(&self, snapshot: E2ESnapshot) -> E2ESnapshot {
+        E2ESnapshot {
+            initialized: self.initialized,
+            sync_state: [self.consecutive, self.wrong_sequence],
+            ..snapshot
+        }
+    }
+
+    /// Read the state from a snapshot, rejecting progress the policy cannot
+    /// reach
+    pub(crate) fn load(policy: &E2ESyncPolicy, snapshot: &E2ESnapshot) -> E2EResult<Self> {
+        let [consecutive, wrong_sequence] = snapshot.sync_state;
+        if consecutive > policy.min_consecutive
+            || (wrong_sequence > 0 && wrong_sequence >= policy.resync_after_errors)
+        {
+            return Err(E2EError::SnapshotInvalid);
+        }
+        Ok(Self {
+            initialized: snapshot.initialized,
+            consecutive,
+            wrong_sequence,
+        })
+    }
+}
+
+/// Upper limit of the no new or repeated data counter of Profiles 1 and 2
+pub(crate) const NO_NEW_OR_REPEATED_DATA_MAX: u8 = 14;
+
+/// Receiver state of Profiles 1 and 2
+///
+/// The accepted counter delta widens by one on every check cycle without a
+/// valid message, and `sync_counter` consecutive valid messages are required
+/// after a counter discontinuity before `Ok` is reported again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CounterWindow {
+    /// Largest counter value of the profile
+    counter_max: u8,
+    /// Configured initial maximum delta
+    init_max_delta_counter: u8,
+    /// Configured tolerance of missing or repeated data
+    max_no_new_or_repeated_data: u8,
+    /// Configured number of messages required to resynchronize
+    init_sync_counter: u8,
+    sync: SyncState,
+    max_delta_counter: u8,
+    no_new_or_repeated_data_counter: u8,
+    sync_counter: u8,
+}
+
+impl CounterWindow {
+    pub(crate) fn new(
+        counter_max: u8,
+        max_delta_counter: u8,
+        max_no_new_or_repeated_data: u8,
+        sync_counter: u8,
+    ) -> Self {
+        Self {
+            counter_max,
+            init_max_delta_counter: max_delta_counter,
+            max_no_new_or_repeated_data,
+            init_sync_counter: sync_counter,
+            sync: SyncState::default(),
+            max_delta_counter,
+            no_new_or_repeated_data_counter: 0,
+            sync_counter: 0,
+        }
+    }
+
+    /// Widen the accepted delta for a new check cycle
+    pub(crate) fn next_cycle(&mut self) {
+        if self.max_delta_counter < self.counter_max {
+            self.max_delta_counter += 1;
+        }
+    }
+
+    /// Track a check cycle without new data
+    pub(crate) fn no_data(&mut self) {
+        self.next_cycle();
+        self.increment_no_new_or_repeated_data_counter();
+    }
+
+    fn increment_no_new_or_repeated_data_counter(&mut self) {
+        if self.no_new_or_repeated_data_counter < NO_NEW_OR_REPEATED_DATA_MAX {
+            self.no_new_or_repeated_data_counter += 1;
+        }
+    }
+
+    /// Evaluate the received counter of data with a valid CRC against the
+    /// last valid `counter`, updating it if the data is accepted
+    pub(crate) fn check(
+        &mut self,
+        policy: &E2ESyncPolicy,
+        counter: &mut u8,
+        rx_counter: u8,
+    ) -> E2EStatus {
+        let modulo = self.counter_max + 1;
+        let delta = (modulo + rx_counter - *counter) % modulo;
+        if !self.sync.initialized() {
+            // First message after startup or a resynchronization
+            let status = self
+                .sync
+                .check_delta(policy, delta.into(), self.max_delta_counter.into());
+            if status != E2EStatus::WrongSequence {
+                self.max_delta_counter = self.init_max_delta_counter;
+                *counter = rx_counter;
+            }
+            return status;
+        }
+        let status = if delta == 0 {
+            self.increment_no_new_or_repeated_data_counter();
+            E2EStatus::Repeated
+        } else if delta == 1 {
+            self.accept_counter(counter, rx_counter, E2EStatus::Ok)
+        } else if delta <= self.max_delta_counter {
+            self.accept_counter(counter, rx_counter, E2EStatus::OkSomeLost)
+        } else {
+            self.no_new_or_repeated_data_counter = 0;
+            self.sync_counter = self.init_sync_counter;
+            if self.sync_counter > 0 {
+                self.max_delta_counter = self.init_max_delta_counter;
+                *counter = rx_counter;
+            }
+            E2EStatus::WrongSequence
+        };
+        self.sync.apply(policy, status)
+    }
+
+    /// Accept the received counter and report either the given status or `Sync`
+    /// while the receiver is resynchronizing
+    fn accept_counter(&mut self, counter: &mut u8, rx_counter: u8, status: E2EStatus) -> E2EStatus {
+        self.max_delta_counter = self.init_max_delta_counter;
+        *counter = rx_counter;
+        if self.no_new_or_repeated_data_counter > self.max_no_new_or_repeated_data {
+            self.sync_counter = self.init_sync_counter;
+        }
+        self.no_new_or_repeated_data_counter = 0;
+        if self.sync_counter > 0 {
+            self.sync_counter -= 1;
+            E2EStatus::Sync
+        } else {
+            status
+        }
+    }
+
+    /// Return to the state after startup
+    pub(crate) fn reset(&mut self) {
+        *self = Self::new(
+            self.counter_max,
+            self.init_max_delta_counter,
+            self.max_no_new_or_repeated_data,
+            self.init_sync_counter,
+        );
+    }
+
+    /// Store the state in a snapshot
+    pub(crate) fn save(&self, snapshot: E2ESnapshot) -> E2ESnapshot {
+        self.sync.save(E2ESnapshot {
+            receiver_state: [
+                self.max_delta_counter,
+                self.no_new_or_repeated_data_counter,
+                self.sync_counter,
+            ],
+            ..snapshot
+        })
+    }
+
+    /// Read the state from a snapshot, rejecting values the receiver cannot
+    /// reach
+    pub(crate) fn load(&mut self, policy: &E2ESyncPolicy, snapshot: &E2ESnapshot) -> E2EResult<()> {
+        let [max_delta_counter, no_new_or_repeated_data_counter, sync_counter] =
+            snapshot.receiver_state;
+        if !(self.init_max_delta_counter..=self.counter_max).contains(&max_delta_counter)
+            || no_new_or_repeated_data_counter > NO_NEW_OR_REPEATED_DATA_MAX
+            || sync_counter > self.init_sync_counter
+        {
+            return Err(E2EError::SnapshotInvalid);
+        }
+        self.sync = SyncState::load(policy, snapshot)?;
+        self.max_delta_counter = max_delta_counter;
+        self.no_new_or_repeated_data_counter = no_new_or_repeated_data_counter;
+        self.sync_counter = sync_counter;
+        Ok(())
+    }
+}
@@ -0,0 +1,827 @@
+//! # E2E Profile 1 Implementation
+//!
+//! Profile 1 is designed for protecting small data packets (up to MAX_DATA_LENGTH_BITS bytes)
+//! with low overhead. It uses:
+//! - 8-bit CRC for data integrity
+//! - 4-bit counter for sequence checking (0-14)
+//! - 16-bit Data ID, fully or partially included in the CRC depending on the mode
+//!
+//! # Data layout
+//! Unlike later profiles, the CRC and counter positions are configured independently.
+//! The default layout is:
+//! [CRC(1B) | HDR(1B) | DATA ...]
+//! - HDR (bits 7..4) : DI_hi_nibble(nibble mode) OR data(other modes)
+//! - HDR (bits 3..0) : counter
+//!
+//! # Modes
+//!
+//! Profile 1 supports four Data ID modes:
+//! - **Both(1A)**: both bytes of the 16-bit Data-ID are implicit (only used in CRC).
+//! - **Alt(1B)**: the low byte is used for even counter values, the high byte for odd ones.
+//! - **Low**: only the low byte of the Data-ID is used in CRC.
+//! - **Nibble(1C)**: high 4-bit is explicit in the header, low 8-bit is implicit (in CRC).
+//!
+//! # Receiver behaviour
+//!
+//! Like Profile 2, the receiver widens the accepted counter delta by one on
+//! every check cycle without a valid message, and requires `sync_counter`
+//! consecutive valid messages after a counter discontinuity before reporting
+//! `Ok` again. The `E2ESyncPolicy` applies on top of this; by default the
+//! first message is accepted with any counter, as in the WaitForFirstData
+//! state of the specification.
+
+use crate::profiles::profile11::CRC8_ALGO;
+use crate::profiles::{check_field_bounds, CounterWindow, NO_NEW_OR_REPEATED_DATA_MAX};
+use crate::snapshot::Fingerprint;
+use crate::{
+    E2ECheckReport, E2EError, E2EHeaderFields, E2EHeaderLayout, E2EProfile, E2EResult, E2ESnapshot,
+    E2EStatus, E2ESyncPolicy, OffsetField,
+};
+use core::ops::Range;
+use crc::Crc;
+
+// Constants
+const NIBBLE_MASK: u8 = 0x0F;
+const COUNTER_MAX: u8 = 14;
+const COUNTER_MODULO: u8 = 15;
+const MIN_DATA_LENGTH_BITS: u8 = 16;
+const MAX_DATA_LENGTH_BITS: u8 = 240;
+const BITS_PER_BYTE: u8 = 8;
+const BITS_PER_NIBBLE: u8 = 4;
+
+/// Data-ID mode for Profile 1.
+///
+/// # Variants
+///
+/// * `Both` - Profile 1A: Both bytes of the 16-bit Data-ID are used
+///   implicitly for CRC calculation.
+///
+/// * `Alt` - Profile 1B: The low byte of the Data-ID is used for even
+///   counter values and the high byte for odd counter values.
+///
+/// * `Low` - Only the low byte of the Data-ID is used for CRC calculation.
+///   The high byte shall be 0x00.
+///
+/// * `Nibble` - Profile 1C: The low nibble of the high byte of the Data-ID
+///   is stored explicitly in the header, while the low byte is used
+///   implicitly for CRC calculation. Data-ID shall be within 0x000-0xFFF.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Profile1IdMode {
+    Both,
+    Alt,
+    Low,
+    Nibble,
+}
+
+/// Configuration for E2E Profile 1
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(remote = "Self", default))]
+pub struct Profile1Config {
+    /// Bit offset of Counter in MSB first order
+    pub counter_offset: u8,
+    /// Bit offset of CRC in MSB first order
+    pub crc_offset: u8,
+    /// Data ID mode(1A, 1B, 1C or low byte only)
+    pub mode: Profile1IdMode,
+    /// A unique identifier
+    pub data_id: u16,
+    /// Bit offset of the low nibble of the high byte of Data ID
+    pub nibble_offset: u8,
+    /// Initial maximum allowed delta between consecutive counters
+    pub max_delta_counter: u8,
+    /// data length (up to MAX_DATA_LENGTH_BITS bits)
+    pub data_length: u8,
+    /// Maximum amount of missing or repeated data the receiver tolerates
+    /// before it has to resynchronize
+    pub max_no_new_or_repeated_data: u8,
+    /// Number of consecutive valid messages required to resynchronize
+    pub sync_counter: u8,
+    /// Receiver behaviour at startup and after a loss of synchronization,
+    /// applied in addition to the synchronization of Profile 1
+    pub sync_policy: E2ESyncPolicy,
+}
+
+impl Default for Profile1Config {
+    fn default() -> Self {
+        Self {
+            counter_offset: 8, // bits
+            crc_offset: 0,     // bits
+            mode: Profile1IdMode::Both,
+            data_id: 0x123,
+            nibble_offset: 12, // bits
+            max_delta_counter: 1,
+            data_length: 64, // bits
+            max_no_new_or_repeated_data: NO_NEW_OR_REPEATED_DATA_MAX,
+            sync_counter: 0,
+            sync_policy: E2ESyncPolicy::accept_any_first(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl_validated_serde!(Profile1Config, Profile1::validate_config);
+
+/// Check Item for E2E Profile 1
+#[derive(Debug, Clone)]
+pub struct Profile1Check {
+    rx_counter: u8,
+    rx_crc: u8,
+    rx_nibble: u8,
+    calculated_crc: u8,
+}
+
+/// E2E Profile 1 Implementation
+///
+/// Implements AUTOSAR E2E Profile 1 protection mechanism with support
+/// for all Data ID modes.
+#[derive(Clone)]
+pub struct Profile1 {
+    config: Profile1Config,
+    counter: u8,
+    window: CounterWindow,
+}
+
+impl Profile1 {
+    /// Validate configuration parameters
+    fn validate_config(config: &Profile1Config) -> E2EResult<()> {
+        if MAX_DATA_LENGTH_BITS < config.data_length {
+            return Err(E2EError::MaxDataLengthExceeded {
+                profile: "Profile 1",
+                max: MAX_DATA_LENGTH_BITS as usize,
+                actual: config.data_length as usize,
+            });
+        }
+
+        if !config.data_length.is_multiple_of(BITS_PER_BYTE) {
+            return Err(E2EError::UnalignedDataLength);
+        }
+
+        if config.data_length < MIN_DATA_LENGTH_BITS {
+            return Err(E2EError::DataLengthOutOfRange {
+                min: (MIN_DATA_LENGTH_BITS / BITS_PER_BYTE) as usize,
+                max: (MAX_DATA_LENGTH_BITS / BITS_PER_BYTE) as usize,
+                actual: config.data_length as usize,
+            });
+        }
+
+        if config.max_delta_counter == 0 || config.max_delta_counter > COUNTER_MAX {
+            return Err(E2EError::MaxDeltaCounterInvalid {
+                max: COUNTER_MAX as u32,
+            });
+        }
+
+        if config.max_no_new_or_repeated_data > NO_NEW_OR_REPEATED_DATA_MAX {
+            return Err(E2EError::MaxNoNewOrRepeatedDataInvalid {
+                max: NO_NEW_OR_REPEATED_DATA_MAX.into(),
+            });
+        }
+
+        if config.sync_counter > COUNTER_MAX {
+            return Err(E2EError::SyncCounterInvalid {
+                max: COUNTER_MAX.into(),
+            });
+        }
+
+        if !config.counter_offset.is_multiple_of(BITS_PER_NIBBLE) {
+            return Err(E2EError::UnalignedOffset {
+                field: OffsetField::Counter,
+                alignment: BITS_PER_NIBBLE as usize,
+            });
+        }
+
+        if !config.crc_offset.is_multiple_of(BITS_PER_BYTE) {
+            return Err(E2EError::UnalignedOffset {
+                field: OffsetField::Crc,
+                alignment: BITS_PER_BYTE as usize,
+            });
+        }
+
+        match config.mode {
+            Profile1IdMode::Nibble => {
+                if !config.nibble_offset.is_multiple_of(BITS_PER_NIBBLE) {
+                    return Err(E2EError::UnalignedOffset {
+                        field: OffsetField::Nibble,
+                        alignment: BITS_PER_NIBBLE as usize,
+                    });
+                }
+                if config.data_id > 0x0FFF {
+                    return Err(E2EError::DataIdOutOfRange {
+                        mode: "nibble",
+                        max: 0x0FFF,
+                        actual: config.data_id.into(),
+                    });
+                }
+            }
+            Profile1IdMode::Low => {
+                if config.data_id > 0x00FF {
+                    return Err(E2EError::DataIdOutOfRange {
+                        mode: "low",
+                        max: 0x00FF,
+                        actual: config.data_id.into(),
+                    });
+                }
+            }
+            Profile1IdMode::Both | Profile1IdMode::Alt => {}
+        }
+
+        let data_length = config.data_length as usize;
+        check_field_bounds(
+            config.counter_offset as usize,
+            BITS_PER_NIBBLE as usize,
+            data_length,
+        )?;
+        check_field_bounds(
+            config.crc_offset as usize,
+            BITS_PER_BYTE as usize,
+            data_length,
+        )?;
+        if config.mode == Profile1IdMode::Nibble {
+            check_field_bounds(
+                config.nibble_offset as usize,
+                BITS_PER_NIBBLE as usize,
+                data_length,
+            )?;
+        }
+
+        Ok(())
+    }
+    /// Validate data length against configured length
+    fn validate_length(&self, len: usize) -> E2EResult<()> {
+        let expected_bytes = (self.config.data_length / BITS_PER_BYTE) as usize;
+        if len != expected_bytes {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: expected_bytes,
+                expected_max: expected_bytes,
+                actual: len,
+            });
+        }
+        Ok(())
+    }
+    fn write_nibble_data(&self, offset: u8, set_value: u8, data: &mut [u8]) {
+        let byte_idx = (offset >> 3) as usize;
+        let shift = offset & 0x07;
+
+        let mask = !(NIBBLE_MASK << shift);
+        let val = (set_value & NIBBLE_MASK) << shift;
+        data[byte_idx] = (data[byte_idx] & mask) | val;
+    }
+    fn write_crc(&self, calculated_crc: u8, data: &mut [u8]) {
+        let byte_position = (self.config.crc_offset / BITS_PER_BYTE) as usize;
+        data[byte_position] = calculated_crc;
+    }
+    fn compute_crc(&self, data: &[u8]) -> u8 {
+        compute_crc(&self.config, data)
+    }
+    fn increment_counter(&mut self) {
+        self.counter = (self.counter + 1) % COUNTER_MODULO;
+    }
+    fn do_checks(&mut self, check_items: Profile1Check) -> E2EStatus {
+        if check_items.calculated_crc != check_items.rx_crc {
+            return E2EStatus::CrcError;
+        }
+        if (self.config.mode == Profile1IdMode::Nibble)
+            && ((self.config.data_id >> BITS_PER_BYTE) as u8 & NIBBLE_MASK) != check_items.rx_nibble
+        {
+            return E2EStatus::DataIdError;
+        }
+        self.window.check(
+            &self.config.sync_policy,
+            &mut self.counter,
+            check_items.rx_counter,
+        )
+    }
+    /// Read the header fields of the protected data without checking them
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn decode(&self, data: &[u8]) -> E2EResult<E2EHeaderFields> {
+        self.validate_length(data.len())?;
+        let header = Profile1Header::at(&self.config, data);
+        Ok(E2EHeaderFields {
+            counter: header.counter() as u32,
+            data_id: header.data_id_nibble().map(|nibble| (nibble as u32) << 8),
+            received_crc: header.crc() as u64,
+            computed_crc: self.compute_crc(data) as u64,
+            ..Default::default()
+        })
+    }
+    /// Check the protected data like `check` and report the decoded header,
+    /// the counter delta and the first failed check
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn check_detailed(&mut self, data: &[u8]) -> E2EResult<E2ECheckReport> {
+        let previous_counter = self.counter.into();
+        let header = self.decode(data)?;
+        let status = self.check(data)?;
+        Ok(E2ECheckReport::new(
+            status,
+            header,
+            previous_counter,
+            COUNTER_MODULO.into(),
+        ))
+    }
+}
+
+impl E2EProfile for Profile1 {
+    type Config = Profile1Config;
+
+    fn new(config: Self::Config) -> E2EResult<Self> {
+        // Validate config
+        Self::validate_config(&config)?;
+        Ok(Self {
+            window: CounterWindow::new(
+                COUNTER_MAX,
+                config.max_delta_counter,
+                config.max_no_new_or_repeated_data,
+                config.sync_counter,
+            ),
+            config,
+            counter: 0,
+        })
+    }
+
+    fn protect(&mut self, data: &mut [u8]) -> E2EResult<()> {
+        self.validate_length(data.len())?;
+        if self.config.mode == Profile1IdMode::Nibble {
+            self.write_nibble_data(
+                self.config.nibble_offset,
+                self.config.data_id.to_le_bytes()[1],
+                data,
+            );
+        }
+        self.write_nibble_data(self.config.counter_offset, self.counter, data);
+        let calculated_crc = self.compute_crc(data);
+        self.write_crc(calculated_crc, data);
+        self.increment_counter();
+        Ok(())
+    }
+
+    fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus> {
+        // Check data length
+        self.validate_length(data.len())?;
+        // The accepted delta grows with every check cycle until a valid message arrives
+        self.window.next_cycle();
+        let header = Profile1Header::at(&self.config, data);
+        let check_items = Profile1Check {
+            rx_nibble: header.data_id_nibble().unwrap_or_default(),
+            rx_counter: header.counter(),
+            rx_crc: header.crc(),
+            calculated_crc: self.compute_crc(data),
+        };
+        let status = self.do_checks(check_items);
+        Ok(status)
+    }
+
+    fn check_no_data(&mut self) -> E2EResult<E2EStatus> {
+        // A cycle without data widens the accepted delta like any other check cycle
+        self.window.no_data();
+        Ok(E2EStatus::NoNewData)
+    }
+
+    fn protect_init(&mut self) {
+        self.counter = 0;
+    }
+
+    fn check_init(&mut self) {
+        self.counter = 0;
+        self.window.reset();
+    }
+
+    fn snapshot(&self) -> E2ESnapshot {
+        self.window.save(E2ESnapshot::new(
+            fingerprint(&self.config),
+            self.counter.into(),
+        ))
+    }
+
+    fn restore(&mut self, snapshot: &E2ESnapshot) -> E2EResult<()> {
+        snapshot.validate(fingerprint(&self.config), COUNTER_MAX.into())?;
+        self.window.load(&self.config.sync_policy, snapshot)?;
+        self.counter = snapshot.counter as u8;
+        Ok(())
+    }
+}
+
+impl E2EHeaderLayout for Profile1 {
+    fn header_offset(&self) -> usize {
+        Profile1Header::at(&self.config, &[]).bytes().start * BITS_PER_BYTE as usize
+    }
+
+    fn header_length(&self) -> usize {
+        Profile1Header::at(&self.config, &[]).bytes().len() * BITS_PER_BYTE as usize
+    }
+}
+
+/// Zero-copy view of the E2E header of Profile 1 protected data
+///
+/// The view only reads the data, so frames can be inspected without a
+/// profile instance and without affecting any receiver state.
+#[derive(Debug, Clone, Copy)]
+pub struct Profile1Header<'a> {
+    data: &'a [u8],
+    counter_offset: u8,
+    crc_offset: u8,
+    nibble_offset: Option<u8>,
+}
+
+impl<'a> Profile1Header<'a> {
+    /// Locate the header in `data` as configured
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+    pub fn new(config: &Profile1Config, data: &'a [u8]) -> E2EResult<Self> {
+        let header = Self::at(config, data);
+        let header_end = header.bytes().end;
+        if data.len() < header_end {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: header_end,
+                expected_max: usize::MAX,
+                actual: data.len(),
+            });
+        }
+        Ok(header)
+    }
+    pub(crate) fn at(config: &Profile1Config, data: &'a [u8]) -> Self {
+        Self {
+            data,
+            counter_offset: config.counter_offset,
+            crc_offset: config.crc_offset,
+            nibble_offset: (config.mode == Profile1IdMode::Nibble).then_some(config.nibble_offset),
+        }
+    }
+    /// Bytes holding the CRC, the counter and the Data ID nibble
+    fn bytes(&self) -> Range<usize> {
+        let bytes = [
+            Some(self.counter_offset),
+            Some(self.crc_offset),
+            self.nibble_offset,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|offset| (offset / BITS_PER_BYTE) as usize);
+        let start = bytes.clone().min().unwrap_or_default();
+        let end = bytes.max().unwrap_or_default() + 1;
+        start..end
+    }
+    fn read_nibble(&self, offset: u8) -> u8 {
+        let byte_idx = (offset >> 3) as usize;
+        let shift = offset & 0x07;
+
+        (self.data[byte_idx] >> shift) & NIBBLE_MASK
+    }
+    /// Sequence counter
+    pub fn counter(&self) -> u8 {
+        self.read_nibble(self.counter_offset)
+    }
+    /// Low nibble of the high byte of the Data ID, only transmitted in
+    /// `Profile1IdMode::Nibble`
+    pub fn data_id_nibble(&self) -> Option<u8> {
+        self.nibble_offset.map(|offset| self.read_nibble(offset))
+    }
+    /// CRC as received
+    pub fn crc(&self) -> u8 {
+        self.data[(self.crc_offset / BITS_PER_BYTE) as usize]
+    }
+}
+
+/// Update Crc with ID, depending on the mode and the counter in the data
+fn update_crc_with_id(config: &Profile1Config, digest: &mut crc::Digest<u8>, counter: u8) {
+    let [low, high] = config.data_id.to_le_bytes();
+    match config.mode {
+        Profile1IdMode::Both => digest.update(&[low, high]),
+        Profile1IdMode::Alt => {
+            if counter.is_multiple_of(2) {
+                digest.update(&[low]);
+            } else {
+                digest.update(&[high]);
+            }
+        }
+        Profile1IdMode::Low => digest.update(&[low]),
+        Profile1IdMode::Nibble => digest.update(&[low, 0x00]),
+    }
+}
+fn update_crc_with_data(config: &Profile1Config, digest: &mut crc::Digest<u8>, data: &[u8]) {
+    let offset_byte = (config.crc_offset / BITS_PER_BYTE) as usize;
+    digest.update(&data[0..offset_byte]); // crc calculation data before offset
+    digest.update(&data[(offset_byte + 1)..]); // crc calculation data after offset
+}
+
+pub(crate) fn compute_crc(config: &Profile1Config, data: &[u8]) -> u8 {
+    let crc: Crc<u8> = Crc::<u8>::new(&CRC8_ALGO);
+    let mut digest = crc.digest();
+    update_crc_with_id(
+        config,
+        &mut digest,
+        Profile1Header::at(config, data).counter(),
+    );
+    update_crc_with_data(config, &mut digest, data);
+    digest.finalize()
+}
+
+/// Verify the CRC of Profile 1 protected data without a profile instance
+///
+/// # Errors
+/// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+pub fn verify_crc(config: &Profile1Config, data: &[u8]) -> E2EResult<bool> {
+    let header = Profile1Header::new(config, data)?;
+    Ok(header.crc() == compute_crc(config, data))
+}
+
+/// Fingerprint of the configuration, see `E2ESnapshot`
+fn fingerprint(config: &Profile1Config) -> u32 {
+    Fingerprint::new("Profile 1")
+        .field(&config.counter_offset.to_be_bytes())
+        .field(&config.crc_offset.to_be_bytes())
+        .field(&[config.mode as u8])
+        .field(&config.data_id.to_be_bytes())
+        .field(&config.nibble_offset.to_be_bytes())
+        .field(&config.max_delta_counter.to_be_bytes())
+        .field(&config.data_length.to_be_bytes())
+        .field(&config.max_no_new_or_repeated_data.to_be_bytes())
+        .field(&config.sync_counter.to_be_bytes())
+        .field(&config.sync_policy.to_bytes())
+        .finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_profile1_basic_both_example() {
+        let mut profile_tx = Profile1::new(Profile1Config::default()).unwrap();
+        let mut profile_rx = Profile1::new(Profile1Config::default()).unwrap();
+
+        let mut data1 = vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        profile_tx.protect(&mut data1).unwrap();
+        assert_eq!(data1[0], 0xcc);
+        assert_eq!(data1[1], 0x00);
+        assert_eq!(profile_rx.check(&data1).unwrap(), E2EStatus::Ok);
+
+        profile_tx.protect(&mut data1).unwrap();
+        assert_eq!(data1[0], 0x91);
+        assert_eq!(data1[1], 0x01);
+        assert_eq!(profile_rx.check(&data1).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile1_basic_nibble_example() {
+        let config = Profile1Config {
+            mode: Profile1IdMode::Nibble,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile1::new(config.clone()).unwrap();
+        let mut profile_rx = Profile1::new(config).unwrap();
+
+        let mut data1 = vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        profile_tx.protect(&mut data1).unwrap();
+        assert_eq!(data1[0], 0x2a);
+        assert_eq!(data1[1], 0x10);
+        assert_eq!(profile_rx.check(&data1).unwrap(), E2EStatus::Ok);
+
+        profile_tx.protect(&mut data1).unwrap();
+        assert_eq!(data1[0], 0x77);
+        assert_eq!(data1[1], 0x11);
+        assert_eq!(profile_rx.check(&data1).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile1_basic_alt_example() {
+        let config = Profile1Config {
+            mode: Profile1IdMode::Alt,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile1::new(config.clone()).unwrap();
+        let mut profile_rx = Profile1::new(config).unwrap();
+
+        let mut data1 = vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        profile_tx.protect(&mut data1).unwrap();
+        assert_eq!(data1[0], 0xce);
+        assert_eq!(data1[1], 0x00);
+        assert_eq!(profile_rx.check(&data1).unwrap(), E2EStatus::Ok);
+
+        profile_tx.protect(&mut data1).unwrap();
+        assert_eq!(data1[0], 0x02);
+        assert_eq!(data1[1], 0x01);
+        assert_eq!(profile_rx.check(&data1).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile1_basic_low_example() {
+        let config = Profile1Config {
+            mode: Profile1IdMode::Low,
+            data_id: 0x23,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile1::new(config.clone()).unwrap();
+        let mut profile_rx = Profile1::new(config).unwrap();
+
+        let mut data1 = vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        profile_tx.protect(&mut data1).unwrap();
+        assert_eq!(data1[0], 0xce);
+        assert_eq!(data1[1], 0x00);
+        assert_eq!(profile_rx.check(&data1).unwrap(), E2EStatus::Ok);
+
+        profile_tx.protect(&mut data1).unwrap();
+        assert_eq!(data1[0], 0x93);
+        assert_eq!(data1[1], 0x01);
+        assert_eq!(profile_rx.check(&data1).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile1_offset_nibble_example() {
+        let config = Profile1Config {
+            max_delta_counter: 1,
+            crc_offset: 64,
+            counter_offset: 72,
+            nibble_offset: 76,
+            data_length: 128,
+            mode: Profile1IdMode::Nibble,
+            data_id: 0x123,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile1::new(config.clone()).unwrap();
+        let mut profile_rx = Profile1::new(config).unwrap();
+
+        let mut data1 = vec![
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ];
+        profile_tx.protect(&mut data1).unwrap();
+        assert_eq!(data1[8], 0x7d);
+        assert_eq!(data1[9], 0x10);
+        assert_eq!(profile_rx.check(&data1).unwrap(), E2EStatus::Ok);
+    }
+    #[test]
+    fn test_profile1_alt_wrong_id_byte() {
+        let config = Profile1Config {
+            mode: Profile1IdMode::Alt,
+            ..Default::default()
+        };
+        let mut profile_tx = Profile1::new(config.clone()).unwrap();
+        let mut profile_rx = Profile1::new(Profile1Config {
+            data_id: 0x223,
+            ..config
+        })
+        .unwrap();
+
+        let mut data = vec![0x00; 8];
+        // even counter uses the low byte, which is identical
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        // odd counter uses the high byte, which differs
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::CrcError);
+    }
+    #[test]
+    fn test_profile1_invalid_config() {
+        assert!(Profile1::new(Profile1Config {
+            mode: Profile1IdMode::Low,
+            data_id: 0x123,
+            ..Default::default()
+        })
+        .is_err());
+        assert!(Profile1::new(Profile1Config {
+            mode: Profile1IdMode::Nibble,
+            data_id: 0x1123,
+            ..Default::default()
+        })
+        .is_err());
+        assert!(Profile1::new(Profile1Config {
+            max_delta_counter: 15,
+            ..Default::default()
+        })
+        .is_err());
+    }
+    #[test]
+    fn test_profile1_wrong_sequence() {
+        let mut tx = Profile1::new(Profile1Config::default()).unwrap();
+        let mut rx = Profile1::new(Profile1Config::default()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        tx.protect(&mut data).unwrap();
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Repeated);
+
+        // Counter jump a lot
+        tx.counter = (tx.counter + 3) % COUNTER_MODULO;
+        tx.protect(&mut data).unwrap();
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+    }
+
+    #[test]
+    fn test_profile1_max_delta_counter_grows() {
+        let mut tx = Profile1::new(Profile1Config::default()).unwrap();
+        let mut rx = Profile1::new(Profile1Config::default()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        tx.protect(&mut data).unwrap();
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Ok);
+        // two cycles with repeated data widen the accepted delta
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Repeated);
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Repeated);
+        // skip two messages: delta 3 is accepted after the two extra cycles
+        for _ in 0..3 {
+            tx.protect(&mut data).unwrap();
+        }
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::OkSomeLost);
+        // accepted delta is reset to its initial value
+        for _ in 0..3 {
+            tx.protect(&mut data).unwrap();
+        }
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+    }
+
+    #[test]
+    fn test_profile1_sync_after_wrong_sequence() {
+        let config = Profile1Config {
+            sync_counter: 2,
+            ..Default::default()
+        };
+        let mut tx = Profile1::new(config.clone()).unwrap();
+        let mut rx = Profile1::new(config).unwrap();
+
+        let mut data = vec![0x00; 8];
+        tx.protect(&mut data).unwrap();
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Ok);
+        for _ in 0..5 {
+            tx.protect(&mut data).unwrap();
+        }
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+        tx.protect(&mut data).unwrap();
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Sync);
+        tx.protect(&mut data).unwrap();
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Sync);
+        tx.protect(&mut data).unwrap();
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile1_sync_after_repeated_data() {
+        let config = Profile1Config {
+            max_no_new_or_repeated_data: 1,
+            sync_counter: 1,
+            ..Default::default()
+        };
+        let mut tx = Profile1::new(config.clone()).unwrap();
+        let mut rx = Profile1::new(config).unwrap();
+
+        let mut data = vec![0x00; 8];
+        tx.protect(&mut data).unwrap();
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Repeated);
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Repeated);
+        tx.protect(&mut data).unwrap();
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Sync);
+        tx.protect(&mut data).unwrap();
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile1_no_new_data() {
+        let config = Profile1Config {
+            max_no_new_or_repeated_data: 1,
+            sync_counter: 1,
+            ..Default::default()
+        };
+        let mut tx = Profile1::new(config.clone()).unwrap();
+        let mut rx = Profile1::new(config).unwrap();
+
+        let mut data = vec![0x00; 8];
+        tx.protect(&mut data).unwrap();
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(rx.check_no_data().unwrap(), E2EStatus::NoNewData);
+        assert_eq!(rx.check_no_data().unwrap(), E2EStatus::NoNewData);
+        // the lost messages are tolerated after two cycles without data,
+        // but the receiver has to resynchronize
+        for _ in 0..3 {
+            tx.protect(&mut data).unwrap();
+        }
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Sync);
+        tx.protect(&mut data).unwrap();
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile1_join_running_communication() {
+        let mut tx = Profile1::new(Profile1Config::default()).unwrap();
+        let mut rx = Profile1::new(Profile1Config::default()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        for _ in 0..8 {
+            tx.protect(&mut data).unwrap();
+        }
+        // the first received counter is 7
+        assert_eq!(Profile1Header::at(&rx.config, &data).counter(), 7);
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Ok);
+        tx.protect(&mut data).unwrap();
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(rx.check(&data).unwrap(), E2EStatus::Repeated);
+    }
+}
@@ -17,19 +17,26 @@
 //! - **Both(11A)**: full 16-bit Data-ID is implicit (only used in CRC).
 //! - **Nibble(11C)**: high 4-bit is explicit in the header (1..=0xE recommended), low 8-bit is implicit (in CRC).
 
-use crate::{E2EError, E2EProfile, E2EResult, E2EStatus};
+use crate::profiles::{check_field_bounds, ForwardAction, SyncState};
+use crate::snapshot::Fingerprint;
+use crate::{
+    E2ECheckReport, E2EError, E2EForward, E2EHeaderFields, E2EHeaderLayout, E2EProfile, E2EResult,
+    E2ESnapshot, E2EStatus, E2ESyncPolicy, OffsetField,
+};
+use core::ops::Range;
 use crc::{Algorithm, Crc};
 
 // Constants
 const NIBBLE_MASK: u8 = 0x0F;
 const COUNTER_MAX: u8 = 14;
 const COUNTER_MODULO: u8 = 15;
+const MIN_DATA_LENGTH_BITS: u8 = 16;
 const MAX_DATA_LENGTH_BITS: u8 = 240;
 const BITS_PER_BYTE: u8 = 8;
 const BITS_PER_NIBBLE: u8 = 4;
 
-// Profile 11 uses CRC-8-SAE-J1850 with custom parameters
-const CRC8_ALGO: Algorithm<u8> = Algorithm {
+// Profiles 1 and 11 use CRC-8-SAE-J1850 with custom parameters
+pub(crate) const CRC8_ALGO: Algorithm<u8> = Algorithm {
     width: 8,
     poly: 0x1d,
     init: 0x00,
@@ -52,6 +59,7 @@ const CRC8_ALGO: Algorithm<u8> = Algorithm {
 ///   stored explicitly in the header, while the lower 8 bits are used
 ///   implicitly for CRC calculation. Recommended range: 0x100-0xE00.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Profile11IdMode {
     Both,
     Nibble, // Only lower 12 bits used: 0x000..=0xFFF
@@ -59,6 +67,8 @@ pub enum Profile11IdMode {
 
 /// Configuration for E2E Profile 11
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(remote = "Self", default))]
 pub struct Profile11Config {
     /// Bit offset of Counter in MSB first order
     pub counter_offset: u8,
@@ -74,6 +84,8 @@ pub struct Profile11Config {
     pub max_delta_counter: u8,
     /// data length (up to DEFAULT_MAX_DATA_LENGTH bytes)
     pub data_length: u8,
+    /// Receiver behaviour at startup and after a loss of synchronization
+    pub sync_policy: E2ESyncPolicy,
 }
 
 impl Default for Profile11Config {
@@ -86,10 +98,14 @@ impl Default for Profile11Config {
             nibble_offset: 12, // bits
             max_delta_counter: 1,
             data_length: 64, // bits
+            sync_policy: E2ESyncPolicy::default(),
         }
     }
 }
 
+#[cfg(feature = "serde")]
+impl_validated_serde!(Profile11Config, Profile11::validate_config);
+
 pub struct Profile11Check {
     rx_counter: u8,
     rx_crc: u8,
@@ -104,48 +120,76 @@ pub struct Profile11Check {
 pub struct Profile11 {
     config: Profile11Config,
     counter: u8,
-    initialized: bool,
+    sync: SyncState,
 }
 
 impl Profile11 {
     /// Validate configuration parameters
     fn validate_config(config: &Profile11Config) -> E2EResult<()> {
-        if config.data_length > MAX_DATA_LENGTH_BITS {
-            return Err(E2EError::InvalidConfiguration(format!(
-                "Maximum data length for Profile 11 is {} bits",
-                MAX_DATA_LENGTH_BITS
-            )));
+        if MAX_DATA_LENGTH_BITS < config.data_length {
+            return Err(E2EError::MaxDataLengthExceeded {
+                profile: "Profile 11",
+                max: MAX_DATA_LENGTH_BITS as usize,
+                actual: config.data_length as usize,
+            });
         }
 
         if !config.data_length.is_multiple_of(BITS_PER_BYTE) {
-            return Err(E2EError::InvalidConfiguration(
-                "Data length shall be a multiple of 8".into(),
-            ));
+            return Err(E2EError::UnalignedDataLength);
+        }
+
+        if config.data_length < MIN_DATA_LENGTH_BITS {
+            return Err(E2EError::DataLengthOutOfRange {
+                min: (MIN_DATA_LENGTH_BITS / BITS_PER_BYTE) as usize,
+                max: (MAX_DATA_LENGTH_BITS / BITS_PER_BYTE) as usize,
+                actual: config.data_length as usize,
+            });
         }
 
         if config.max_delta_counter == 0 || config.max_delta_counter > COUNTER_MAX {
-            return Err(E2EError::InvalidConfiguration(format!(
-                "Max delta counter must be between 1 and {}",
-                COUNTER_MAX
-            )));
+            return Err(E2EError::MaxDeltaCounterInvalid {
+                max: COUNTER_MAX as u32,
+            });
         }
 
         if !config.counter_offset.is_multiple_of(BITS_PER_NIBBLE) {
-            return Err(E2EError::InvalidConfiguration(
-                "Counter offset shall be a multiple of 4".into(),
-            ));
+            return Err(E2EError::UnalignedOffset {
+                field: OffsetField::Counter,
+                alignment: BITS_PER_NIBBLE as usize,
+            });
         }
 
         if !config.crc_offset.is_multiple_of(BITS_PER_BYTE) {
-            return Err(E2EError::InvalidConfiguration(
-                "Crc offset shall be a multiple of 8".into(),
-            ));
+            return Err(E2EError::UnalignedOffset {
+                field: OffsetField::Crc,
+                alignment: BITS_PER_BYTE as usize,
+            });
         }
 
         if config.mode == Profile11IdMode::Nibble && !config.nibble_offset.is_multiple_of(4) {
-            return Err(E2EError::InvalidConfiguration(
-                "Nibble offset must be a multiple of 4 bits".into(),
-            ));
+            return Err(E2EError::UnalignedOffset {
+                field: OffsetField::Nibble,
+                alignment: BITS_PER_NIBBLE as usize,
+            });
+        }
+
+        let data_length = config.data_length as usize;
+        check_field_bounds(
+            config.counter_offset as usize,
+            BITS_PER_NIBBLE as usize,
+            data_length,
+        )?;
+        check_field_bounds(
+            config.crc_offset as usize,
+            BITS_PER_BYTE as usize,
+            data_length,
+        )?;
+        if config.mode == Profile11IdMode::Nibble {
+            check_field_bounds(
+                config.nibble_offset as usize,
+                BITS_PER_NIBBLE as usize,
+                data_length,
+            )?;
         }
 
         Ok(())
@@ -154,10 +198,11 @@ impl Profile11 {
     fn validate_length(&self, len: usize) -> E2EResult<()> {
         let expected_bytes = (self.config.data_length / BITS_PER_BYTE) as usize;
         if len != expected_bytes {
-            return Err(E2EError::InvalidDataFormat(format!(
-                "Expected {} bytes, got {} bytes",
-                expected_bytes, len
-            )));
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: expected_bytes,
+                expected_max: expected_bytes,
+                actual: len,
+            });
         }
         Ok(())
     }
@@ -169,46 +214,15 @@ impl Profile11 {
         let val = (set_value & NIBBLE_MASK) << shift;
         data[byte_idx] = (data[byte_idx] & mask) | val;
     }
-    fn read_nibble_data(&self, offset: u8, data: &[u8]) -> u8 {
-        let byte_idx = (offset >> 3) as usize;
-        let shift = offset & 0x07;
-
-        (data[byte_idx] >> shift) & NIBBLE_MASK
-    }
     fn write_crc(&self, calculated_crc: u8, data: &mut [u8]) {
         let byte_position = (self.config.crc_offset / BITS_PER_BYTE) as usize;
         data[byte_position] = calculated_crc;
     }
     fn read_crc(&self, data: &[u8]) -> u8 {
-        let byte_position = (self.config.crc_offset / BITS_PER_BYTE) as usize;
-        data[byte_position]
-    }
-    /// Update Crc with ID
-    fn update_crc_with_id(&self, digest: &mut crc::Digest<u8>) {
-        match self.config.mode {
-            Profile11IdMode::Both => {
-                digest.update(&self.config.data_id.to_le_bytes());
-            }
-            Profile11IdMode::Nibble => {
-                digest.update(&[self.config.data_id.to_le_bytes()[0], 0x00]);
-            }
-        }
-    }
-    fn update_crc_with_data(&self, digest: &mut crc::Digest<u8>, data: &[u8]) {
-        if self.config.crc_offset > 0 {
-            let offset_byte = (self.config.crc_offset / BITS_PER_BYTE) as usize;
-            digest.update(&data[0..offset_byte]);
-            digest.update(&data[(offset_byte + 1)..]);
-        } else {
-            digest.update(&data[1..]);
-        }
+        Profile11Header::at(&self.config, data).crc()
     }
     fn compute_crc(&self, data: &[u8]) -> u8 {
-        let crc: Crc<u8> = Crc::<u8>::new(&CRC8_ALGO);
-        let mut digest = crc.digest();
-        self.update_crc_with_id(&mut digest);
-        self.update_crc_with_data(&mut digest, data);
-        digest.finalize()
+        compute_crc(&self.config, data)
     }
     fn increment_counter(&mut self) {
         self.counter = (self.counter + 1) % COUNTER_MODULO;
@@ -235,22 +249,44 @@ impl Profile11 {
             (COUNTER_MODULO + received_counter - self.counter) % COUNTER_MODULO
         }
     }
-    fn validate_counter(&self, rx_counter: u8) -> E2EStatus {
+    fn validate_counter(&mut self, rx_counter: u8) -> E2EStatus {
         let delta = self.check_counter_delta(rx_counter);
-
-        if delta == 0 {
-            if self.initialized {
-                E2EStatus::Repeated
-            } else {
-                E2EStatus::Ok
-            }
-        } else if delta == 1 {
-            E2EStatus::Ok
-        } else if delta >= 2 && delta <= self.config.max_delta_counter {
-            E2EStatus::OkSomeLost
-        } else {
-            E2EStatus::WrongSequence
-        }
+        self.sync.check_delta(
+            &self.config.sync_policy,
+            delta.into(),
+            self.config.max_delta_counter.into(),
+        )
+    }
+    /// Read the header fields of the protected data without checking them
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn decode(&self, data: &[u8]) -> E2EResult<E2EHeaderFields> {
+        self.validate_length(data.len())?;
+        let header = Profile11Header::at(&self.config, data);
+        Ok(E2EHeaderFields {
+            counter: header.counter() as u32,
+            data_id: header.data_id_nibble().map(|nibble| (nibble as u32) << 8),
+            received_crc: header.crc() as u64,
+            computed_crc: self.compute_crc(data) as u64,
+            ..Default::default()
+        })
+    }
+    /// Check the protected data like `check` and report the decoded header,
+    /// the counter delta and the first failed check
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn check_detailed(&mut self, data: &[u8]) -> E2EResult<E2ECheckReport> {
+        let previous_counter = self.counter.into();
+        let header = self.decode(data)?;
+        let status = self.check(data)?;
+        Ok(E2ECheckReport::new(
+            status,
+            header,
+            previous_counter,
+            COUNTER_MODULO.into(),
+        ))
     }
 }
 
@@ -263,7 +299,7 @@ impl E2EProfile for Profile11 {
         Ok(Self {
             config,
             counter: 0,
-            initialized: false,
+            sync: SyncState::default(),
         })
     }
 
@@ -286,18 +322,197 @@ impl E2EProfile for Profile11 {
     fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus> {
         // Check data length
         self.validate_length(data.len())?;
+        let header = Profile11Header::at(&self.config, data);
         let check_items = Profile11Check {
-            rx_nibble: self.read_nibble_data(self.config.nibble_offset, data),
-            rx_counter: self.read_nibble_data(self.config.counter_offset, data),
-            rx_crc: self.read_crc(data),
+            rx_nibble: header.data_id_nibble().unwrap_or_default(),
+            rx_counter: header.counter(),
+            rx_crc: header.crc(),
             calculated_crc: self.compute_crc(data),
         };
         let status = self.do_checks(check_items);
-        if !self.initialized && matches!(status, E2EStatus::Ok | E2EStatus::OkSomeLost) {
-            self.initialized = true;
-        }
         Ok(status)
     }
+
+    fn protect_init(&mut self) {
+        self.counter = 0;
+    }
+
+    fn check_init(&mut self) {
+        self.counter = 0;
+        self.sync = SyncState::default();
+    }
+
+    fn snapshot(&self) -> E2ESnapshot {
+        self.sync.save(E2ESnapshot::new(
+            fingerprint(&self.config),
+            self.counter.into(),
+        ))
+    }
+
+    fn restore(&mut self, snapshot: &E2ESnapshot) -> E2EResult<()> {
+        snapshot.validate(fingerprint(&self.config), COUNTER_MAX.into())?;
+        self.sync = SyncState::load(&self.config.sync_policy, snapshot)?;
+        self.counter = snapshot.counter as u8;
+        Ok(())
+    }
+}
+
+impl E2EForward for Profile11 {
+    fn forward(&mut self, data: &mut [u8], status: E2EStatus) -> E2EResult<()> {
+        self.validate_length(data.len())?;
+        let action = ForwardAction::from_status_with_delta(
+            status,
+            self.config.max_delta_counter.into(),
+            COUNTER_MAX.into(),
+        )?;
+        match action {
+            ForwardAction::Repeat => self.counter = (self.counter + COUNTER_MAX) % COUNTER_MODULO,
+            ForwardAction::Skip => {
+                self.counter = (self.counter + self.config.max_delta_counter) % COUNTER_MODULO
+            }
+            ForwardAction::Protect | ForwardAction::CorruptCrc => {}
+        }
+        self.protect(data)?;
+        if action == ForwardAction::CorruptCrc {
+            let calculated_crc = self.read_crc(data);
+            self.write_crc(!calculated_crc, data);
+        }
+        Ok(())
+    }
+}
+
+impl E2EHeaderLayout for Profile11 {
+    fn header_offset(&self) -> usize {
+        Profile11Header::at(&self.config, &[]).bytes().start * BITS_PER_BYTE as usize
+    }
+
+    fn header_length(&self) -> usize {
+        Profile11Header::at(&self.config, &[]).bytes().len() * BITS_PER_BYTE as usize
+    }
+}
+
+/// Zero-copy view of the E2E header of Profile 11 protected data
+///
+/// The view only reads the data, so frames can be inspected without a
+/// profile instance and without affecting any receiver state.
+#[derive(Debug, Clone, Copy)]
+pub struct Profile11Header<'a> {
+    data: &'a [u8],
+    counter_offset: u8,
+    crc_offset: u8,
+    nibble_offset: Option<u8>,
+}
+
+impl<'a> Profile11Header<'a> {
+    /// Locate the header in `data` as configured
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+    pub fn new(config: &Profile11Config, data: &'a [u8]) -> E2EResult<Self> {
+        let header = Self::at(config, data);
+        let header_end = header.bytes().end;
+        if data.len() < header_end {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: header_end,
+                expected_max: usize::MAX,
+                actual: data.len(),
+            });
+        }
+        Ok(header)
+    }
+    pub(crate) fn at(config: &Profile11Config, data: &'a [u8]) -> Self {
+        Self {
+            data,
+            counter_offset: config.counter_offset,
+            crc_offset: config.crc_offset,
+            nibble_offset: (config.mode == Profile11IdMode::Nibble).then_some(config.nibble_offset),
+        }
+    }
+    /// Bytes holding the CRC, the counter and the Data ID nibble
+    fn bytes(&self) -> Range<usize> {
+        let bytes = [
+            Some(self.counter_offset),
+            Some(self.crc_offset),
+            self.nibble_offset,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|offset| (offset / BITS_PER_BYTE) as usize);
+        let start = bytes.clone().min().unwrap_or_default();
+        let end = bytes.max().unwrap_or_default() + 1;
+        start..end
+    }
+    fn read_nibble(&self, offset: u8) -> u8 {
+        let byte_idx = (offset >> 3) as usize;
+        let shift = offset & 0x07;
+
+        (self.data[byte_idx] >> shift) & NIBBLE_MASK
+    }
+    /// Sequence counter
+    pub fn counter(&self) -> u8 {
+        self.read_nibble(self.counter_offset)
+    }
+    /// Low nibble of the high byte of the Data ID, only transmitted in
+    /// `Profile11IdMode::Nibble`
+    pub fn data_id_nibble(&self) -> Option<u8> {
+        self.nibble_offset.map(|offset| self.read_nibble(offset))
+    }
+    /// CRC as received
+    pub fn crc(&self) -> u8 {
+        self.data[(self.crc_offset / BITS_PER_BYTE) as usize]
+    }
+}
+
+/// Update Crc with ID
+fn update_crc_with_id(config: &Profile11Config, digest: &mut crc::Digest<u8>) {
+    match config.mode {
+        Profile11IdMode::Both => {
+            digest.update(&config.data_id.to_le_bytes());
+        }
+        Profile11IdMode::Nibble => {
+            digest.update(&[config.data_id.to_le_bytes()[0], 0x00]);
+        }
+    }
+}
+fn update_crc_with_data(config: &Profile11Config, digest: &mut crc::Digest<u8>, data: &[u8]) {
+    if config.crc_offset > 0 {
+        let offset_byte = (config.crc_offset / BITS_PER_BYTE) as usize;
+        digest.update(&data[0..offset_byte]);
+        digest.update(&data[(offset_byte + 1)..]);
+    } else {
+        digest.update(&data[1..]);
+    }
+}
+
+pub(crate) fn compute_crc(config: &Profile11Config, data: &[u8]) -> u8 {
+    let crc: Crc<u8> = Crc::<u8>::new(&CRC8_ALGO);
+    let mut digest = crc.digest();
+    update_crc_with_id(config, &mut digest);
+    update_crc_with_data(config, &mut digest, data);
+    digest.finalize()
+}
+
+/// Verify the CRC of Profile 11 protected data without a profile instance
+///
+/// # Errors
+/// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+pub fn verify_crc(config: &Profile11Config, data: &[u8]) -> E2EResult<bool> {
+    let header = Profile11Header::new(config, data)?;
+    Ok(header.crc() == compute_crc(config, data))
+}
+
+/// Fingerprint of the configuration, see `E2ESnapshot`
+fn fingerprint(config: &Profile11Config) -> u32 {
+    Fingerprint::new("Profile 11")
+        .field(&config.counter_offset.to_be_bytes())
+        .field(&config.crc_offset.to_be_bytes())
+        .field(&[config.mode as u8])
+        .field(&config.data_id.to_be_bytes())
+        .field(&config.nibble_offset.to_be_bytes())
+        .field(&config.max_delta_counter.to_be_bytes())
+        .field(&config.data_length.to_be_bytes())
+        .field(&config.sync_policy.to_bytes())
+        .finalize()
 }
 
 #[cfg(test)]
@@ -351,6 +566,34 @@ mod tests {
         assert_eq!(profile_rx.check(&data1).unwrap(), E2EStatus::Ok);
     }
     #[test]
+    fn test_profile11_header_view() {
+        let config = Profile11Config {
+            mode: Profile11IdMode::Nibble,
+            data_id: 0x123,
+            ..Default::default()
+        };
+        let mut profile_tx = Profile11::new(config.clone()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        let header = Profile11Header::new(&config, &data).unwrap();
+        assert_eq!(header.crc(), 0x77);
+        assert_eq!(header.counter(), 1);
+        assert_eq!(header.data_id_nibble(), Some(0x1));
+        assert!(verify_crc(&config, &data).unwrap());
+        data[0] ^= 0xFF;
+        assert!(!verify_crc(&config, &data).unwrap());
+
+        let config = Profile11Config {
+            mode: Profile11IdMode::Both,
+            ..Default::default()
+        };
+        let header = Profile11Header::new(&config, &data).unwrap();
+        assert_eq!(header.data_id_nibble(), None);
+        assert!(Profile11Header::new(&config, &data[..1]).is_err());
+    }
+    #[test]
     fn test_profile11_offset_nibble_example() {
         let config = Profile11Config {
             max_delta_counter: 1,
@@ -360,6 +603,7 @@ mod tests {
             data_length: 128,
             mode: Profile11IdMode::Nibble,
             data_id: 0x123,
+            ..Default::default()
         };
 
         let mut profile_tx = Profile11::new(config.clone()).unwrap();
@@ -389,10 +633,7 @@ mod tests {
         for _ in 0..=COUNTER_MAX + 1 {
             profile.protect(&mut data).unwrap();
         }
-        assert_eq!(
-            profile.read_nibble_data(profile.config.counter_offset, &data),
-            0x00
-        );
+        assert_eq!(Profile11Header::at(&profile.config, &data).counter(), 0x00);
     }
     #[test]
     fn test_profile11_some_lost_ok() {
@@ -439,4 +680,70 @@ mod tests {
         assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
         assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
     }
+
+    #[test]
+    fn test_profile11_forward() {
+        let mut profile_tx = Profile11::new(Profile11Config::default()).unwrap();
+        let mut profile_rx = Profile11::new(Profile11Config::default()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.forward(&mut data, E2EStatus::Ok).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::Repeated).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+        profile_tx
+            .forward(&mut data, E2EStatus::OkSomeLost)
+            .unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::CrcError).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::CrcError);
+        profile_tx
+            .forward(&mut data, E2EStatus::WrongSequence)
+            .unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+        assert_eq!(
+            profile_tx.forward(&mut data, E2EStatus::NoNewData),
+            Err(E2EError::InvalidForwardStatus(E2EStatus::NoNewData))
+        );
+    }
+
+    #[test]
+    fn test_profile11_forward_skip_full_window() {
+        // every counter delta but 0 is accepted, a skip would wrap to Repeated
+        let config = Profile11Config {
+            max_delta_counter: COUNTER_MAX,
+            ..Default::default()
+        };
+        let mut profile_tx = Profile11::new(config.clone()).unwrap();
+        let mut profile_rx = Profile11::new(config).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.forward(&mut data, E2EStatus::Ok).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(
+            profile_tx.forward(&mut data, E2EStatus::WrongSequence),
+            Err(E2EError::InvalidForwardStatus(E2EStatus::WrongSequence))
+        );
+        // the rejected forward leaves the sender state untouched
+        profile_tx.forward(&mut data, E2EStatus::Ok).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile11_first_message() {
+        // the first message is checked against the initial counter 0, so only
+        // a sender starting from it is accepted
+        let config = Profile11Config::default();
+        let mut profile_tx = Profile11::new(config.clone()).unwrap();
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile11::new(config.clone()).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile11::new(config).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+    }
 }
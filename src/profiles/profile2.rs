@@ -0,0 +1,620 @@
+//! # E2E Profile 2 Implementation
+//!
+//! Profile 2 is designed for protecting small data packets
+//! with low overhead. It uses:
+//! - 8-bit CRC (CRC-8H2F) for data integrity
+//! - 4-bit counter for sequence checking (0-15)
+//! - 8-bit Data ID selected from a list of 16 Data IDs by the counter value
+//!
+//! # Data layout
+//! [CRC(1B) | HDR(1B) | DATA ...]
+//! - HDR (bits 3..0) : counter
+//!
+//! # Receiver behaviour
+//!
+//! Unlike the other profiles, the receiver of Profile 2 widens the accepted
+//! counter delta by one on every check cycle without a valid message, and
+//! requires `sync_counter` consecutive valid messages after a counter
+//! discontinuity before reporting `Ok` again.
+//! The `E2ESyncPolicy` applies on top of this; by default it accepts the
+//! first message with any counter, as specified for Profile 2.
+
+use crate::profiles::{CounterWindow, NO_NEW_OR_REPEATED_DATA_MAX};
+use crate::snapshot::Fingerprint;
+use crate::{
+    E2ECheckReport, E2EError, E2EHeaderFields, E2EHeaderLayout, E2EProfile, E2EResult, E2ESnapshot,
+    E2EStatus, E2ESyncPolicy,
+};
+use crc::{Crc, CRC_8_AUTOSAR};
+
+// Constants
+const COUNTER_MASK: u8 = 0x0F;
+const COUNTER_MAX: u8 = 15;
+const COUNTER_MODULO: u8 = 16;
+const BITS_PER_BYTE: usize = 8;
+const HEADER_LENGTH: usize = 2;
+const DATA_ID_NUMBER: usize = 16;
+const MIN_DATA_LENGTH_BITS: usize = 2 * BITS_PER_BYTE;
+const MAX_DATA_LENGTH_BITS: usize = 256 * BITS_PER_BYTE;
+
+/// Configuration for E2E Profile 2
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(remote = "Self", default))]
+pub struct Profile2Config {
+    /// Length of Data, in bits. The value shall be a multiple of 8.
+    pub data_length: usize,
+    /// An array of appropriately chosen Data IDs for protection against masquerading.
+    pub data_id_list: [u8; DATA_ID_NUMBER],
+    /// Initial maximum allowed delta between consecutive counters
+    pub max_delta_counter: u8,
+    /// Maximum amount of missing or repeated data the receiver tolerates
+    /// before it has to resynchronize
+    pub max_no_new_or_repeated_data: u8,
+    /// Number of consecutive valid messages required to resynchronize
+    pub sync_counter: u8,
+    /// Receiver behaviour at startup and after a loss of synchronization,
+    /// applied in addition to the synchronization of Profile 2
+    pub sync_policy: E2ESyncPolicy,
+}
+
+impl Default for Profile2Config {
+    fn default() -> Self {
+        Self {
+            data_length: 64, // bits
+            data_id_list: [
+                0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+                0x0f, 0x10,
+            ],
+            max_delta_counter: 1,
+            max_no_new_or_repeated_data: NO_NEW_OR_REPEATED_DATA_MAX,
+            sync_counter: 0,
+            sync_policy: E2ESyncPolicy::accept_any_first(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl_validated_serde!(Profile2Config, Profile2::validate_config);
+
+/// Check Item for E2E Profile 2
+#[derive(Debug, Clone)]
+pub struct Profile2Check {
+    rx_counter: u8,
+    rx_crc: u8,
+    calculated_crc: u8,
+}
+
+/// E2E Profile 2 Implementation
+///
+/// Implements AUTOSAR E2E Profile 2 protection mechanism
+#[derive(Clone)]
+pub struct Profile2 {
+    config: Profile2Config,
+    counter: u8,
+    window: CounterWindow,
+}
+
+impl Profile2 {
+    /// Validate configuration parameters
+    fn validate_config(config: &Profile2Config) -> E2EResult<()> {
+        if !config.data_length.is_multiple_of(BITS_PER_BYTE) {
+            return Err(E2EError::UnalignedDataLength);
+        }
+
+        if config.data_length < MIN_DATA_LENGTH_BITS || MAX_DATA_LENGTH_BITS < config.data_length {
+            return Err(E2EError::DataLengthOutOfRange {
+                min: MIN_DATA_LENGTH_BITS / BITS_PER_BYTE,
+                max: MAX_DATA_LENGTH_BITS / BITS_PER_BYTE,
+                actual: config.data_length,
+            });
+        }
+
+        if config.max_delta_counter == 0 || config.max_delta_counter > COUNTER_MAX {
+            return Err(E2EError::MaxDeltaCounterInvalid {
+                max: COUNTER_MAX as u32,
+            });
+        }
+
+        if config.max_no_new_or_repeated_data > NO_NEW_OR_REPEATED_DATA_MAX {
+            return Err(E2EError::MaxNoNewOrRepeatedDataInvalid {
+                max: NO_NEW_OR_REPEATED_DATA_MAX.into(),
+            });
+        }
+
+        if config.sync_counter > COUNTER_MAX {
+            return Err(E2EError::SyncCounterInvalid {
+                max: COUNTER_MAX.into(),
+            });
+        }
+
+        Ok(())
+    }
+    /// Validate data length
+    fn validate_length(&self, len: usize) -> E2EResult<()> {
+        let expected_bytes = self.config.data_length / BITS_PER_BYTE;
+        if len != expected_bytes {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: expected_bytes,
+                expected_max: expected_bytes,
+                actual: len,
+            });
+        }
+        Ok(())
+    }
+    fn increment_counter(&mut self) {
+        self.counter = (self.counter + 1) % COUNTER_MODULO;
+    }
+    fn write_counter(&self, data: &mut [u8]) {
+        data[1] = (data[1] & 0xF0) | self.counter;
+    }
+    fn write_crc(&self, calculated_crc: u8, data: &mut [u8]) {
+        data[0] = calculated_crc;
+    }
+    fn compute_crc(&self, data: &[u8]) -> u8 {
+        compute_crc(&self.config, data)
+    }
+    fn do_checks(&mut self, check_items: Profile2Check) -> E2EStatus {
+        if check_items.calculated_crc != check_items.rx_crc {
+            return E2EStatus::CrcError;
+        }
+        self.window.check(
+            &self.config.sync_policy,
+            &mut self.counter,
+            check_items.rx_counter,
+        )
+    }
+    /// Read the header fields of the protected data without checking them
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn decode(&self, data: &[u8]) -> E2EResult<E2EHeaderFields> {
+        self.validate_length(data.len())?;
+        let header = Profile2Header::at(&self.config, data);
+        Ok(E2EHeaderFields {
+            counter: header.counter() as u32,
+            received_crc: header.crc() as u64,
+            computed_crc: self.compute_crc(data) as u64,
+            ..Default::default()
+        })
+    }
+    /// Check the protected data like `check` and report the decoded header,
+    /// the counter delta and the first failed check
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn check_detailed(&mut self, data: &[u8]) -> E2EResult<E2ECheckReport> {
+        let previous_counter = self.counter.into();
+        let header = self.decode(data)?;
+        let status = self.check(data)?;
+        Ok(E2ECheckReport::new(
+            status,
+            header,
+            previous_counter,
+            COUNTER_MODULO.into(),
+        ))
+    }
+}
+
+impl E2EProfile for Profile2 {
+    type Config = Profile2Config;
+
+    fn new(config: Self::Config) -> E2EResult<Self> {
+        // Validate config
+        Self::validate_config(&config)?;
+        Ok(Self {
+            window: CounterWindow::new(
+                COUNTER_MAX,
+                config.max_delta_counter,
+                config.max_no_new_or_repeated_data,
+                config.sync_counter,
+            ),
+            config,
+            counter: 0,
+        })
+    }
+
+    fn protect(&mut self, data: &mut [u8]) -> E2EResult<()> {
+        self.validate_length(data.len())?;
+        self.increment_counter();
+        self.write_counter(data);
+        let calculated_crc = self.compute_crc(data);
+        self.write_crc(calculated_crc, data);
+        Ok(())
+    }
+
+    fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus> {
+        // Check data length
+        self.validate_length(data.len())?;
+        // The accepted delta grows with every check cycle until a valid message arrives
+        self.window.next_cycle();
+        let header = Profile2Header::at(&self.config, data);
+        let check_items = Profile2Check {
+            rx_counter: header.counter(),
+            rx_crc: header.crc(),
+            calculated_crc: self.compute_crc(data),
+        };
+        let status = self.do_checks(check_items);
+        Ok(status)
+    }
+
+    fn check_no_data(&mut self) -> E2EResult<E2EStatus> {
+        // A cycle without data widens the accepted delta like any other check cycle
+        self.window.no_data();
+        Ok(E2EStatus::NoNewData)
+    }
+
+    fn protect_init(&mut self) {
+        self.counter = 0;
+    }
+
+    fn check_init(&mut self) {
+        self.counter = 0;
+        self.window.reset();
+    }
+
+    fn snapshot(&self) -> E2ESnapshot {
+        self.window.save(E2ESnapshot::new(
+            fingerprint(&self.config),
+            self.counter.into(),
+        ))
+    }
+
+    fn restore(&mut self, snapshot: &E2ESnapshot) -> E2EResult<()> {
+        snapshot.validate(fingerprint(&self.config), COUNTER_MAX.into())?;
+        self.window.load(&self.config.sync_policy, snapshot)?;
+        self.counter = snapshot.counter as u8;
+        Ok(())
+    }
+}
+
+impl E2EHeaderLayout for Profile2 {
+    fn header_offset(&self) -> usize {
+        0
+    }
+
+    fn header_length(&self) -> usize {
+        HEADER_LENGTH * BITS_PER_BYTE
+    }
+}
+
+/// Zero-copy view of the E2E header of Profile 2 protected data
+///
+/// The view only reads the data, so frames can be inspected without a
+/// profile instance and without affecting any receiver state.
+#[derive(Debug, Clone, Copy)]
+pub struct Profile2Header<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Profile2Header<'a> {
+    /// Locate the header in `data` as configured
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+    pub fn new(config: &Profile2Config, data: &'a [u8]) -> E2EResult<Self> {
+        let header_end = HEADER_LENGTH;
+        if data.len() < header_end {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: header_end,
+                expected_max: usize::MAX,
+                actual: data.len(),
+            });
+        }
+        Ok(Self::at(config, data))
+    }
+    pub(crate) fn at(_config: &Profile2Config, data: &'a [u8]) -> Self {
+        Self { data }
+    }
+    /// Sequence counter
+    pub fn counter(&self) -> u8 {
+        self.data[1] & COUNTER_MASK
+    }
+    /// CRC as received
+    pub fn crc(&self) -> u8 {
+        self.data[0]
+    }
+}
+
+/// Compute the CRC, using the Data ID selected by the counter in the data
+pub(crate) fn compute_crc(config: &Profile2Config, data: &[u8]) -> u8 {
+    let crc: Crc<u8> = Crc::<u8>::new(&CRC_8_AUTOSAR);
+    let mut digest = crc.digest();
+    digest.update(&data[1..]); // crc calculation data after crc
+    digest.update(&[config.data_id_list[Profile2Header::at(config, data).counter() as usize]]); // crc calculation data id
+    digest.finalize()
+}
+
+/// Verify the CRC of Profile 2 protected data without a profile instance
+///
+/// # Errors
+/// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+pub fn verify_crc(config: &Profile2Config, data: &[u8]) -> E2EResult<bool> {
+    let header = Profile2Header::new(config, data)?;
+    Ok(header.crc() == compute_crc(config, data))
+}
+
+/// Fingerprint of the configuration, see `E2ESnapshot`
+fn fingerprint(config: &Profile2Config) -> u32 {
+    Fingerprint::new("Profile 2")
+        .field(&(config.data_length as u64).to_be_bytes())
+        .field(&config.data_id_list)
+        .field(&config.max_delta_counter.to_be_bytes())
+        .field(&config.max_no_new_or_repeated_data.to_be_bytes())
+        .field(&config.sync_counter.to_be_bytes())
+        .field(&config.sync_policy.to_bytes())
+        .finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::E2ECheckItem;
+    /// Profile 2 frames whose CRC input, the data after the CRC followed by
+    /// the Data ID, is one of the CRC-8H2F examples of the AUTOSAR CRC library
+    /// specification
+    const CRC_LIBRARY_FRAMES: [(u8, &[u8]); 7] = [
+        // (Data ID, [CRC, counter, data ...])
+        (0x00, &[0x12, 0x00, 0x00, 0x00]),
+        (0x83, &[0xC2, 0xF2, 0x01]),
+        (0x55, &[0xC6, 0x0F, 0xAA, 0x00]),
+        (0x11, &[0x77, 0x00, 0xFF, 0x55]),
+        (
+            0xFF,
+            &[0x11, 0x33, 0x22, 0x55, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE],
+        ),
+        (0x55, &[0x33, 0x92, 0x6B]),
+        (0xFF, &[0x6C, 0xFF, 0xFF, 0xFF]),
+    ];
+
+    fn crc_library_config(data_id: u8, frame: &[u8]) -> Profile2Config {
+        let mut data_id_list = [0x00; DATA_ID_NUMBER];
+        data_id_list[(frame[1] & COUNTER_MASK) as usize] = data_id;
+        Profile2Config {
+            data_length: frame.len() * BITS_PER_BYTE,
+            data_id_list,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_profile2_crc_library_example() {
+        for (data_id, frame) in CRC_LIBRARY_FRAMES {
+            let config = crc_library_config(data_id, frame);
+            let mut profile_tx = Profile2::new(config.clone()).unwrap();
+            let mut profile_rx = Profile2::new(config.clone()).unwrap();
+
+            let mut data = frame.to_vec();
+            data[0] = 0x00;
+            // the counter is incremented before it is written
+            profile_tx.counter = ((frame[1] & COUNTER_MASK) + COUNTER_MAX) % COUNTER_MODULO;
+            profile_tx.protect(&mut data).unwrap();
+            assert_eq!(data, frame);
+            assert!(verify_crc(&config, frame).unwrap());
+            assert_eq!(profile_rx.check(frame).unwrap(), E2EStatus::Ok);
+        }
+    }
+
+    #[test]
+    fn test_profile2_data_id_selected_by_counter() {
+        let (data_id, frame) = CRC_LIBRARY_FRAMES[1];
+        let config = crc_library_config(data_id, frame);
+        let mut profile_tx = Profile2::new(config.clone()).unwrap();
+        let mut profile_rx = Profile2::new(config).unwrap();
+
+        // counter 1 uses Data ID 0x00, counter 2 uses 0x83
+        let mut data = frame.to_vec();
+        for counter in 1..3 {
+            profile_tx.protect(&mut data).unwrap();
+            assert_eq!(data[1], 0xF0 | counter);
+            assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        }
+        assert_eq!(data, frame);
+
+        // the same frame is rejected if counter 2 selects another Data ID
+        let mut profile_rx = Profile2::new(crc_library_config(0x84, frame)).unwrap();
+        assert_eq!(profile_rx.check(frame).unwrap(), E2EStatus::CrcError);
+    }
+
+    #[test]
+    fn test_profile2_upper_nibble_preserved() {
+        let mut profile_tx = Profile2::new(Profile2Config::default()).unwrap();
+        let mut profile_rx = Profile2::new(Profile2Config::default()).unwrap();
+
+        let mut data = vec![0x00, 0xA0, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(data[1], 0xA1);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        data[2] ^= 0x01;
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::CrcError);
+    }
+
+    #[test]
+    fn test_profile2_max_delta_counter_grows() {
+        let mut profile_tx = Profile2::new(Profile2Config::default()).unwrap();
+        let mut profile_rx = Profile2::new(Profile2Config::default()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        // two cycles with repeated data widen the accepted delta
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+        // skip two messages: delta 3 is accepted after the two extra cycles
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::OkSomeLost);
+        // accepted delta is reset to its initial value
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+    }
+
+    #[test]
+    fn test_profile2_wrong_sequence_without_sync() {
+        let mut profile_tx = Profile2::new(Profile2Config::default()).unwrap();
+        let mut profile_rx = Profile2::new(Profile2Config::default()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        for _ in 0..5 {
+            profile_tx.protect(&mut data).unwrap();
+        }
+        // the last valid counter is kept, the accepted delta keeps growing
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::OkSomeLost);
+    }
+
+    #[test]
+    fn test_profile2_sync_after_wrong_sequence() {
+        let config = Profile2Config {
+            sync_counter: 2,
+            ..Default::default()
+        };
+        let mut profile_tx = Profile2::new(config.clone()).unwrap();
+        let mut profile_rx = Profile2::new(config).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        for _ in 0..5 {
+            profile_tx.protect(&mut data).unwrap();
+        }
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Sync);
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Sync);
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile2_sync_after_repeated_data() {
+        let config = Profile2Config {
+            max_no_new_or_repeated_data: 1,
+            sync_counter: 1,
+            ..Default::default()
+        };
+        let mut profile_tx = Profile2::new(config.clone()).unwrap();
+        let mut profile_rx = Profile2::new(config).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Sync);
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile2_no_new_data() {
+        let config = Profile2Config {
+            max_no_new_or_repeated_data: 1,
+            sync_counter: 1,
+            ..Default::default()
+        };
+        let mut profile_tx = Profile2::new(config.clone()).unwrap();
+        let mut profile_rx = Profile2::new(config).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.check_no_data().unwrap(), E2EStatus::NoNewData);
+        assert_eq!(profile_rx.check_no_data().unwrap(), E2EStatus::NoNewData);
+        // the lost message is tolerated after two cycles without data,
+        // but the receiver has to resynchronize
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Sync);
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile2_check_detailed() {
+        let mut profile_tx = Profile2::new(Profile2Config::default()).unwrap();
+        let mut profile_rx = Profile2::new(Profile2Config::default()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::Repeated);
+        assert_eq!(report.failed_check, Some(E2ECheckItem::Counter));
+        assert_eq!(report.previous_counter, 1);
+        assert_eq!(report.counter_delta, 0);
+        assert_eq!(report.header.length, None);
+        // the counter wraps around at 16
+        for _ in 0..15 {
+            profile_tx.protect(&mut data).unwrap();
+        }
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.header.counter, 0);
+        assert_eq!(report.counter_delta, 15);
+    }
+
+    #[test]
+    fn test_profile2_snapshot_restore() {
+        let config = Profile2Config {
+            sync_counter: 2,
+            ..Default::default()
+        };
+        let mut profile_tx = Profile2::new(config.clone()).unwrap();
+        let mut profile_rx = Profile2::new(config.clone()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        // a counter jump starts the resynchronization
+        for _ in 0..5 {
+            profile_tx.protect(&mut data).unwrap();
+        }
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+        profile_rx.check_no_data().unwrap();
+
+        let snapshot = profile_rx.snapshot();
+        assert_eq!(snapshot.receiver_state, [2, 1, 2]);
+        let mut profile_restored = Profile2::new(config.clone()).unwrap();
+        profile_restored.restore(&snapshot).unwrap();
+        for _ in 0..3 {
+            profile_tx.protect(&mut data).unwrap();
+            assert_eq!(
+                profile_restored.check(&data).unwrap(),
+                profile_rx.check(&data).unwrap()
+            );
+        }
+
+        let invalid = E2ESnapshot {
+            receiver_state: [2, 1, 3],
+            ..snapshot
+        };
+        assert_eq!(
+            profile_restored.restore(&invalid),
+            Err(E2EError::SnapshotInvalid)
+        );
+    }
+
+    #[test]
+    fn test_profile2_first_message() {
+        // any first message is accepted, e.g. when joining a running
+        // communication
+        let mut profile_tx = Profile2::new(Profile2Config::default()).unwrap();
+        let mut data = vec![0x00; 8];
+        for _ in 0..5 {
+            profile_tx.protect(&mut data).unwrap();
+        }
+        let mut profile_rx = Profile2::new(Profile2Config::default()).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+}
@@ -10,7 +10,12 @@
 //! [DATA ... | CRC(1B) | HDR(1B) | DATA ...]
 //! - HDR (bits 3..0) : counter
 
-use crate::{E2EError, E2EProfile, E2EResult, E2EStatus};
+use crate::profiles::{ForwardAction, SyncState};
+use crate::snapshot::Fingerprint;
+use crate::{
+    E2ECheckReport, E2EError, E2EForward, E2EHeaderFields, E2EHeaderLayout, E2EProfile, E2EResult,
+    E2ESnapshot, E2EStatus, E2ESyncPolicy,
+};
 use crc::{Crc, CRC_8_AUTOSAR};
 
 // Constants
@@ -18,10 +23,14 @@ const COUNTER_MASK: u8 = 0x0F;
 const COUNTER_MAX: u8 = 15;
 const COUNTER_MODULO: u8 = 16;
 const BITS_PER_BYTE: usize = 8;
+const HEADER_LENGTH: usize = 2;
 const DATA_ID_NUMBER: usize = 16;
+const MIN_DATA_LENGTH_BITS: usize = 2 * BITS_PER_BYTE;
 
 /// Configuration for E2E Profile 22
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(remote = "Self", default))]
 pub struct Profile22Config {
     /// Length of Data, in bits. The value shall be a multiple of 8.
     pub data_length: usize,
@@ -31,6 +40,8 @@ pub struct Profile22Config {
     pub max_delta_counter: u8,
     /// Bit offset of E2E header in the Data[] array in bits.
     pub offset: usize,
+    /// Receiver behaviour at startup and after a loss of synchronization
+    pub sync_policy: E2ESyncPolicy,
 }
 
 impl Default for Profile22Config {
@@ -43,10 +54,14 @@ impl Default for Profile22Config {
             ],
             max_delta_counter: 1,
             offset: 0, // bits
+            sync_policy: E2ESyncPolicy::default(),
         }
     }
 }
 
+#[cfg(feature = "serde")]
+impl_validated_serde!(Profile22Config, Profile22::validate_config);
+
 /// Check Item for E2E Profile 22
 #[derive(Debug, Clone)]
 pub struct Profile22Check {
@@ -61,22 +76,35 @@ pub struct Profile22Check {
 pub struct Profile22 {
     config: Profile22Config,
     counter: u8,
+    sync: SyncState,
 }
 
 impl Profile22 {
     /// Validate configuration parameters
     fn validate_config(config: &Profile22Config) -> E2EResult<()> {
         if !config.data_length.is_multiple_of(BITS_PER_BYTE) {
-            return Err(E2EError::InvalidConfiguration(
-                "Data length shall be a multiple of 8".into(),
-            ));
+            return Err(E2EError::UnalignedDataLength);
+        }
+
+        if config.data_length < MIN_DATA_LENGTH_BITS {
+            return Err(E2EError::DataLengthOutOfRange {
+                min: MIN_DATA_LENGTH_BITS / BITS_PER_BYTE,
+                max: usize::MAX,
+                actual: config.data_length,
+            });
+        }
+
+        if config.data_length - MIN_DATA_LENGTH_BITS < config.offset {
+            return Err(E2EError::OffsetBeyondHeader {
+                offset: config.offset,
+                header_length: HEADER_LENGTH,
+            });
         }
 
         if config.max_delta_counter == 0 || config.max_delta_counter > COUNTER_MAX {
-            return Err(E2EError::InvalidConfiguration(format!(
-                "Max delta counter must be between 1 and {}",
-                COUNTER_MAX
-            )));
+            return Err(E2EError::MaxDeltaCounterInvalid {
+                max: COUNTER_MAX as u32,
+            });
         }
 
         Ok(())
@@ -85,10 +113,11 @@ impl Profile22 {
     fn validate_length(&self, len: usize) -> E2EResult<()> {
         let expected_bytes = self.config.data_length / BITS_PER_BYTE;
         if len != expected_bytes {
-            return Err(E2EError::InvalidDataFormat(format!(
-                "Expected {} bytes, got {} bytes",
-                expected_bytes, len
-            )));
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: expected_bytes,
+                expected_max: expected_bytes,
+                actual: len,
+            });
         }
         Ok(())
     }
@@ -100,27 +129,15 @@ impl Profile22 {
 
         data[byte_idx + 1] = (data[byte_idx + 1] & 0xF0) | self.counter;
     }
-    fn read_counter(&self, data: &[u8]) -> u8 {
-        let byte_idx = self.config.offset >> 3;
-
-        data[byte_idx + 1] & COUNTER_MASK
-    }
     fn write_crc(&self, calculated_crc: u8, data: &mut [u8]) {
         let byte_position = self.config.offset / BITS_PER_BYTE;
         data[byte_position] = calculated_crc;
     }
     fn read_crc(&self, data: &[u8]) -> u8 {
-        let byte_position = self.config.offset / BITS_PER_BYTE;
-        data[byte_position]
+        Profile22Header::at(&self.config, data).crc()
     }
     fn compute_crc(&self, data: &[u8]) -> u8 {
-        let crc: Crc<u8> = Crc::<u8>::new(&CRC_8_AUTOSAR);
-        let mut digest = crc.digest();
-        let offset_byte = self.config.offset / BITS_PER_BYTE;
-        digest.update(&data[0..offset_byte]); // crc calculation data before offset
-        digest.update(&data[(offset_byte + 1)..]); // crc calculation data after offset
-        digest.update(&[self.config.data_id_list[self.read_counter(data) as usize]]); // crc calculation data id
-        digest.finalize()
+        compute_crc(&self.config, data)
     }
     fn do_checks(&mut self, check_items: Profile22Check) -> E2EStatus {
         if check_items.calculated_crc != check_items.rx_crc {
@@ -139,18 +156,43 @@ impl Profile22 {
             (COUNTER_MODULO + received_counter - self.counter) % COUNTER_MODULO
         }
     }
-    fn validate_counter(&self, rx_counter: u8) -> E2EStatus {
+    fn validate_counter(&mut self, rx_counter: u8) -> E2EStatus {
         let delta = self.check_counter_delta(rx_counter);
-
-        if delta == 0 {
-            E2EStatus::Repeated
-        } else if delta == 1 {
-            E2EStatus::Ok
-        } else if delta >= 2 && delta <= self.config.max_delta_counter {
-            E2EStatus::OkSomeLost
-        } else {
-            E2EStatus::WrongSequence
-        }
+        self.sync.check_delta(
+            &self.config.sync_policy,
+            delta.into(),
+            self.config.max_delta_counter.into(),
+        )
+    }
+    /// Read the header fields of the protected data without checking them
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn decode(&self, data: &[u8]) -> E2EResult<E2EHeaderFields> {
+        self.validate_length(data.len())?;
+        let header = Profile22Header::at(&self.config, data);
+        Ok(E2EHeaderFields {
+            counter: header.counter() as u32,
+            received_crc: header.crc() as u64,
+            computed_crc: self.compute_crc(data) as u64,
+            ..Default::default()
+        })
+    }
+    /// Check the protected data like `check` and report the decoded header,
+    /// the counter delta and the first failed check
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn check_detailed(&mut self, data: &[u8]) -> E2EResult<E2ECheckReport> {
+        let previous_counter = self.counter.into();
+        let header = self.decode(data)?;
+        let status = self.check(data)?;
+        Ok(E2ECheckReport::new(
+            status,
+            header,
+            previous_counter,
+            COUNTER_MODULO.into(),
+        ))
     }
 }
 
@@ -160,7 +202,11 @@ impl E2EProfile for Profile22 {
     fn new(config: Self::Config) -> E2EResult<Self> {
         // Validate config
         Self::validate_config(&config)?;
-        Ok(Self { config, counter: 0 })
+        Ok(Self {
+            config,
+            counter: 0,
+            sync: SyncState::default(),
+        })
     }
 
     fn protect(&mut self, data: &mut [u8]) -> E2EResult<()> {
@@ -175,14 +221,145 @@ impl E2EProfile for Profile22 {
     fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus> {
         // Check data length
         self.validate_length(data.len())?;
+        let header = Profile22Header::at(&self.config, data);
         let check_items = Profile22Check {
-            rx_counter: self.read_counter(data),
-            rx_crc: self.read_crc(data),
+            rx_counter: header.counter(),
+            rx_crc: header.crc(),
             calculated_crc: self.compute_crc(data),
         };
         let status = self.do_checks(check_items);
         Ok(status)
     }
+
+    fn protect_init(&mut self) {
+        self.counter = 0;
+    }
+
+    fn check_init(&mut self) {
+        self.counter = 0;
+        self.sync = SyncState::default();
+    }
+
+    fn snapshot(&self) -> E2ESnapshot {
+        self.sync.save(E2ESnapshot::new(
+            fingerprint(&self.config),
+            self.counter.into(),
+        ))
+    }
+
+    fn restore(&mut self, snapshot: &E2ESnapshot) -> E2EResult<()> {
+        snapshot.validate(fingerprint(&self.config), COUNTER_MAX.into())?;
+        self.sync = SyncState::load(&self.config.sync_policy, snapshot)?;
+        self.counter = snapshot.counter as u8;
+        Ok(())
+    }
+}
+
+impl E2EForward for Profile22 {
+    fn forward(&mut self, data: &mut [u8], status: E2EStatus) -> E2EResult<()> {
+        self.validate_length(data.len())?;
+        let action = ForwardAction::from_status_with_delta(
+            status,
+            self.config.max_delta_counter.into(),
+            COUNTER_MAX.into(),
+        )?;
+        match action {
+            ForwardAction::Repeat => self.counter = (self.counter + COUNTER_MAX) % COUNTER_MODULO,
+            ForwardAction::Skip => {
+                self.counter = (self.counter + self.config.max_delta_counter) % COUNTER_MODULO
+            }
+            ForwardAction::Protect | ForwardAction::CorruptCrc => {}
+        }
+        self.protect(data)?;
+        if action == ForwardAction::CorruptCrc {
+            let calculated_crc = self.read_crc(data);
+            self.write_crc(!calculated_crc, data);
+        }
+        Ok(())
+    }
+}
+
+impl E2EHeaderLayout for Profile22 {
+    fn header_offset(&self) -> usize {
+        self.config.offset
+    }
+
+    fn header_length(&self) -> usize {
+        HEADER_LENGTH * BITS_PER_BYTE
+    }
+}
+
+/// Zero-copy view of the E2E header of Profile 22 protected data
+///
+/// The view only reads the data, so frames can be inspected without a
+/// profile instance and without affecting any receiver state.
+#[derive(Debug, Clone, Copy)]
+pub struct Profile22Header<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Profile22Header<'a> {
+    /// Locate the header in `data` as configured
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+    pub fn new(config: &Profile22Config, data: &'a [u8]) -> E2EResult<Self> {
+        let header_end = config.offset / BITS_PER_BYTE + HEADER_LENGTH;
+        if data.len() < header_end {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: header_end,
+                expected_max: usize::MAX,
+                actual: data.len(),
+            });
+        }
+        Ok(Self::at(config, data))
+    }
+    pub(crate) fn at(config: &Profile22Config, data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: config.offset / BITS_PER_BYTE,
+        }
+    }
+    /// Sequence counter
+    pub fn counter(&self) -> u8 {
+        self.data[self.offset + 1] & COUNTER_MASK
+    }
+    /// CRC as received
+    pub fn crc(&self) -> u8 {
+        self.data[self.offset]
+    }
+}
+
+/// Compute the CRC, using the Data ID selected by the counter in the data
+pub(crate) fn compute_crc(config: &Profile22Config, data: &[u8]) -> u8 {
+    let crc: Crc<u8> = Crc::<u8>::new(&CRC_8_AUTOSAR);
+    let mut digest = crc.digest();
+    let offset_byte = config.offset / BITS_PER_BYTE;
+    digest.update(&data[0..offset_byte]); // crc calculation data before offset
+    digest.update(&data[(offset_byte + 1)..]); // crc calculation data after offset
+    digest.update(&[config.data_id_list[Profile22Header::at(config, data).counter() as usize]]); // crc calculation data id
+    digest.finalize()
+}
+
+/// Verify the CRC of Profile 22 protected data without a profile instance
+///
+/// # Errors
+/// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+pub fn verify_crc(config: &Profile22Config, data: &[u8]) -> E2EResult<bool> {
+    let header = Profile22Header::new(config, data)?;
+    Ok(header.crc() == compute_crc(config, data))
+}
+
+/// Fingerprint of the configuration, see `E2ESnapshot`
+fn fingerprint(config: &Profile22Config) -> u32 {
+    Fingerprint::new("Profile 22")
+        .field(&(config.data_length as u64).to_be_bytes())
+        .field(&config.data_id_list)
+        .field(&config.max_delta_counter.to_be_bytes())
+        .field(&(config.offset as u64).to_be_bytes())
+        .field(&config.sync_policy.to_bytes())
+        .finalize()
 }
 
 #[cfg(test)]
@@ -280,4 +457,70 @@ mod tests {
         assert_eq!(data1[9], 0x01);
         assert_eq!(profile_rx.check(&data1).unwrap(), E2EStatus::Ok);
     }
+
+    #[test]
+    fn test_profile22_forward() {
+        let mut profile_tx = Profile22::new(Profile22Config::default()).unwrap();
+        let mut profile_rx = Profile22::new(Profile22Config::default()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.forward(&mut data, E2EStatus::Ok).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::Repeated).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+        profile_tx
+            .forward(&mut data, E2EStatus::OkSomeLost)
+            .unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::CrcError).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::CrcError);
+        profile_tx
+            .forward(&mut data, E2EStatus::WrongSequence)
+            .unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+        assert_eq!(
+            profile_tx.forward(&mut data, E2EStatus::NoNewData),
+            Err(E2EError::InvalidForwardStatus(E2EStatus::NoNewData))
+        );
+    }
+
+    #[test]
+    fn test_profile22_forward_skip_full_window() {
+        // every counter delta but 0 is accepted, a skip would wrap to Repeated
+        let config = Profile22Config {
+            max_delta_counter: COUNTER_MAX,
+            ..Default::default()
+        };
+        let mut profile_tx = Profile22::new(config.clone()).unwrap();
+        let mut profile_rx = Profile22::new(config).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.forward(&mut data, E2EStatus::Ok).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(
+            profile_tx.forward(&mut data, E2EStatus::WrongSequence),
+            Err(E2EError::InvalidForwardStatus(E2EStatus::WrongSequence))
+        );
+        // the rejected forward leaves the sender state untouched
+        profile_tx.forward(&mut data, E2EStatus::Ok).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile22_first_message() {
+        // the first message is checked against the initial counter 0, so only
+        // a sender starting from it is accepted
+        let config = Profile22Config::default();
+        let mut profile_tx = Profile22::new(config.clone()).unwrap();
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile22::new(config.clone()).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile22::new(config).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+    }
 }
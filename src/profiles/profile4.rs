@@ -9,16 +9,17 @@
 //!
 //! # Data layout
 //! [DATA ... | LENGTH(2B) | COUNTER(2B) | ID (4B) | CRC(4B) | DATA ...]
-use crate::{E2EError, E2EProfile, E2EResult, E2EStatus};
-use crc::{Crc, CRC_32_AUTOSAR};
-
-// Constants
-const BITS_PER_BYTE: u16 = 8;
-const COUNTER_MAX: u16 = 0xFFFF;
-const COUNTER_MODULO: u32 = 0x10000;
+//!
+//! The implementation is shared with Profile 44, see `profile4x`.
+use crate::profiles::profile4x::sealed::Layout;
+pub use crate::profiles::profile4x::verify_crc;
+use crate::profiles::profile4x::{Profile4x, Profile4xHeader};
+use crate::E2ESyncPolicy;
 
 /// Configuration for E2E Profile 4
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(remote = "Self", default))]
 pub struct Profile4Config {
     /// data id
     pub data_id: u32,
@@ -30,17 +31,8 @@ pub struct Profile4Config {
     pub max_data_length: u16,
     /// Maximum allowed delta between consecutive counters
     pub max_delta_counter: u16,
-}
-
-/// Check Item for E2E Profile 4
-#[derive(Debug, Clone)]
-pub struct Profile4Check {
-    rx_data_length: u16,
-    rx_counter: u16,
-    rx_data_id: u32,
-    rx_crc: u32,
-    calculated_crc: u32,
-    data_len: u16,
+    /// Receiver behaviour at startup and after a loss of synchronization
+    pub sync_policy: E2ESyncPolicy,
 }
 
 impl Default for Profile4Config {
@@ -51,199 +43,62 @@ impl Default for Profile4Config {
             min_data_length: 96,    // 12bytes
             max_data_length: 32768, // 4096bytes
             max_delta_counter: 1,
+            sync_policy: E2ESyncPolicy::default(),
         }
     }
 }
 
-/// E2E Profile 4 Implementation
-///
-/// Implements AUTOSAR E2E Profile 4 protection mechanism
-#[derive(Clone)]
-pub struct Profile4 {
-    config: Profile4Config,
-    counter: u16,
-    initialized: bool,
-}
+#[cfg(feature = "serde")]
+impl_validated_serde!(Profile4Config, Profile4::validate_config);
 
-impl Profile4 {
-    /// Validate configuration parameters
-    fn validate_config(config: &Profile4Config) -> E2EResult<()> {
-        if config.min_data_length < 12 * BITS_PER_BYTE
-            || 4096 * BITS_PER_BYTE < config.min_data_length
-        {
-            return Err(E2EError::InvalidConfiguration(
-                "Minimum Data length shall be between 12B and 4096B".into(),
-            ));
-        }
-        if config.max_data_length < config.min_data_length || 4096 * 8 < config.max_data_length {
-            return Err(E2EError::InvalidConfiguration(
-                "Maximum Data length shall be between MinDataLength and 4096B".into(),
-            ));
-        }
-        if config.max_delta_counter == 0 || config.max_delta_counter == COUNTER_MAX {
-            return Err(E2EError::InvalidConfiguration(format!(
-                "Max delta counter must be between 1 and {}",
-                COUNTER_MAX
-            )));
-        }
-        Ok(())
-    }
-    /// Validate data length against min/max constraints
-    fn validate_length(&self, len: u16) -> E2EResult<()> {
-        let min_bytes = self.config.min_data_length / BITS_PER_BYTE;
-        let max_bytes = self.config.max_data_length / BITS_PER_BYTE;
-        if len < min_bytes || max_bytes < len {
-            return Err(E2EError::InvalidDataFormat(format!(
-                "Expected {} - {} bytes, got {} bytes",
-                min_bytes, max_bytes, len
-            )));
-        }
-        Ok(())
-    }
-    fn increment_counter(&mut self) {
-        self.counter = (self.counter as u32 + 1) as u16 & COUNTER_MAX;
-    }
-    fn write_data_length(&self, data: &mut [u8]) {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        let len16 = data.len() as u16;
-        data[offset..=offset + 1].copy_from_slice(&len16.to_be_bytes());
-    }
-    fn write_counter(&self, data: &mut [u8]) {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        data[offset + 2..=offset + 3].copy_from_slice(&self.counter.to_be_bytes());
-    }
-    fn write_data_id(&self, data: &mut [u8]) {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        data[offset + 4..=offset + 7].copy_from_slice(&self.config.data_id.to_be_bytes());
+impl Layout for Profile4Config {
+    const NAME: &'static str = "Profile 4";
+    const NAME_M: &'static str = "Profile 4M";
+    const LENGTH_WIDTH: usize = 2;
+    const MAX_DATA_LENGTH: usize = 4096;
+    type Length = u16;
+
+    fn length(value: u32) -> u16 {
+        value as u16
     }
-    fn compute_crc(&self, data: &[u8]) -> u32 {
-        let crc: Crc<u32> = Crc::<u32>::new(&CRC_32_AUTOSAR);
-        let mut digest = crc.digest();
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        digest.update(&data[0..offset + 8]); // crc calculation data before offset
-        digest.update(&data[(offset + 12)..]); // crc calculation data after offset
-        digest.finalize()
+    fn data_id(&self) -> u32 {
+        self.data_id
     }
-    fn write_crc(&self, calculated_crc: u32, data: &mut [u8]) {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        data[offset + 8..=offset + 11].copy_from_slice(&calculated_crc.to_be_bytes());
+    fn offset(&self) -> usize {
+        self.offset as usize
     }
-    fn read_data_length(&self, data: &[u8]) -> u16 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        u16::from_be_bytes([data[offset], data[offset + 1]])
+    fn min_data_length(&self) -> usize {
+        self.min_data_length as usize
     }
-    fn read_counter(&self, data: &[u8]) -> u16 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        u16::from_be_bytes([data[offset + 2], data[offset + 3]])
+    fn max_data_length(&self) -> usize {
+        self.max_data_length as usize
     }
-    fn read_data_id(&self, data: &[u8]) -> u32 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        u32::from_be_bytes([
-            data[offset + 4],
-            data[offset + 5],
-            data[offset + 6],
-            data[offset + 7],
-        ])
+    fn set_min_data_length(&mut self, bits: usize) {
+        self.min_data_length = bits as u16;
     }
-    fn read_crc(&self, data: &[u8]) -> u32 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        u32::from_be_bytes([
-            data[offset + 8],
-            data[offset + 9],
-            data[offset + 10],
-            data[offset + 11],
-        ])
+    fn max_delta_counter(&self) -> u16 {
+        self.max_delta_counter
     }
-
-    fn do_checks(&mut self, check_items: Profile4Check) -> E2EStatus {
-        if check_items.calculated_crc != check_items.rx_crc {
-            return E2EStatus::CrcError;
-        }
-        if check_items.rx_data_id != self.config.data_id {
-            return E2EStatus::DataIdError;
-        }
-        if check_items.rx_data_length != check_items.data_len {
-            return E2EStatus::DataLengthError;
-        }
-        let status = self.validate_counter(check_items.rx_counter);
-        self.counter = check_items.rx_counter;
-        status
-    }
-    /// Check if counter delta is within acceptable range
-    fn check_counter_delta(&self, received_counter: u16) -> u16 {
-        if received_counter >= self.counter {
-            received_counter - self.counter
-        } else {
-            // Handle wrap-around
-            ((COUNTER_MODULO + received_counter as u32 - self.counter as u32) % COUNTER_MODULO)
-                as u16
-        }
-    }
-    fn validate_counter(&self, rx_counter: u16) -> E2EStatus {
-        let delta = self.check_counter_delta(rx_counter);
-
-        if delta == 0 {
-            if self.initialized {
-                E2EStatus::Repeated
-            } else {
-                E2EStatus::Ok
-            }
-        } else if delta == 1 {
-            E2EStatus::Ok
-        } else if delta >= 2 && delta <= self.config.max_delta_counter {
-            E2EStatus::OkSomeLost
-        } else {
-            E2EStatus::WrongSequence
-        }
+    fn sync_policy(&self) -> &E2ESyncPolicy {
+        &self.sync_policy
     }
 }
 
-impl E2EProfile for Profile4 {
-    type Config = Profile4Config;
-
-    fn new(config: Self::Config) -> E2EResult<Self> {
-        // Validate config
-        Self::validate_config(&config)?;
-        Ok(Self {
-            config,
-            counter: 0,
-            initialized: false,
-        })
-    }
-
-    fn protect(&mut self, data: &mut [u8]) -> E2EResult<()> {
-        self.validate_length(data.len() as u16)?;
-        self.write_data_length(data);
-        self.write_counter(data);
-        self.write_data_id(data);
-        let calculated_crc = self.compute_crc(data);
-        self.write_crc(calculated_crc, data);
-        self.increment_counter();
-        Ok(())
-    }
+/// E2E Profile 4 Implementation
+///
+/// Implements AUTOSAR E2E Profile 4 protection mechanism
+pub type Profile4 = Profile4x<Profile4Config>;
 
-    fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus> {
-        // Check data length
-        self.validate_length(data.len() as u16)?;
-        let check_items = Profile4Check {
-            rx_data_length: self.read_data_length(data),
-            rx_counter: self.read_counter(data),
-            rx_crc: self.read_crc(data),
-            rx_data_id: self.read_data_id(data),
-            calculated_crc: self.compute_crc(data),
-            data_len: data.len() as u16,
-        };
-        let status = self.do_checks(check_items);
-        if !self.initialized && matches!(status, E2EStatus::Ok | E2EStatus::OkSomeLost) {
-            self.initialized = true;
-        }
-        Ok(status)
-    }
-}
+/// Zero-copy view of the E2E header of Profile 4 protected data
+///
+/// The view only reads the data, so frames can be inspected without a
+/// profile instance and without affecting any receiver state.
+pub type Profile4Header<'a> = Profile4xHeader<'a, Profile4Config>;
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{E2ECheckItem, E2EError, E2EForward, E2EProfile, E2ESnapshot, E2EStatus};
     #[test]
     fn test_profile4_basic_example() {
         let mut profile_tx = Profile4::new(Profile4Config::default()).unwrap();
@@ -282,6 +137,7 @@ mod tests {
     fn test_profile4_offset_example() {
         let config = Profile4Config {
             offset: 64,
+            min_data_length: 160,
             ..Default::default()
         };
 
@@ -315,6 +171,7 @@ mod tests {
     fn test_profile4_counter_wraparound() {
         let config = Profile4Config {
             offset: 64,
+            min_data_length: 160,
             ..Default::default()
         };
 
@@ -343,4 +200,197 @@ mod tests {
         assert_eq!(data[11], 0x00);
         assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
     }
+
+    #[test]
+    fn test_profile4_no_new_data() {
+        let mut profile_tx = Profile4::new(Profile4Config::default()).unwrap();
+        let mut profile_rx = Profile4::new(Profile4Config::default()).unwrap();
+
+        let mut data = vec![0x00; 16];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.check_no_data().unwrap(), E2EStatus::NoNewData);
+        assert_eq!(profile_rx.check_no_data().unwrap(), E2EStatus::NoNewData);
+        // cycles without data do not affect the counter delta
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile4_forward() {
+        let mut profile_tx = Profile4::new(Profile4Config::default()).unwrap();
+        let mut profile_rx = Profile4::new(Profile4Config::default()).unwrap();
+
+        let mut data = vec![0x00; 16];
+        profile_tx.forward(&mut data, E2EStatus::Ok).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::Repeated).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+        profile_tx
+            .forward(&mut data, E2EStatus::OkSomeLost)
+            .unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::CrcError).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::CrcError);
+        profile_tx
+            .forward(&mut data, E2EStatus::WrongSequence)
+            .unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+        assert_eq!(
+            profile_tx.forward(&mut data, E2EStatus::NoNewData),
+            Err(E2EError::InvalidForwardStatus(E2EStatus::NoNewData))
+        );
+    }
+
+    #[test]
+    fn test_profile4_check_detailed() {
+        let mut profile_tx = Profile4::new(Profile4Config::default()).unwrap();
+        let mut profile_rx = Profile4::new(Profile4Config::default()).unwrap();
+
+        let mut data = vec![0x00; 16];
+        profile_tx.protect(&mut data).unwrap();
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::Ok);
+        assert_eq!(report.failed_check, None);
+        assert_eq!(report.header.length, Some(16));
+        assert_eq!(report.header.data_id, Some(0x0a0b0c0d));
+        assert_eq!(report.header.received_crc, 0x862b0556);
+        assert_eq!(report.header.computed_crc, 0x862b0556);
+        // two frames lost
+        for _ in 0..3 {
+            profile_tx.protect(&mut data).unwrap();
+        }
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::WrongSequence);
+        assert_eq!(report.failed_check, Some(E2ECheckItem::Counter));
+        assert_eq!(report.previous_counter, 0);
+        assert_eq!(report.header.counter, 3);
+        assert_eq!(report.counter_delta, 3);
+        // the CRC is checked first
+        data[4] ^= 0x01;
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::CrcError);
+        assert_eq!(report.failed_check, Some(E2ECheckItem::Crc));
+        assert_ne!(report.header.received_crc, report.header.computed_crc);
+        assert_eq!(report.header.data_id, Some(0x0b0b0c0d));
+    }
+
+    #[test]
+    fn test_profile4_header_view() {
+        let config = Profile4Config::default();
+        let mut profile_tx = Profile4::new(config.clone()).unwrap();
+        let mut profile_rx = Profile4::new(config.clone()).unwrap();
+
+        let mut data = vec![0x00; 16];
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        let header = Profile4Header::new(&config, &data).unwrap();
+        assert_eq!(header.data_length(), 16);
+        assert_eq!(header.counter(), 1);
+        assert_eq!(header.data_id(), 0x0a0b0c0d);
+        assert!(verify_crc(&config, &data).unwrap());
+        // inspecting the frame leaves the receiver untouched
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.counter, 1);
+
+        data[12] ^= 0x01;
+        assert!(!verify_crc(&config, &data).unwrap());
+        assert_eq!(
+            Profile4Header::new(&config, &data[..11]).unwrap_err(),
+            E2EError::LengthOutOfRange {
+                expected_min: 12,
+                expected_max: usize::MAX,
+                actual: 11,
+            }
+        );
+    }
+
+    #[test]
+    fn test_profile4_snapshot_restore() {
+        let config = Profile4Config::default();
+        let mut profile_tx = Profile4::new(config.clone()).unwrap();
+        let mut profile_rx = Profile4::new(config.clone()).unwrap();
+
+        let mut data = vec![0x00; 16];
+        for _ in 0..5 {
+            profile_tx.protect(&mut data).unwrap();
+            profile_rx.check(&data).unwrap();
+        }
+        let snapshot = profile_rx.snapshot();
+        assert_eq!(snapshot.counter, 4);
+        assert!(snapshot.initialized);
+
+        // a receiver restarting without its state flags the next frame
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_fresh = Profile4::new(config.clone()).unwrap();
+        assert_eq!(
+            profile_fresh.check(&data).unwrap(),
+            E2EStatus::WrongSequence
+        );
+        let mut profile_restored = Profile4::new(config.clone()).unwrap();
+        profile_restored.restore(&snapshot).unwrap();
+        assert_eq!(profile_restored.check(&data).unwrap(), E2EStatus::Ok);
+
+        let other_config = Profile4Config {
+            data_id: 0x01020304,
+            ..config
+        };
+        let mut profile_other = Profile4::new(other_config).unwrap();
+        assert!(matches!(
+            profile_other.restore(&snapshot),
+            Err(E2EError::SnapshotConfigMismatch { .. })
+        ));
+        assert_eq!(profile_other.counter, 0);
+        let out_of_range = E2ESnapshot {
+            counter: 0x10000,
+            ..snapshot
+        };
+        assert_eq!(
+            profile_restored.restore(&out_of_range),
+            Err(E2EError::SnapshotInvalid)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_profile4_serde() {
+        let config = Profile4Config {
+            data_id: 0x12345678,
+            max_delta_counter: 3,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        let config: Profile4Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(config.data_id, 0x12345678);
+        assert_eq!(config.max_delta_counter, 3);
+        // missing fields take their default value
+        let config: Profile4Config = serde_json::from_str(r#"{"data_id": 1}"#).unwrap();
+        assert_eq!(
+            config.max_data_length,
+            Profile4Config::default().max_data_length
+        );
+        // invalid configurations are rejected with the validation message
+        let error = serde_json::from_str::<Profile4Config>(r#"{"max_delta_counter": 0}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("Invalid configuration: Max delta counter must be between 1 and"));
+    }
+
+    #[test]
+    fn test_profile4_first_message() {
+        // the first message is checked against the initial counter 0, so only
+        // a sender starting from it is accepted
+        let config = Profile4Config::default();
+        let mut profile_tx = Profile4::new(config.clone()).unwrap();
+        let mut data = vec![0x00; 16];
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile4::new(config.clone()).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile4::new(config).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+    }
 }
@@ -0,0 +1,235 @@
+//! # E2E Profile 44 Implementation
+//!
+//! Profile 44 is identical to Profile 4 but extends the length field
+//! to protect data packets larger than 4096 bytes. It uses:
+//! - 32-bit CRC for data integrity
+//! - 16-bit counter for sequence checking
+//! - 32-bit Data ID for masquerade prevention
+//! - 32-bit Data Length to support dynamic size data
+//!
+//! # Data layout
+//! [DATA ... | LENGTH(4B) | COUNTER(2B) | ID (4B) | CRC(4B) | DATA ...]
+//!
+//! The implementation is shared with Profile 4, see `profile4x`.
+use crate::profiles::profile4x::sealed::Layout;
+pub use crate::profiles::profile4x::verify_crc;
+use crate::profiles::profile4x::{Profile4x, Profile4xHeader};
+use crate::E2ESyncPolicy;
+
+/// Configuration for E2E Profile 44
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(remote = "Self", default))]
+pub struct Profile44Config {
+    /// data id
+    pub data_id: u32,
+    /// Bit offset of the first bit of the E2E header from the beginning of the Data
+    pub offset: u32,
+    /// Minimal length of Data, in bits
+    pub min_data_length: u32,
+    /// Maximal length of Data, in bits
+    pub max_data_length: u32,
+    /// Maximum allowed delta between consecutive counters
+    pub max_delta_counter: u16,
+    /// Receiver behaviour at startup and after a loss of synchronization
+    pub sync_policy: E2ESyncPolicy,
+}
+
+impl Default for Profile44Config {
+    fn default() -> Self {
+        Self {
+            data_id: 0x0a0b0c0d,
+            offset: 0x00000000,
+            min_data_length: 112,        // 14bytes
+            max_data_length: 4294967295, // MAX(U32)
+            max_delta_counter: 1,
+            sync_policy: E2ESyncPolicy::default(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl_validated_serde!(Profile44Config, Profile44::validate_config);
+
+impl Layout for Profile44Config {
+    const NAME: &'static str = "Profile 44";
+    const NAME_M: &'static str = "Profile 44M";
+    const LENGTH_WIDTH: usize = 4;
+    const MAX_DATA_LENGTH: usize = usize::MAX;
+    type Length = u32;
+
+    fn length(value: u32) -> u32 {
+        value
+    }
+    fn data_id(&self) -> u32 {
+        self.data_id
+    }
+    fn offset(&self) -> usize {
+        self.offset as usize
+    }
+    fn min_data_length(&self) -> usize {
+        self.min_data_length as usize
+    }
+    fn max_data_length(&self) -> usize {
+        self.max_data_length as usize
+    }
+    fn set_min_data_length(&mut self, bits: usize) {
+        self.min_data_length = bits as u32;
+    }
+    fn max_delta_counter(&self) -> u16 {
+        self.max_delta_counter
+    }
+    fn sync_policy(&self) -> &E2ESyncPolicy {
+        &self.sync_policy
+    }
+}
+
+/// E2E Profile 44 Implementation
+///
+/// Implements AUTOSAR E2E Profile 44 protection mechanism
+pub type Profile44 = Profile4x<Profile44Config>;
+
+/// Zero-copy view of the E2E header of Profile 44 protected data
+///
+/// The view only reads the data, so frames can be inspected without a
+/// profile instance and without affecting any receiver state.
+pub type Profile44Header<'a> = Profile4xHeader<'a, Profile44Config>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{E2EProfile, E2EStatus};
+    #[test]
+    fn test_profile44_basic_example() {
+        let mut profile_tx = Profile44::new(Profile44Config::default()).unwrap();
+        let mut profile_rx = Profile44::new(Profile44Config::default()).unwrap();
+
+        let mut data = vec![
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ];
+        profile_tx.protect(&mut data).unwrap();
+        // length check
+        assert_eq!(data[0], 0x00);
+        assert_eq!(data[1], 0x00);
+        assert_eq!(data[2], 0x00);
+        assert_eq!(data[3], 0x10);
+        // counter check
+        assert_eq!(data[4], 0x00);
+        assert_eq!(data[5], 0x00);
+        // data id check
+        assert_eq!(data[6], 0x0a);
+        assert_eq!(data[7], 0x0b);
+        assert_eq!(data[8], 0x0c);
+        assert_eq!(data[9], 0x0d);
+        // crc check
+        assert_eq!(data[10], 0xc2);
+        assert_eq!(data[11], 0x2e);
+        assert_eq!(data[12], 0xef);
+        assert_eq!(data[13], 0x57);
+        // data check
+        assert_eq!(data[14], 0x00);
+        assert_eq!(data[15], 0x00);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile44_offset_example() {
+        let config = Profile44Config {
+            offset: 64,
+            min_data_length: 176,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile44::new(config.clone()).unwrap();
+        let mut profile_rx = Profile44::new(config).unwrap();
+
+        let mut data = vec![
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        profile_tx.protect(&mut data).unwrap();
+        // length check
+        assert_eq!(data[8], 0x00);
+        assert_eq!(data[9], 0x00);
+        assert_eq!(data[10], 0x00);
+        assert_eq!(data[11], 0x18);
+        // counter check
+        assert_eq!(data[12], 0x00);
+        assert_eq!(data[13], 0x00);
+        // data id check
+        assert_eq!(data[14], 0x0a);
+        assert_eq!(data[15], 0x0b);
+        assert_eq!(data[16], 0x0c);
+        assert_eq!(data[17], 0x0d);
+        // crc check
+        assert_eq!(data[18], 0x75);
+        assert_eq!(data[19], 0x3a);
+        assert_eq!(data[20], 0xb8);
+        assert_eq!(data[21], 0x04);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile44_large_payload_example() {
+        let mut profile_tx = Profile44::new(Profile44Config::default()).unwrap();
+        let mut profile_rx = Profile44::new(Profile44Config::default()).unwrap();
+
+        // larger than the 4096 bytes supported by Profile 4
+        let mut data = vec![0x00; 5000];
+        profile_tx.protect(&mut data).unwrap();
+        // length check
+        assert_eq!(data[0], 0x00);
+        assert_eq!(data[1], 0x00);
+        assert_eq!(data[2], 0x13);
+        assert_eq!(data[3], 0x88);
+        // crc check
+        assert_eq!(data[10], 0x77);
+        assert_eq!(data[11], 0x9c);
+        assert_eq!(data[12], 0xe6);
+        assert_eq!(data[13], 0x96);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        data[4999] ^= 0x01;
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::CrcError);
+    }
+
+    #[test]
+    fn test_profile44_counter_wraparound() {
+        let mut profile_tx = Profile44::new(Profile44Config::default()).unwrap();
+        let mut profile_rx = Profile44::new(Profile44Config::default()).unwrap();
+
+        let mut data = vec![0x00; 16];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_rx.counter = 0xFFFE;
+        profile_tx.counter = 0xFFFF;
+        profile_tx.protect(&mut data).unwrap();
+        // counter check
+        assert_eq!(data[4], 0xFF);
+        assert_eq!(data[5], 0xFF);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.protect(&mut data).unwrap();
+        // counter check
+        assert_eq!(data[4], 0x00);
+        assert_eq!(data[5], 0x00);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile44_first_message() {
+        // the first message is checked against the initial counter 0, so only
+        // a sender starting from it is accepted
+        let config = Profile44Config::default();
+        let mut profile_tx = Profile44::new(config.clone()).unwrap();
+        let mut data = vec![0x00; 16];
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile44::new(config.clone()).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile44::new(config).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+    }
+}
@@ -0,0 +1,204 @@
+//! # E2E Profile 44M Implementation
+//!
+//! Profile 44M is identical to Profile 44 but includes additional fields
+//! in CRC calculation: message_type, message_result, and source_id
+//!
+//! The metadata fields extend the header to 18 bytes, which have to fit into
+//! the minimum data length behind the offset.
+//!
+//! The implementation is shared with Profile 4M, see `profile4x`.
+
+pub use crate::profile44::verify_crc; // The CRC covers the metadata fields as data
+use crate::profile44::Profile44Config; // Reuse Profile44Config
+use crate::profiles::profile4x::{Profile4xm, Profile4xmHeader};
+
+/// E2E Profile 44m Implementation - minimal code by reusing Profile44 logic
+pub type Profile44m = Profile4xm<Profile44Config>;
+
+/// Zero-copy view of the E2E header of Profile 44M protected data, including the
+/// metadata fields behind the Profile 44 header
+pub type Profile44mHeader<'a> = Profile4xmHeader<'a, Profile44Config>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::E2ESyncPolicy;
+    use crate::{E2EProfile, E2EStatus};
+    #[test]
+    fn test_profile44m_basic_request_example() {
+        let config = Profile44Config {
+            min_data_length: 160,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile44m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile44m::new(config).unwrap();
+
+        let mut data = vec![
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        profile_tx.source_id = 0x00123456;
+        profile_tx.message_result = 0;
+        profile_tx.message_type = 0;
+        profile_tx.protect(&mut data).unwrap();
+        // length check
+        assert_eq!(data[0], 0x00);
+        assert_eq!(data[1], 0x00);
+        assert_eq!(data[2], 0x00);
+        assert_eq!(data[3], 0x14);
+        // counter check
+        assert_eq!(data[4], 0x00);
+        assert_eq!(data[5], 0x00);
+        // data id check
+        assert_eq!(data[6], 0x0a);
+        assert_eq!(data[7], 0x0b);
+        assert_eq!(data[8], 0x0c);
+        assert_eq!(data[9], 0x0d);
+        // crc check
+        assert_eq!(data[10], 0x07);
+        assert_eq!(data[11], 0x21);
+        assert_eq!(data[12], 0x7e);
+        assert_eq!(data[13], 0xb1);
+        // message type/result/source id check
+        assert_eq!(data[14], 0x00);
+        assert_eq!(data[15], 0x12);
+        assert_eq!(data[16], 0x34);
+        assert_eq!(data[17], 0x56);
+        profile_rx.source_id = 0x00123456;
+        profile_rx.message_result = 0;
+        profile_rx.message_type = 0;
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+    #[test]
+    fn test_profile44m_basic_response_example() {
+        let config = Profile44Config {
+            min_data_length: 160,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile44m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile44m::new(config).unwrap();
+
+        let mut data = vec![
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        profile_tx.source_id = 0x00123456;
+        profile_tx.message_result = 0;
+        profile_tx.message_type = 1;
+        profile_tx.protect(&mut data).unwrap();
+        // crc check
+        assert_eq!(data[10], 0xb2);
+        assert_eq!(data[11], 0xe6);
+        assert_eq!(data[12], 0x02);
+        assert_eq!(data[13], 0xf5);
+        // message type/result/source id check
+        assert_eq!(data[14], 0x40);
+        assert_eq!(data[15], 0x12);
+        assert_eq!(data[16], 0x34);
+        assert_eq!(data[17], 0x56);
+        profile_rx.source_id = 0x00123456;
+        profile_rx.message_result = 0;
+        profile_rx.message_type = 1;
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+    #[test]
+    fn test_profile44m_basic_error_example() {
+        let config = Profile44Config {
+            min_data_length: 160,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile44m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile44m::new(config).unwrap();
+
+        let mut data = vec![
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        profile_tx.source_id = 0x00123456;
+        profile_tx.message_result = 1;
+        profile_tx.message_type = 1;
+        profile_tx.protect(&mut data).unwrap();
+        // crc check
+        assert_eq!(data[10], 0x9f);
+        assert_eq!(data[11], 0x97);
+        assert_eq!(data[12], 0xdd);
+        assert_eq!(data[13], 0xe4);
+        // message type/result/source id check
+        assert_eq!(data[14], 0x50);
+        assert_eq!(data[15], 0x12);
+        assert_eq!(data[16], 0x34);
+        assert_eq!(data[17], 0x56);
+        profile_rx.source_id = 0x00123456;
+        profile_rx.message_result = 1;
+        profile_rx.message_type = 0;
+        assert_eq!(
+            profile_rx.check(&data).unwrap(),
+            E2EStatus::MessageTypeError
+        );
+    }
+
+    #[test]
+    fn test_profile44m_default_config() {
+        // the default of Profile 44 leaves no room for the metadata fields
+        assert!(Profile44m::new(Profile44Config::default()).is_err());
+        let config = Profile44m::default_config();
+        assert_eq!(config.min_data_length, 144);
+        let mut profile_tx = Profile44m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile44m::new(config).unwrap();
+
+        let mut data = vec![0x00; 18];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile44m_first_message() {
+        // the first message is checked against the initial counter 0, so only
+        // a sender starting from it is accepted
+        let config = Profile44m::default_config();
+        let mut profile_tx = Profile44m::new(config.clone()).unwrap();
+        let mut data = vec![0x00; 18];
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile44m::new(config.clone()).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile44m::new(config).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+    }
+
+    #[test]
+    fn test_profile44m_sync_ignores_other_source() {
+        let config = Profile44Config {
+            sync_policy: E2ESyncPolicy {
+                min_consecutive: 2,
+                ..Default::default()
+            },
+            ..Profile44m::default_config()
+        };
+        let mut profile_tx = Profile44m::new(config.clone()).unwrap();
+        let mut profile_other = Profile44m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile44m::new(config).unwrap();
+        profile_other.source_id = 0x00123456;
+
+        // data of another sender is rejected before its counter is evaluated,
+        // so it does not count towards the synchronization
+        let mut data = vec![0x00; 18];
+        profile_other.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::SourceIdError);
+        profile_other.protect(&mut data).unwrap();
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::SourceIdError);
+        assert_eq!(report.header.source_id, Some(0x00123456));
+
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Sync);
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+}
@@ -2,119 +2,37 @@
 //!
 //! Profile 4M is identical to Profile 4 but includes additional fields
 //! in CRC calculation: message_type, message_result, and source_id
+//!
+//! The metadata fields extend the header to 16 bytes, which have to fit into
+//! the minimum data length behind the offset.
+//!
+//! The implementation is shared with Profile 44M, see `profile4x`.
 
-use crate::profile4::{Profile4, Profile4Config}; // Reuse Profile4Config
-use crate::{E2EProfile, E2EResult, E2EStatus};
-
-const BITS_PER_BYTE: u16 = 8;
-
-/// Check Item for E2E Profile 4
-#[derive(Debug, Clone)]
-pub struct Profile4mCheck {
-    rx_source_id: u32,
-    rx_message_type: u8,
-    rx_message_result: u8,
-}
+pub use crate::profile4::verify_crc; // The CRC covers the metadata fields as data
+use crate::profile4::Profile4Config; // Reuse Profile4Config
+use crate::profiles::profile4x::{Profile4xm, Profile4xmHeader};
 
 /// E2E Profile 4m Implementation - minimal code by reusing Profile4 logic
-#[derive(Clone)]
-pub struct Profile4m {
-    base: Profile4,
-    config: Profile4Config,
-    pub message_type: u8,
-    pub message_result: u8,
-    pub source_id: u32,
-}
-
-impl Profile4m {
-    fn write_source_id(&self, data: &mut [u8]) {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        data[offset + 12..=offset + 15].copy_from_slice(&self.source_id.to_be_bytes());
-    }
-    fn write_message_type(&self, data: &mut [u8]) {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        data[offset + 12] = (data[offset + 12] & 0x3F) | ((self.message_type & 0x03) << 6);
-    }
-    fn write_message_result(&self, data: &mut [u8]) {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        data[offset + 12] = (data[offset + 12] & 0xCF) | ((self.message_result & 0x03) << 4);
-    }
-    fn read_source_id(&self, data: &[u8]) -> u32 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        u32::from_be_bytes([
-            data[offset + 12],
-            data[offset + 13],
-            data[offset + 14],
-            data[offset + 15],
-        ]) & 0x0FFFFFFF
-    }
-    fn read_message_type(&self, data: &[u8]) -> u8 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        (data[offset + 12] >> 6) & 0x03
-    }
-    fn read_message_result(&self, data: &[u8]) -> u8 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        (data[offset + 12] >> 4) & 0x03
-    }
-    fn do_checks(&mut self, check_items: Profile4mCheck) -> E2EStatus {
-        if self.source_id != check_items.rx_source_id {
-            return E2EStatus::SourceIdError;
-        }
-        if self.message_result != check_items.rx_message_result {
-            return E2EStatus::MessageResultError;
-        }
-        if self.message_type != check_items.rx_message_type {
-            return E2EStatus::MessageTypeError;
-        }
-        E2EStatus::Ok
-    }
-}
-
-impl E2EProfile for Profile4m {
-    type Config = Profile4Config;
-
-    fn new(config: Self::Config) -> E2EResult<Self> {
-        // Validate using Profile4's validation
-        let base = crate::profile4::Profile4::new(config.clone())?; // This validates config
-        Ok(Self {
-            base,
-            config,
-            message_type: 0x00,
-            message_result: 0x00,
-            source_id: 0x0a0b0c0d,
-        })
-    }
-
-    fn protect(&mut self, data: &mut [u8]) -> E2EResult<()> {
-        // Write Profile4m specific fields first
-        self.write_source_id(data);
-        self.write_message_result(data);
-        self.write_message_type(data);
-        self.base.protect(data)?;
-        Ok(())
-    }
+pub type Profile4m = Profile4xm<Profile4Config>;
 
-    fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus> {
-        let mut status = self.base.check(data)?;
-        let check_items = Profile4mCheck {
-            rx_source_id: self.read_source_id(data),
-            rx_message_result: self.read_message_result(data),
-            rx_message_type: self.read_message_type(data),
-        };
-        if (status == E2EStatus::Ok) || (status == E2EStatus::OkSomeLost) {
-            status = self.do_checks(check_items);
-        }
-        Ok(status)
-    }
-}
+/// Zero-copy view of the E2E header of Profile 4M protected data, including the
+/// metadata fields behind the Profile 4 header
+pub type Profile4mHeader<'a> = Profile4xmHeader<'a, Profile4Config>;
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::E2ESyncPolicy;
+    use crate::{E2EError, E2EForward, E2EProfile, E2EStatus};
     #[test]
     fn test_profile4m_basic_request_example() {
-        let mut profile_tx = Profile4m::new(Profile4Config::default()).unwrap();
-        let mut profile_rx = Profile4m::new(Profile4Config::default()).unwrap();
+        let config = Profile4Config {
+            min_data_length: 160,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile4m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile4m::new(config).unwrap();
 
         let mut data = vec![
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -152,8 +70,13 @@ mod tests {
     }
     #[test]
     fn test_profile4m_basic_response_example() {
-        let mut profile_tx = Profile4m::new(Profile4Config::default()).unwrap();
-        let mut profile_rx = Profile4m::new(Profile4Config::default()).unwrap();
+        let config = Profile4Config {
+            min_data_length: 160,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile4m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile4m::new(config).unwrap();
 
         let mut data = vec![
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -191,8 +114,13 @@ mod tests {
     }
     #[test]
     fn test_profile4m_basic_error_example() {
-        let mut profile_tx = Profile4m::new(Profile4Config::default()).unwrap();
-        let mut profile_rx = Profile4m::new(Profile4Config::default()).unwrap();
+        let config = Profile4Config {
+            min_data_length: 160,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile4m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile4m::new(config).unwrap();
 
         let mut data = vec![
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -228,4 +156,122 @@ mod tests {
         profile_rx.message_type = 1;
         assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
     }
+
+    #[test]
+    fn test_profile4m_forward() {
+        let config = Profile4Config {
+            min_data_length: 160,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile4m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile4m::new(config).unwrap();
+
+        let mut data = vec![0x00; 20];
+        profile_tx.forward(&mut data, E2EStatus::Ok).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::Repeated).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+        profile_tx
+            .forward(&mut data, E2EStatus::OkSomeLost)
+            .unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::CrcError).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::CrcError);
+        profile_tx
+            .forward(&mut data, E2EStatus::WrongSequence)
+            .unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+        assert_eq!(
+            profile_tx.forward(&mut data, E2EStatus::NoNewData),
+            Err(E2EError::InvalidForwardStatus(E2EStatus::NoNewData))
+        );
+    }
+
+    #[test]
+    fn test_profile4m_header_exceeds_min_data_length() {
+        // The default Profile 4 configuration leaves no room for the metadata
+        assert_eq!(
+            Profile4m::new(Profile4Config::default()).err(),
+            Some(E2EError::MinDataLengthOutOfRange {
+                min: 16,
+                max: 4096,
+                actual: 96,
+            })
+        );
+        let config = Profile4Config {
+            offset: 64,
+            min_data_length: 160,
+            ..Default::default()
+        };
+        assert_eq!(
+            Profile4m::new(config).err(),
+            Some(E2EError::OffsetBeyondHeader {
+                offset: 64,
+                header_length: 16,
+            })
+        );
+    }
+
+    #[test]
+    fn test_profile4m_default_config() {
+        // the default of Profile 4 leaves no room for the metadata fields
+        assert!(Profile4m::new(Profile4Config::default()).is_err());
+        let config = Profile4m::default_config();
+        assert_eq!(config.min_data_length, 128);
+        let mut profile_tx = Profile4m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile4m::new(config).unwrap();
+
+        let mut data = vec![0x00; 16];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile4m_first_message() {
+        // the first message is checked against the initial counter 0, so only
+        // a sender starting from it is accepted
+        let config = Profile4m::default_config();
+        let mut profile_tx = Profile4m::new(config.clone()).unwrap();
+        let mut data = vec![0x00; 16];
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile4m::new(config.clone()).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile4m::new(config).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+    }
+
+    #[test]
+    fn test_profile4m_sync_ignores_other_source() {
+        let config = Profile4Config {
+            sync_policy: E2ESyncPolicy {
+                min_consecutive: 2,
+                ..Default::default()
+            },
+            ..Profile4m::default_config()
+        };
+        let mut profile_tx = Profile4m::new(config.clone()).unwrap();
+        let mut profile_other = Profile4m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile4m::new(config).unwrap();
+        profile_other.source_id = 0x00123456;
+
+        // data of another sender is rejected before its counter is evaluated,
+        // so it does not count towards the synchronization
+        let mut data = vec![0x00; 16];
+        profile_other.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::SourceIdError);
+        profile_other.protect(&mut data).unwrap();
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::SourceIdError);
+        assert_eq!(report.header.source_id, Some(0x00123456));
+
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Sync);
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
 }
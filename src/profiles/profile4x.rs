@@ -0,0 +1,745 @@
+//! # Shared implementation of E2E Profiles 4 and 44
+//!
+//! Profiles 4 and 44 only differ in the width of the length field, which
+//! shifts the remaining header fields:
+//! [DATA ... | LENGTH(2B or 4B) | COUNTER(2B) | ID (4B) | CRC(4B) | DATA ...]
+//!
+//! The M variants append the message metadata to the header:
+//! [... | CRC(4B) | TYPE(2b) RESULT(2b) SOURCE ID(28b) | DATA ...]
+//!
+//! The types of this module are instantiated by `profile4`, `profile4m`,
+//! `profile44` and `profile44m`, whose configuration defines the layout.
+use crate::profiles::{check_header_fits, ForwardAction, SyncState};
+use crate::snapshot::Fingerprint;
+use crate::{
+    E2ECheckReport, E2EError, E2EForward, E2EHeaderFields, E2EHeaderLayout, E2EProfile, E2EResult,
+    E2ESnapshot, E2EStatus,
+};
+use core::fmt;
+use core::marker::PhantomData;
+use crc::{Crc, CRC_32_AUTOSAR};
+
+// Constants
+const BITS_PER_BYTE: usize = 8;
+const COUNTER_MAX: u16 = 0xFFFF;
+const COUNTER_MODULO: u32 = 0x10000;
+/// Length of the message type, message result and source ID of the M variants
+const METADATA_LENGTH: usize = 4;
+
+pub(crate) mod sealed {
+    use crate::E2ESyncPolicy;
+
+    /// Layout and parameters of a Profile 4 family configuration
+    pub trait Layout: Clone {
+        /// Name of the profile
+        const NAME: &'static str;
+        /// Name of the M variant of the profile
+        const NAME_M: &'static str;
+        /// Width of the length field, in bytes
+        const LENGTH_WIDTH: usize;
+        /// Length of the E2E header, in bytes
+        const HEADER_LENGTH: usize = Self::LENGTH_WIDTH + 10;
+        /// Largest supported data length, in bytes, `usize::MAX` if unbounded
+        const MAX_DATA_LENGTH: usize;
+        /// Type of the length field
+        type Length: Copy + PartialEq + Into<u32>;
+
+        /// Truncate a length to the width of the length field
+        fn length(value: u32) -> Self::Length;
+        fn data_id(&self) -> u32;
+        fn offset(&self) -> usize;
+        fn min_data_length(&self) -> usize;
+        fn max_data_length(&self) -> usize;
+        fn set_min_data_length(&mut self, bits: usize);
+        fn max_delta_counter(&self) -> u16;
+        fn sync_policy(&self) -> &E2ESyncPolicy;
+    }
+}
+
+/// Configuration of a profile of the Profile 4 family, implemented by
+/// `Profile4Config` and `Profile44Config`
+pub trait Profile4xConfig: sealed::Layout {}
+
+impl<C: sealed::Layout> Profile4xConfig for C {}
+
+/// Check Item for the Profile 4 family
+#[derive(Debug, Clone)]
+struct Profile4xCheck<L> {
+    rx_data_length: L,
+    rx_counter: u16,
+    rx_data_id: u32,
+    rx_crc: u32,
+    calculated_crc: u32,
+    data_len: L,
+}
+
+/// E2E Profile 4 family implementation, used as `Profile4` and `Profile44`
+#[derive(Clone)]
+pub struct Profile4x<C> {
+    config: C,
+    pub(crate) counter: u16,
+    sync: SyncState,
+}
+
+impl<C: Profile4xConfig> Profile4x<C> {
+    /// Validate configuration parameters
+    pub(crate) fn validate_config(config: &C) -> E2EResult<()> {
+        let header_bits = C::HEADER_LENGTH * BITS_PER_BYTE;
+        let max_bits = C::MAX_DATA_LENGTH.saturating_mul(BITS_PER_BYTE);
+        let min_data_length = config.min_data_length();
+        let max_data_length = config.max_data_length();
+        if min_data_length < header_bits || max_bits < min_data_length {
+            return Err(E2EError::MinDataLengthOutOfRange {
+                min: C::HEADER_LENGTH,
+                max: C::MAX_DATA_LENGTH,
+                actual: min_data_length,
+            });
+        }
+        if max_data_length < min_data_length || max_bits < max_data_length {
+            return Err(E2EError::MaxDataLengthOutOfRange {
+                min: min_data_length / BITS_PER_BYTE,
+                max: C::MAX_DATA_LENGTH,
+                actual: max_data_length,
+            });
+        }
+        if min_data_length - header_bits < config.offset() {
+            return Err(E2EError::OffsetBeyondHeader {
+                offset: config.offset(),
+                header_length: C::HEADER_LENGTH,
+            });
+        }
+        if config.max_delta_counter() == 0 || config.max_delta_counter() == COUNTER_MAX {
+            return Err(E2EError::MaxDeltaCounterInvalid {
+                max: COUNTER_MAX as u32,
+            });
+        }
+        Ok(())
+    }
+    /// Validate data length against min/max constraints
+    fn validate_length(&self, len: usize) -> E2EResult<()> {
+        let min_bytes = self.config.min_data_length() / BITS_PER_BYTE;
+        let max_bytes = self.config.max_data_length() / BITS_PER_BYTE;
+        if len < min_bytes || max_bytes < len {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: min_bytes,
+                expected_max: max_bytes,
+                actual: len,
+            });
+        }
+        Ok(())
+    }
+    fn increment_counter(&mut self) {
+        self.counter = (self.counter as u32 + 1) as u16 & COUNTER_MAX;
+    }
+    fn write_data_length(&self, data: &mut [u8]) {
+        let offset = self.config.offset() / BITS_PER_BYTE;
+        let length = (data.len() as u32).to_be_bytes();
+        data[offset..offset + C::LENGTH_WIDTH].copy_from_slice(&length[4 - C::LENGTH_WIDTH..]);
+    }
+    fn write_counter(&self, data: &mut [u8]) {
+        let offset = self.config.offset() / BITS_PER_BYTE + C::LENGTH_WIDTH;
+        data[offset..offset + 2].copy_from_slice(&self.counter.to_be_bytes());
+    }
+    fn write_data_id(&self, data: &mut [u8]) {
+        let offset = self.config.offset() / BITS_PER_BYTE + C::LENGTH_WIDTH + 2;
+        data[offset..offset + 4].copy_from_slice(&self.config.data_id().to_be_bytes());
+    }
+    fn compute_crc(&self, data: &[u8]) -> u32 {
+        compute_crc(&self.config, data)
+    }
+    fn write_crc(&self, calculated_crc: u32, data: &mut [u8]) {
+        let offset = self.config.offset() / BITS_PER_BYTE + C::LENGTH_WIDTH + 6;
+        data[offset..offset + 4].copy_from_slice(&calculated_crc.to_be_bytes());
+    }
+
+    fn do_checks(&mut self, check_items: Profile4xCheck<C::Length>) -> E2EStatus {
+        if check_items.calculated_crc != check_items.rx_crc {
+            return E2EStatus::CrcError;
+        }
+        if check_items.rx_data_id != self.config.data_id() {
+            return E2EStatus::DataIdError;
+        }
+        if check_items.rx_data_length != check_items.data_len {
+            return E2EStatus::DataLengthError;
+        }
+        let status = self.validate_counter(check_items.rx_counter);
+        self.counter = check_items.rx_counter;
+        status
+    }
+    /// Check if counter delta is within acceptable range
+    fn check_counter_delta(&self, received_counter: u16) -> u16 {
+        if received_counter >= self.counter {
+            received_counter - self.counter
+        } else {
+            // Handle wrap-around
+            ((COUNTER_MODULO + received_counter as u32 - self.counter as u32) % COUNTER_MODULO)
+                as u16
+        }
+    }
+    fn validate_counter(&mut self, rx_counter: u16) -> E2EStatus {
+        let delta = self.check_counter_delta(rx_counter);
+        self.sync.check_delta(
+            self.config.sync_policy(),
+            delta.into(),
+            self.config.max_delta_counter().into(),
+        )
+    }
+    /// Read the header fields of the protected data without checking them
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn decode(&self, data: &[u8]) -> E2EResult<E2EHeaderFields> {
+        self.validate_length(data.len())?;
+        let header = Profile4xHeader::at(&self.config, data);
+        Ok(E2EHeaderFields {
+            counter: header.counter() as u32,
+            length: Some(header.data_length().into()),
+            data_id: Some(header.data_id()),
+            received_crc: header.crc() as u64,
+            computed_crc: self.compute_crc(data) as u64,
+            ..Default::default()
+        })
+    }
+    /// Check the protected data like `check` and report the decoded header,
+    /// the counter delta and the first failed check
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn check_detailed(&mut self, data: &[u8]) -> E2EResult<E2ECheckReport> {
+        let previous_counter = self.counter.into();
+        let header = self.decode(data)?;
+        let status = self.check(data)?;
+        Ok(E2ECheckReport::new(
+            status,
+            header,
+            previous_counter,
+            COUNTER_MODULO.into(),
+        ))
+    }
+}
+
+impl<C: Profile4xConfig> E2EProfile for Profile4x<C> {
+    type Config = C;
+
+    fn new(config: Self::Config) -> E2EResult<Self> {
+        // Validate config
+        Self::validate_config(&config)?;
+        Ok(Self {
+            config,
+            counter: 0,
+            sync: SyncState::default(),
+        })
+    }
+
+    fn protect(&mut self, data: &mut [u8]) -> E2EResult<()> {
+        self.validate_length(data.len())?;
+        self.write_data_length(data);
+        self.write_counter(data);
+        self.write_data_id(data);
+        let calculated_crc = self.compute_crc(data);
+        self.write_crc(calculated_crc, data);
+        self.increment_counter();
+        Ok(())
+    }
+
+    fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus> {
+        // Check data length
+        self.validate_length(data.len())?;
+        let header = Profile4xHeader::at(&self.config, data);
+        let check_items = Profile4xCheck {
+            rx_data_length: header.data_length(),
+            rx_counter: header.counter(),
+            rx_crc: header.crc(),
+            rx_data_id: header.data_id(),
+            calculated_crc: self.compute_crc(data),
+            data_len: C::length(data.len() as u32),
+        };
+        let status = self.do_checks(check_items);
+        Ok(status)
+    }
+
+    fn protect_init(&mut self) {
+        self.counter = 0;
+    }
+
+    fn check_init(&mut self) {
+        self.counter = 0;
+        self.sync = SyncState::default();
+    }
+
+    fn snapshot(&self) -> E2ESnapshot {
+        self.sync.save(E2ESnapshot::new(
+            fingerprint(&self.config, C::NAME),
+            self.counter.into(),
+        ))
+    }
+
+    fn restore(&mut self, snapshot: &E2ESnapshot) -> E2EResult<()> {
+        snapshot.validate(fingerprint(&self.config, C::NAME), COUNTER_MAX.into())?;
+        self.sync = SyncState::load(self.config.sync_policy(), snapshot)?;
+        self.counter = snapshot.counter as u16;
+        Ok(())
+    }
+}
+
+impl<C: Profile4xConfig> E2EForward for Profile4x<C> {
+    fn forward(&mut self, data: &mut [u8], status: E2EStatus) -> E2EResult<()> {
+        self.validate_length(data.len())?;
+        let action = ForwardAction::from_status(status)?;
+        match action {
+            ForwardAction::Repeat => self.counter = self.counter.wrapping_sub(1),
+            ForwardAction::Skip => {
+                self.counter = self.counter.wrapping_add(self.config.max_delta_counter())
+            }
+            ForwardAction::Protect | ForwardAction::CorruptCrc => {}
+        }
+        self.protect(data)?;
+        if action == ForwardAction::CorruptCrc {
+            let calculated_crc = Profile4xHeader::at(&self.config, data).crc();
+            self.write_crc(!calculated_crc, data);
+        }
+        Ok(())
+    }
+}
+
+impl<C: Profile4xConfig> E2EHeaderLayout for Profile4x<C> {
+    fn header_offset(&self) -> usize {
+        self.config.offset()
+    }
+
+    fn header_length(&self) -> usize {
+        C::HEADER_LENGTH * BITS_PER_BYTE
+    }
+}
+
+/// Zero-copy view of the E2E header of Profile 4 family protected data, used
+/// as `Profile4Header` and `Profile44Header`
+///
+/// The view only reads the data, so frames can be inspected without a
+/// profile instance and without affecting any receiver state.
+pub struct Profile4xHeader<'a, C> {
+    data: &'a [u8],
+    offset: usize,
+    layout: PhantomData<fn() -> C>,
+}
+
+impl<'a, C: Profile4xConfig> Profile4xHeader<'a, C> {
+    /// Locate the header in `data` as configured
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+    pub fn new(config: &C, data: &'a [u8]) -> E2EResult<Self> {
+        let header_end = config.offset() / BITS_PER_BYTE + C::HEADER_LENGTH;
+        if data.len() < header_end {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: header_end,
+                expected_max: usize::MAX,
+                actual: data.len(),
+            });
+        }
+        Ok(Self::at(config, data))
+    }
+    pub(crate) fn at(config: &C, data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: config.offset() / BITS_PER_BYTE,
+            layout: PhantomData,
+        }
+    }
+    /// Length field
+    pub fn data_length(&self) -> C::Length {
+        let mut length = [0u8; 4];
+        length[4 - C::LENGTH_WIDTH..]
+            .copy_from_slice(&self.data[self.offset..self.offset + C::LENGTH_WIDTH]);
+        C::length(u32::from_be_bytes(length))
+    }
+    /// Sequence counter
+    pub fn counter(&self) -> u16 {
+        let offset = self.offset + C::LENGTH_WIDTH;
+        u16::from_be_bytes([self.data[offset], self.data[offset + 1]])
+    }
+    /// Data ID
+    pub fn data_id(&self) -> u32 {
+        let offset = self.offset + C::LENGTH_WIDTH + 2;
+        u32::from_be_bytes([
+            self.data[offset],
+            self.data[offset + 1],
+            self.data[offset + 2],
+            self.data[offset + 3],
+        ])
+    }
+    /// CRC as received
+    pub fn crc(&self) -> u32 {
+        let offset = self.offset + C::LENGTH_WIDTH + 6;
+        u32::from_be_bytes([
+            self.data[offset],
+            self.data[offset + 1],
+            self.data[offset + 2],
+            self.data[offset + 3],
+        ])
+    }
+}
+
+impl<C> Clone for Profile4xHeader<'_, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for Profile4xHeader<'_, C> {}
+
+impl<C> fmt::Debug for Profile4xHeader<'_, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Profile4xHeader")
+            .field("data", &self.data)
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
+
+fn compute_crc<C: Profile4xConfig>(config: &C, data: &[u8]) -> u32 {
+    let crc: Crc<u32> = Crc::<u32>::new(&CRC_32_AUTOSAR);
+    let mut digest = crc.digest();
+    let offset = config.offset() / BITS_PER_BYTE;
+    digest.update(&data[0..offset + C::LENGTH_WIDTH + 6]); // crc calculation data before offset
+    digest.update(&data[(offset + C::HEADER_LENGTH)..]); // crc calculation data after offset
+    digest.finalize()
+}
+
+/// Verify the CRC of Profile 4 family protected data without a profile instance
+///
+/// # Errors
+/// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+pub fn verify_crc<C: Profile4xConfig>(config: &C, data: &[u8]) -> E2EResult<bool> {
+    let header = Profile4xHeader::new(config, data)?;
+    Ok(header.crc() == compute_crc(config, data))
+}
+
+/// Fingerprint of the configuration for the given profile name, which
+/// separates the snapshots of the M variant from the ones of the base profile
+fn fingerprint<C: Profile4xConfig>(config: &C, profile: &str) -> u32 {
+    // Offset and lengths are configured with the width of the length field
+    let width = 4 - C::LENGTH_WIDTH;
+    Fingerprint::new(profile)
+        .field(&config.data_id().to_be_bytes())
+        .field(&(config.offset() as u32).to_be_bytes()[width..])
+        .field(&(config.min_data_length() as u32).to_be_bytes()[width..])
+        .field(&(config.max_data_length() as u32).to_be_bytes()[width..])
+        .field(&config.max_delta_counter().to_be_bytes())
+        .field(&config.sync_policy().to_bytes())
+        .finalize()
+}
+
+/// Check Item for the M variants of the Profile 4 family
+#[derive(Debug, Clone)]
+struct Profile4xmCheck {
+    rx_source_id: u32,
+    rx_message_type: u8,
+    rx_message_result: u8,
+}
+
+/// E2E Profile 4M family implementation, used as `Profile4m` and `Profile44m`
+///
+/// Identical to the base profile but includes additional fields in the CRC
+/// calculation: message_type, message_result, and source_id
+#[derive(Clone)]
+pub struct Profile4xm<C> {
+    base: Profile4x<C>,
+    pub message_type: u8,
+    pub message_result: u8,
+    pub source_id: u32,
+}
+
+impl<C: Profile4xConfig> Profile4xm<C> {
+    /// Validate data length against min/max constraints
+    fn validate_length(&self, len: usize) -> E2EResult<()> {
+        let config = &self.base.config;
+        let min_bytes = config.min_data_length() / BITS_PER_BYTE;
+        let max_bytes = config.max_data_length() / BITS_PER_BYTE;
+        if len < min_bytes || max_bytes < len {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: min_bytes,
+                expected_max: max_bytes,
+                actual: len,
+            });
+        }
+        Ok(())
+    }
+    fn metadata_offset(&self) -> usize {
+        self.base.config.offset() / BITS_PER_BYTE + C::HEADER_LENGTH
+    }
+    fn write_source_id(&self, data: &mut [u8]) {
+        let offset = self.metadata_offset();
+        data[offset..offset + 4].copy_from_slice(&self.source_id.to_be_bytes());
+    }
+    fn write_message_type(&self, data: &mut [u8]) {
+        let offset = self.metadata_offset();
+        data[offset] = (data[offset] & 0x3F) | ((self.message_type & 0x03) << 6);
+    }
+    fn write_message_result(&self, data: &mut [u8]) {
+        let offset = self.metadata_offset();
+        data[offset] = (data[offset] & 0xCF) | ((self.message_result & 0x03) << 4);
+    }
+    fn read_metadata(&self, data: &[u8]) -> Profile4xmCheck {
+        let header = Profile4xmHeader::at(&self.base.config, data);
+        Profile4xmCheck {
+            rx_source_id: header.source_id(),
+            rx_message_result: header.message_result(),
+            rx_message_type: header.message_type(),
+        }
+    }
+    fn do_checks(&mut self, check_items: Profile4xmCheck) -> E2EStatus {
+        if self.source_id != check_items.rx_source_id {
+            return E2EStatus::SourceIdError;
+        }
+        if self.message_result != check_items.rx_message_result {
+            return E2EStatus::MessageResultError;
+        }
+        if self.message_type != check_items.rx_message_type {
+            return E2EStatus::MessageTypeError;
+        }
+        E2EStatus::Ok
+    }
+    /// Check the metadata of data that passed the CRC, Data ID and length
+    /// checks of the base profile, before its counter check counts the data
+    ///
+    /// `base` is the base profile before its check, which is restored if the
+    /// metadata does not match, so data of another sender neither updates the
+    /// counter nor advances the synchronization.
+    fn check_metadata(
+        &mut self,
+        base: Profile4x<C>,
+        check_items: Profile4xmCheck,
+        status: E2EStatus,
+    ) -> E2EStatus {
+        match status {
+            E2EStatus::Ok
+            | E2EStatus::OkSomeLost
+            | E2EStatus::Sync
+            | E2EStatus::Repeated
+            | E2EStatus::WrongSequence => match self.do_checks(check_items) {
+                E2EStatus::Ok => status,
+                metadata_status => {
+                    self.base = base;
+                    metadata_status
+                }
+            },
+            _ => status,
+        }
+    }
+
+    /// Read the header fields of the protected data without checking them
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn decode(&self, data: &[u8]) -> E2EResult<E2EHeaderFields> {
+        self.validate_length(data.len())?;
+        let mut fields = self.base.decode(data)?;
+        let metadata = self.read_metadata(data);
+        fields.source_id = Some(metadata.rx_source_id);
+        fields.message_type = Some(metadata.rx_message_type);
+        fields.message_result = Some(metadata.rx_message_result);
+        Ok(fields)
+    }
+
+    /// Check the protected data like `check` and report the decoded header,
+    /// including the metadata fields, the counter delta and the first failed check
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn check_detailed(&mut self, data: &[u8]) -> E2EResult<E2ECheckReport> {
+        self.validate_length(data.len())?;
+        let base = self.base.clone();
+        let mut report = self.base.check_detailed(data)?;
+        let check_items = self.read_metadata(data);
+        report.header.source_id = Some(check_items.rx_source_id);
+        report.header.message_type = Some(check_items.rx_message_type);
+        report.header.message_result = Some(check_items.rx_message_result);
+        let status = self.check_metadata(base, check_items, report.status);
+        if status != report.status {
+            report = report.with_status(status);
+        }
+        Ok(report)
+    }
+}
+
+impl<C: Profile4xConfig + Default> Profile4xm<C> {
+    /// Default configuration of the M variant
+    ///
+    /// The default configuration of the base profile leaves no room for the
+    /// metadata fields, so the minimum data length is raised to the header
+    /// length of the M variant.
+    pub fn default_config() -> C {
+        let mut config = C::default();
+        config.set_min_data_length((C::HEADER_LENGTH + METADATA_LENGTH) * BITS_PER_BYTE);
+        config
+    }
+}
+
+impl<C: Profile4xConfig> E2EProfile for Profile4xm<C> {
+    type Config = C;
+
+    fn new(config: Self::Config) -> E2EResult<Self> {
+        // Validate using the validation of the base profile
+        let base = Profile4x::new(config)?;
+        let config = &base.config;
+
+        // The metadata fields extend the header, which has to fit into the minimal length
+        check_header_fits(
+            config.offset(),
+            C::HEADER_LENGTH + METADATA_LENGTH,
+            config.min_data_length(),
+            C::MAX_DATA_LENGTH,
+        )?;
+        Ok(Self {
+            base,
+            message_type: 0x00,
+            message_result: 0x00,
+            source_id: 0x0a0b0c0d,
+        })
+    }
+
+    fn protect(&mut self, data: &mut [u8]) -> E2EResult<()> {
+        self.validate_length(data.len())?;
+        // Write the metadata fields first, they are covered by the CRC
+        self.write_source_id(data);
+        self.write_message_result(data);
+        self.write_message_type(data);
+        self.base.protect(data)?;
+        Ok(())
+    }
+
+    fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus> {
+        self.validate_length(data.len())?;
+        let base = self.base.clone();
+        let status = self.base.check(data)?;
+        let check_items = self.read_metadata(data);
+        Ok(self.check_metadata(base, check_items, status))
+    }
+
+    fn protect_init(&mut self) {
+        self.base.protect_init();
+    }
+
+    fn check_init(&mut self) {
+        // The metadata fields are configured by the application and kept
+        self.base.check_init();
+    }
+
+    fn snapshot(&self) -> E2ESnapshot {
+        E2ESnapshot {
+            fingerprint: fingerprint(&self.base.config, C::NAME_M),
+            message_type: self.message_type,
+            message_result: self.message_result,
+            source_id: self.source_id,
+            ..self.base.snapshot()
+        }
+    }
+
+    fn restore(&mut self, snapshot: &E2ESnapshot) -> E2EResult<()> {
+        snapshot.validate(fingerprint(&self.base.config, C::NAME_M), u32::MAX)?;
+        // The counter state belongs to the base profile
+        self.base.restore(&E2ESnapshot {
+            fingerprint: fingerprint(&self.base.config, C::NAME),
+            ..*snapshot
+        })?;
+        self.message_type = snapshot.message_type;
+        self.message_result = snapshot.message_result;
+        self.source_id = snapshot.source_id;
+        Ok(())
+    }
+}
+
+impl<C: Profile4xConfig> E2EForward for Profile4xm<C> {
+    fn forward(&mut self, data: &mut [u8], status: E2EStatus) -> E2EResult<()> {
+        self.validate_length(data.len())?;
+        self.write_source_id(data);
+        self.write_message_result(data);
+        self.write_message_type(data);
+        self.base.forward(data, status)
+    }
+}
+
+impl<C: Profile4xConfig> E2EHeaderLayout for Profile4xm<C> {
+    fn header_offset(&self) -> usize {
+        self.base.header_offset()
+    }
+
+    fn header_length(&self) -> usize {
+        (C::HEADER_LENGTH + METADATA_LENGTH) * BITS_PER_BYTE
+    }
+}
+
+/// Zero-copy view of the E2E header of Profile 4M family protected data,
+/// including the metadata fields behind the base header, used as
+/// `Profile4mHeader` and `Profile44mHeader`
+pub struct Profile4xmHeader<'a, C> {
+    base: Profile4xHeader<'a, C>,
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a, C: Profile4xConfig> Profile4xmHeader<'a, C> {
+    /// Locate the header in `data` as configured
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+    pub fn new(config: &C, data: &'a [u8]) -> E2EResult<Self> {
+        let header_end = config.offset() / BITS_PER_BYTE + C::HEADER_LENGTH + METADATA_LENGTH;
+        if data.len() < header_end {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: header_end,
+                expected_max: usize::MAX,
+                actual: data.len(),
+            });
+        }
+        Ok(Self::at(config, data))
+    }
+    fn at(config: &C, data: &'a [u8]) -> Self {
+        Self {
+            base: Profile4xHeader::at(config, data),
+            data,
+            offset: config.offset() / BITS_PER_BYTE + C::HEADER_LENGTH,
+        }
+    }
+    /// Header fields shared with the base profile
+    pub fn base(&self) -> &Profile4xHeader<'a, C> {
+        &self.base
+    }
+    /// Source ID
+    pub fn source_id(&self) -> u32 {
+        let offset = self.offset;
+        u32::from_be_bytes([
+            self.data[offset],
+            self.data[offset + 1],
+            self.data[offset + 2],
+            self.data[offset + 3],
+        ]) & 0x0FFFFFFF
+    }
+    /// Message type
+    pub fn message_type(&self) -> u8 {
+        (self.data[self.offset] >> 6) & 0x03
+    }
+    /// Message result
+    pub fn message_result(&self) -> u8 {
+        (self.data[self.offset] >> 4) & 0x03
+    }
+}
+
+impl<C> Clone for Profile4xmHeader<'_, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for Profile4xmHeader<'_, C> {}
+
+impl<C> fmt::Debug for Profile4xmHeader<'_, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Profile4xmHeader")
+            .field("base", &self.base)
+            .field("data", &self.data)
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
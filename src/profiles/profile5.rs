@@ -8,16 +8,24 @@
 //!
 //! # Data layout
 //! [DATA ... | CRC(2B) | COUNTER(1B) | DATA ...]
-use crate::{E2EError, E2EProfile, E2EResult, E2EStatus};
+use crate::profiles::{ForwardAction, SyncState};
+use crate::snapshot::Fingerprint;
+use crate::{
+    E2ECheckReport, E2EError, E2EForward, E2EHeaderFields, E2EHeaderLayout, E2EProfile, E2EResult,
+    E2ESnapshot, E2EStatus, E2ESyncPolicy,
+};
 use crc::{Crc, CRC_16_IBM_3740};
 
 // Constants
 const COUNTER_MAX: u8 = 0xFF;
 const BITS_PER_BYTE: u16 = 8;
+const HEADER_LENGTH: usize = 3;
 const COUNTER_MODULO: u16 = 0x100;
 
 /// Configuration for E2E Profile 5
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(remote = "Self", default))]
 pub struct Profile5Config {
     /// Length of Data, in bits. The value shall be a multiple of 8.
     pub data_length: u16,
@@ -27,6 +35,8 @@ pub struct Profile5Config {
     pub max_delta_counter: u8,
     /// Bit offset of E2E header in the Data[] array in bits.
     pub offset: u16,
+    /// Receiver behaviour at startup and after a loss of synchronization
+    pub sync_policy: E2ESyncPolicy,
 }
 
 /// Check Item for E2E Profile 5
@@ -44,10 +54,14 @@ impl Default for Profile5Config {
             offset: 0x0000,
             data_length: 24, // 3bytes
             max_delta_counter: 1,
+            sync_policy: E2ESyncPolicy::default(),
         }
     }
 }
 
+#[cfg(feature = "serde")]
+impl_validated_serde!(Profile5Config, Profile5::validate_config);
+
 /// E2E Profile 5 Implementation
 ///
 /// Implements AUTOSAR E2E Profile 5 protection mechanism
@@ -55,38 +69,41 @@ impl Default for Profile5Config {
 pub struct Profile5 {
     config: Profile5Config,
     counter: u8,
-    initialized: bool,
+    sync: SyncState,
 }
 
 impl Profile5 {
     /// Validate configuration parameters
     fn validate_config(config: &Profile5Config) -> E2EResult<()> {
         if config.data_length < 3 * BITS_PER_BYTE || 4096 * BITS_PER_BYTE < config.data_length {
-            return Err(E2EError::InvalidConfiguration(
-                "Minimum Data length shall be between 3B and 4096B".into(),
-            ));
+            return Err(E2EError::MinDataLengthOutOfRange {
+                min: 3,
+                max: 4096,
+                actual: config.data_length.into(),
+            });
         }
         if config.data_length - 3 * BITS_PER_BYTE < config.offset {
-            return Err(E2EError::InvalidConfiguration(
-                "Offset shall be between 0 and data length - 3B".into(),
-            ));
+            return Err(E2EError::OffsetBeyondHeader {
+                offset: config.offset.into(),
+                header_length: HEADER_LENGTH,
+            });
         }
         if config.max_delta_counter == 0 || config.max_delta_counter == COUNTER_MAX {
-            return Err(E2EError::InvalidConfiguration(format!(
-                "Max delta counter must be between 1 and {}",
-                COUNTER_MAX
-            )));
+            return Err(E2EError::MaxDeltaCounterInvalid {
+                max: COUNTER_MAX as u32,
+            });
         }
         Ok(())
     }
     /// Validate data length against min/max constraints
-    fn validate_length(&self, len: u16) -> E2EResult<()> {
-        let expected_bytes = self.config.data_length / BITS_PER_BYTE;
+    fn validate_length(&self, len: usize) -> E2EResult<()> {
+        let expected_bytes = (self.config.data_length / BITS_PER_BYTE) as usize;
         if len != expected_bytes {
-            return Err(E2EError::InvalidDataFormat(format!(
-                "Expected {} bytes, got {} bytes",
-                expected_bytes, len
-            )));
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: expected_bytes,
+                expected_max: expected_bytes,
+                actual: len,
+            });
         }
         Ok(())
     }
@@ -95,13 +112,7 @@ impl Profile5 {
         data[offset + 2] = self.counter;
     }
     fn compute_crc(&self, data: &[u8]) -> u16 {
-        let crc: Crc<u16> = Crc::<u16>::new(&CRC_16_IBM_3740);
-        let mut digest = crc.digest();
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        digest.update(&data[0..offset]); // crc calculation data before offset
-        digest.update(&data[(offset + 2)..]); // crc calculation data after offset
-        digest.update(&self.config.data_id.to_le_bytes());
-        digest.finalize()
+        compute_crc(&self.config, data)
     }
     fn write_crc(&self, calculated_crc: u16, data: &mut [u8]) {
         let offset = (self.config.offset / BITS_PER_BYTE) as usize;
@@ -111,12 +122,10 @@ impl Profile5 {
         self.counter = (self.counter as u16 + 1) as u8 & COUNTER_MAX;
     }
     fn read_counter(&self, data: &[u8]) -> u8 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        data[offset + 2]
+        Profile5Header::at(&self.config, data).counter()
     }
     fn read_crc(&self, data: &[u8]) -> u16 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        u16::from_le_bytes([data[offset], data[offset + 1]])
+        Profile5Header::at(&self.config, data).crc()
     }
 
     fn do_checks(&mut self, check_items: Profile5Check) -> E2EStatus {
@@ -137,22 +146,42 @@ impl Profile5 {
                 as u8
         }
     }
-    fn validate_counter(&self, rx_counter: u8) -> E2EStatus {
+    fn validate_counter(&mut self, rx_counter: u8) -> E2EStatus {
         let delta = self.check_counter_delta(rx_counter);
-
-        if delta == 0 {
-            if self.initialized {
-                E2EStatus::Repeated
-            } else {
-                E2EStatus::Ok
-            }
-        } else if delta == 1 {
-            E2EStatus::Ok
-        } else if delta >= 2 && delta <= self.config.max_delta_counter {
-            E2EStatus::OkSomeLost
-        } else {
-            E2EStatus::WrongSequence
-        }
+        self.sync.check_delta(
+            &self.config.sync_policy,
+            delta.into(),
+            self.config.max_delta_counter.into(),
+        )
+    }
+    /// Read the header fields of the protected data without checking them
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn decode(&self, data: &[u8]) -> E2EResult<E2EHeaderFields> {
+        self.validate_length(data.len())?;
+        Ok(E2EHeaderFields {
+            counter: self.read_counter(data) as u32,
+            received_crc: self.read_crc(data) as u64,
+            computed_crc: self.compute_crc(data) as u64,
+            ..Default::default()
+        })
+    }
+    /// Check the protected data like `check` and report the decoded header,
+    /// the counter delta and the first failed check
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn check_detailed(&mut self, data: &[u8]) -> E2EResult<E2ECheckReport> {
+        let previous_counter = self.counter.into();
+        let header = self.decode(data)?;
+        let status = self.check(data)?;
+        Ok(E2ECheckReport::new(
+            status,
+            header,
+            previous_counter,
+            COUNTER_MODULO.into(),
+        ))
     }
 }
 
@@ -165,12 +194,12 @@ impl E2EProfile for Profile5 {
         Ok(Self {
             config,
             counter: 0,
-            initialized: false,
+            sync: SyncState::default(),
         })
     }
 
     fn protect(&mut self, data: &mut [u8]) -> E2EResult<()> {
-        self.validate_length(data.len() as u16)?;
+        self.validate_length(data.len())?;
         self.write_counter(data);
         let calculated_crc = self.compute_crc(data);
         self.write_crc(calculated_crc, data);
@@ -180,23 +209,148 @@ impl E2EProfile for Profile5 {
 
     fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus> {
         // Check data length
-        self.validate_length(data.len() as u16)?;
+        self.validate_length(data.len())?;
         let check_items = Profile5Check {
             rx_counter: self.read_counter(data),
             rx_crc: self.read_crc(data),
             calculated_crc: self.compute_crc(data),
         };
         let status = self.do_checks(check_items);
-        if !self.initialized && matches!(status, E2EStatus::Ok | E2EStatus::OkSomeLost) {
-            self.initialized = true;
-        }
         Ok(status)
     }
+
+    fn protect_init(&mut self) {
+        self.counter = 0;
+    }
+
+    fn check_init(&mut self) {
+        self.counter = 0;
+        self.sync = SyncState::default();
+    }
+
+    fn snapshot(&self) -> E2ESnapshot {
+        self.sync.save(E2ESnapshot::new(
+            fingerprint(&self.config),
+            self.counter.into(),
+        ))
+    }
+
+    fn restore(&mut self, snapshot: &E2ESnapshot) -> E2EResult<()> {
+        snapshot.validate(fingerprint(&self.config), COUNTER_MAX.into())?;
+        self.sync = SyncState::load(&self.config.sync_policy, snapshot)?;
+        self.counter = snapshot.counter as u8;
+        Ok(())
+    }
+}
+
+impl E2EForward for Profile5 {
+    fn forward(&mut self, data: &mut [u8], status: E2EStatus) -> E2EResult<()> {
+        self.validate_length(data.len())?;
+        let action = ForwardAction::from_status(status)?;
+        match action {
+            ForwardAction::Repeat => self.counter = self.counter.wrapping_sub(1),
+            ForwardAction::Skip => {
+                self.counter = self.counter.wrapping_add(self.config.max_delta_counter)
+            }
+            ForwardAction::Protect | ForwardAction::CorruptCrc => {}
+        }
+        self.protect(data)?;
+        if action == ForwardAction::CorruptCrc {
+            let calculated_crc = self.read_crc(data);
+            self.write_crc(!calculated_crc, data);
+        }
+        Ok(())
+    }
+}
+
+impl E2EHeaderLayout for Profile5 {
+    fn header_offset(&self) -> usize {
+        self.config.offset as usize
+    }
+
+    fn header_length(&self) -> usize {
+        HEADER_LENGTH * BITS_PER_BYTE as usize
+    }
+}
+
+/// Zero-copy view of the E2E header of Profile 5 protected data
+///
+/// The view only reads the data, so frames can be inspected without a
+/// profile instance and without affecting any receiver state.
+#[derive(Debug, Clone, Copy)]
+pub struct Profile5Header<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Profile5Header<'a> {
+    /// Locate the header in `data` as configured
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+    pub fn new(config: &Profile5Config, data: &'a [u8]) -> E2EResult<Self> {
+        let header_end = (config.offset / BITS_PER_BYTE) as usize + HEADER_LENGTH;
+        if data.len() < header_end {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: header_end,
+                expected_max: usize::MAX,
+                actual: data.len(),
+            });
+        }
+        Ok(Self::at(config, data))
+    }
+    pub(crate) fn at(config: &Profile5Config, data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: (config.offset / BITS_PER_BYTE) as usize,
+        }
+    }
+    /// Sequence counter
+    pub fn counter(&self) -> u8 {
+        let offset = self.offset;
+        self.data[offset + 2]
+    }
+    /// CRC as received
+    pub fn crc(&self) -> u16 {
+        let offset = self.offset;
+        u16::from_le_bytes([self.data[offset], self.data[offset + 1]])
+    }
+}
+
+pub(crate) fn compute_crc(config: &Profile5Config, data: &[u8]) -> u16 {
+    let crc: Crc<u16> = Crc::<u16>::new(&CRC_16_IBM_3740);
+    let mut digest = crc.digest();
+    let offset = (config.offset / BITS_PER_BYTE) as usize;
+    digest.update(&data[0..offset]); // crc calculation data before offset
+    digest.update(&data[(offset + 2)..]); // crc calculation data after offset
+    digest.update(&config.data_id.to_le_bytes());
+    digest.finalize()
+}
+
+/// Verify the CRC of Profile 5 protected data without a profile instance
+///
+/// # Errors
+/// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+pub fn verify_crc(config: &Profile5Config, data: &[u8]) -> E2EResult<bool> {
+    let header = Profile5Header::new(config, data)?;
+    Ok(header.crc() == compute_crc(config, data))
+}
+
+/// Fingerprint of the configuration, see `E2ESnapshot`
+fn fingerprint(config: &Profile5Config) -> u32 {
+    Fingerprint::new("Profile 5")
+        .field(&config.data_length.to_be_bytes())
+        .field(&config.data_id.to_be_bytes())
+        .field(&config.max_delta_counter.to_be_bytes())
+        .field(&config.offset.to_be_bytes())
+        .field(&config.sync_policy.to_bytes())
+        .finalize()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::E2ECheckItem;
     #[test]
     fn test_profile5_basic_example() {
         let config = Profile5Config {
@@ -272,4 +426,164 @@ mod tests {
         assert_eq!(data[10], 0x00);
         assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
     }
+
+    #[test]
+    fn test_profile5_forward() {
+        let mut profile_tx = Profile5::new(Profile5Config::default()).unwrap();
+        let mut profile_rx = Profile5::new(Profile5Config::default()).unwrap();
+
+        let mut data = vec![0x00; 3];
+        profile_tx.forward(&mut data, E2EStatus::Ok).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::Repeated).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+        profile_tx
+            .forward(&mut data, E2EStatus::OkSomeLost)
+            .unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::CrcError).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::CrcError);
+        profile_tx
+            .forward(&mut data, E2EStatus::WrongSequence)
+            .unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+        assert_eq!(
+            profile_tx.forward(&mut data, E2EStatus::NoNewData),
+            Err(E2EError::InvalidForwardStatus(E2EStatus::NoNewData))
+        );
+    }
+
+    #[test]
+    fn test_profile5_check_detailed() {
+        let config = Profile5Config {
+            data_length: 8 * BITS_PER_BYTE,
+            ..Default::default()
+        };
+        let mut profile_tx = Profile5::new(config.clone()).unwrap();
+        let mut profile_rx = Profile5::new(config).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::Ok);
+        assert_eq!(report.failed_check, None);
+        assert_eq!(report.header.length, None);
+        assert_eq!(report.header.data_id, None);
+        assert_eq!(report.header.received_crc, 0xca1c);
+        assert_eq!(report.header.computed_crc, 0xca1c);
+        // two frames lost
+        for _ in 0..3 {
+            profile_tx.protect(&mut data).unwrap();
+        }
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::WrongSequence);
+        assert_eq!(report.failed_check, Some(E2ECheckItem::Counter));
+        assert_eq!(report.previous_counter, 0);
+        assert_eq!(report.header.counter, 3);
+        assert_eq!(report.counter_delta, 3);
+        // the CRC is checked first
+        data[4] ^= 0x01;
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::CrcError);
+        assert_eq!(report.failed_check, Some(E2ECheckItem::Crc));
+        assert_ne!(report.header.received_crc, report.header.computed_crc);
+    }
+
+    #[test]
+    fn test_profile5_header_view() {
+        let config = Profile5Config {
+            data_length: 8 * BITS_PER_BYTE,
+            ..Default::default()
+        };
+        let mut profile_tx = Profile5::new(config.clone()).unwrap();
+        let mut profile_rx = Profile5::new(config.clone()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        let header = Profile5Header::new(&config, &data).unwrap();
+        assert_eq!(header.counter(), 0);
+        assert_eq!(header.crc(), 0xca1c);
+        profile_tx.protect(&mut data).unwrap();
+        let header = Profile5Header::new(&config, &data).unwrap();
+        assert_eq!(header.counter(), 1);
+        assert!(verify_crc(&config, &data).unwrap());
+        // inspecting the frame leaves the receiver untouched
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.counter, 1);
+
+        data[4] ^= 0x01;
+        assert!(!verify_crc(&config, &data).unwrap());
+        assert_eq!(
+            Profile5Header::new(&config, &data[..2]).unwrap_err(),
+            E2EError::LengthOutOfRange {
+                expected_min: 3,
+                expected_max: usize::MAX,
+                actual: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_profile5_snapshot_restore() {
+        let config = Profile5Config::default();
+        let mut profile_tx = Profile5::new(config.clone()).unwrap();
+        let mut profile_rx = Profile5::new(config.clone()).unwrap();
+
+        let mut data = vec![0x00; 3];
+        for _ in 0..5 {
+            profile_tx.protect(&mut data).unwrap();
+            profile_rx.check(&data).unwrap();
+        }
+        let snapshot = profile_rx.snapshot();
+        assert_eq!(snapshot.counter, 4);
+        assert!(snapshot.initialized);
+
+        // a receiver restarting without its state flags the next frame
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_fresh = Profile5::new(config.clone()).unwrap();
+        assert_eq!(
+            profile_fresh.check(&data).unwrap(),
+            E2EStatus::WrongSequence
+        );
+        let mut profile_restored = Profile5::new(config.clone()).unwrap();
+        profile_restored.restore(&snapshot).unwrap();
+        assert_eq!(profile_restored.check(&data).unwrap(), E2EStatus::Ok);
+
+        let other_config = Profile5Config {
+            data_id: 0x4321,
+            ..config
+        };
+        let mut profile_other = Profile5::new(other_config).unwrap();
+        assert!(matches!(
+            profile_other.restore(&snapshot),
+            Err(E2EError::SnapshotConfigMismatch { .. })
+        ));
+        assert_eq!(profile_other.counter, 0);
+        let out_of_range = E2ESnapshot {
+            counter: 0x100,
+            ..snapshot
+        };
+        assert_eq!(
+            profile_restored.restore(&out_of_range),
+            Err(E2EError::SnapshotInvalid)
+        );
+    }
+
+    #[test]
+    fn test_profile5_first_message() {
+        // the first message is checked against the initial counter 0, so only
+        // a sender starting from it is accepted
+        let config = Profile5Config::default();
+        let mut profile_tx = Profile5::new(config.clone()).unwrap();
+        let mut data = vec![0x00; 3];
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile5::new(config.clone()).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile5::new(config).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+    }
 }
@@ -9,16 +9,24 @@
 //!
 //! # Data layout
 //! [DATA ... | CRC(2B) | LENGTH(2B) | COUNTER(1B) | DATA ...]
-use crate::{E2EError, E2EProfile, E2EResult, E2EStatus};
+use crate::profiles::{ForwardAction, SyncState};
+use crate::snapshot::Fingerprint;
+use crate::{
+    E2ECheckReport, E2EError, E2EForward, E2EHeaderFields, E2EHeaderLayout, E2EProfile, E2EResult,
+    E2ESnapshot, E2EStatus, E2ESyncPolicy,
+};
 use crc::{Crc, CRC_16_IBM_3740};
 
 // Constants
 const BITS_PER_BYTE: u16 = 8;
+const HEADER_LENGTH: usize = 5;
 const COUNTER_MAX: u8 = 0xFF;
 const COUNTER_MODULO: u16 = 0x100;
 
 /// Configuration for E2E Profile 6
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(remote = "Self", default))]
 pub struct Profile6Config {
     /// data id
     pub data_id: u16,
@@ -30,6 +38,8 @@ pub struct Profile6Config {
     pub max_data_length: u16,
     /// Maximum allowed delta between consecutive counters
     pub max_delta_counter: u8,
+    /// Receiver behaviour at startup and after a loss of synchronization
+    pub sync_policy: E2ESyncPolicy,
 }
 
 /// Check Item for E2E Profile 6
@@ -50,10 +60,14 @@ impl Default for Profile6Config {
             min_data_length: 40,    // 5bytes
             max_data_length: 32768, // 4096bytes
             max_delta_counter: 1,
+            sync_policy: E2ESyncPolicy::default(),
         }
     }
 }
 
+#[cfg(feature = "serde")]
+impl_validated_serde!(Profile6Config, Profile6::validate_config);
+
 /// E2E Profile 6 Implementation
 ///
 /// Implements AUTOSAR E2E Profile 6 protection mechanism
@@ -61,7 +75,7 @@ impl Default for Profile6Config {
 pub struct Profile6 {
     config: Profile6Config,
     counter: u8,
-    initialized: bool,
+    sync: SyncState,
 }
 
 impl Profile6 {
@@ -70,32 +84,42 @@ impl Profile6 {
         if config.min_data_length < 5 * BITS_PER_BYTE
             || 4096 * BITS_PER_BYTE < config.min_data_length
         {
-            return Err(E2EError::InvalidConfiguration(
-                "Minimum Data length shall be between 5B and 4096B".into(),
-            ));
+            return Err(E2EError::MinDataLengthOutOfRange {
+                min: 5,
+                max: 4096,
+                actual: config.min_data_length as usize,
+            });
         }
         if config.max_data_length < config.min_data_length || 4096 * 8 < config.max_data_length {
-            return Err(E2EError::InvalidConfiguration(
-                "Maximum Data length shall be between MinDataLength and 4096B".into(),
-            ));
+            return Err(E2EError::MaxDataLengthOutOfRange {
+                min: (config.min_data_length / BITS_PER_BYTE) as usize,
+                max: 4096,
+                actual: config.max_data_length as usize,
+            });
+        }
+        if config.min_data_length - 5 * BITS_PER_BYTE < config.offset {
+            return Err(E2EError::OffsetBeyondHeader {
+                offset: config.offset as usize,
+                header_length: HEADER_LENGTH,
+            });
         }
         if config.max_delta_counter == 0 || config.max_delta_counter == COUNTER_MAX {
-            return Err(E2EError::InvalidConfiguration(format!(
-                "Max delta counter must be between 1 and {}",
-                COUNTER_MAX
-            )));
+            return Err(E2EError::MaxDeltaCounterInvalid {
+                max: COUNTER_MAX as u32,
+            });
         }
         Ok(())
     }
     /// Validate data length against min/max constraints
-    fn validate_length(&self, len: u16) -> E2EResult<()> {
-        let min_bytes = self.config.min_data_length / BITS_PER_BYTE;
-        let max_bytes = self.config.max_data_length / BITS_PER_BYTE;
+    fn validate_length(&self, len: usize) -> E2EResult<()> {
+        let min_bytes = (self.config.min_data_length / BITS_PER_BYTE) as usize;
+        let max_bytes = (self.config.max_data_length / BITS_PER_BYTE) as usize;
         if len < min_bytes || max_bytes < len {
-            return Err(E2EError::InvalidDataFormat(format!(
-                "Expected {} - {} bytes, got {} bytes",
-                min_bytes, max_bytes, len
-            )));
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: min_bytes,
+                expected_max: max_bytes,
+                actual: len,
+            });
         }
         Ok(())
     }
@@ -109,13 +133,7 @@ impl Profile6 {
         data[offset + 4] = self.counter;
     }
     fn compute_crc(&self, data: &[u8]) -> u16 {
-        let crc: Crc<u16> = Crc::<u16>::new(&CRC_16_IBM_3740);
-        let mut digest = crc.digest();
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        digest.update(&data[0..offset]); // crc calculation data before offset
-        digest.update(&data[(offset + 2)..]); // crc calculation data after offset
-        digest.update(&self.config.data_id.to_be_bytes());
-        digest.finalize()
+        compute_crc(&self.config, data)
     }
     fn write_crc(&self, calculated_crc: u16, data: &mut [u8]) {
         let offset = (self.config.offset / BITS_PER_BYTE) as usize;
@@ -126,16 +144,13 @@ impl Profile6 {
     }
 
     fn read_data_length(&self, data: &[u8]) -> u16 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        u16::from_be_bytes([data[offset + 2], data[offset + 3]])
+        Profile6Header::at(&self.config, data).data_length()
     }
     fn read_counter(&self, data: &[u8]) -> u8 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        data[offset + 4]
+        Profile6Header::at(&self.config, data).counter()
     }
     fn read_crc(&self, data: &[u8]) -> u16 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        u16::from_be_bytes([data[offset], data[offset + 1]])
+        Profile6Header::at(&self.config, data).crc()
     }
 
     fn do_checks(&mut self, check_items: Profile6Check) -> E2EStatus {
@@ -159,22 +174,43 @@ impl Profile6 {
                 as u8
         }
     }
-    fn validate_counter(&self, rx_counter: u8) -> E2EStatus {
+    fn validate_counter(&mut self, rx_counter: u8) -> E2EStatus {
         let delta = self.check_counter_delta(rx_counter);
-
-        if delta == 0 {
-            if self.initialized {
-                E2EStatus::Repeated
-            } else {
-                E2EStatus::Ok
-            }
-        } else if delta == 1 {
-            E2EStatus::Ok
-        } else if delta >= 2 && delta <= self.config.max_delta_counter {
-            E2EStatus::OkSomeLost
-        } else {
-            E2EStatus::WrongSequence
-        }
+        self.sync.check_delta(
+            &self.config.sync_policy,
+            delta.into(),
+            self.config.max_delta_counter.into(),
+        )
+    }
+    /// Read the header fields of the protected data without checking them
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn decode(&self, data: &[u8]) -> E2EResult<E2EHeaderFields> {
+        self.validate_length(data.len())?;
+        Ok(E2EHeaderFields {
+            counter: self.read_counter(data) as u32,
+            length: Some(self.read_data_length(data) as u32),
+            received_crc: self.read_crc(data) as u64,
+            computed_crc: self.compute_crc(data) as u64,
+            ..Default::default()
+        })
+    }
+    /// Check the protected data like `check` and report the decoded header,
+    /// the counter delta and the first failed check
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn check_detailed(&mut self, data: &[u8]) -> E2EResult<E2ECheckReport> {
+        let previous_counter = self.counter.into();
+        let header = self.decode(data)?;
+        let status = self.check(data)?;
+        Ok(E2ECheckReport::new(
+            status,
+            header,
+            previous_counter,
+            COUNTER_MODULO.into(),
+        ))
     }
 }
 
@@ -187,12 +223,12 @@ impl E2EProfile for Profile6 {
         Ok(Self {
             config,
             counter: 0,
-            initialized: false,
+            sync: SyncState::default(),
         })
     }
 
     fn protect(&mut self, data: &mut [u8]) -> E2EResult<()> {
-        self.validate_length(data.len() as u16)?;
+        self.validate_length(data.len())?;
         self.write_data_length(data);
         self.write_counter(data);
         let calculated_crc = self.compute_crc(data);
@@ -203,7 +239,7 @@ impl E2EProfile for Profile6 {
 
     fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus> {
         // Check data length
-        self.validate_length(data.len() as u16)?;
+        self.validate_length(data.len())?;
         let check_items = Profile6Check {
             rx_data_length: self.read_data_length(data),
             rx_counter: self.read_counter(data),
@@ -212,16 +248,147 @@ impl E2EProfile for Profile6 {
             data_len: data.len() as u16,
         };
         let status = self.do_checks(check_items);
-        if !self.initialized && matches!(status, E2EStatus::Ok | E2EStatus::OkSomeLost) {
-            self.initialized = true;
-        }
         Ok(status)
     }
+
+    fn protect_init(&mut self) {
+        self.counter = 0;
+    }
+
+    fn check_init(&mut self) {
+        self.counter = 0;
+        self.sync = SyncState::default();
+    }
+
+    fn snapshot(&self) -> E2ESnapshot {
+        self.sync.save(E2ESnapshot::new(
+            fingerprint(&self.config),
+            self.counter.into(),
+        ))
+    }
+
+    fn restore(&mut self, snapshot: &E2ESnapshot) -> E2EResult<()> {
+        snapshot.validate(fingerprint(&self.config), COUNTER_MAX.into())?;
+        self.sync = SyncState::load(&self.config.sync_policy, snapshot)?;
+        self.counter = snapshot.counter as u8;
+        Ok(())
+    }
+}
+
+impl E2EForward for Profile6 {
+    fn forward(&mut self, data: &mut [u8], status: E2EStatus) -> E2EResult<()> {
+        self.validate_length(data.len())?;
+        let action = ForwardAction::from_status(status)?;
+        match action {
+            ForwardAction::Repeat => self.counter = self.counter.wrapping_sub(1),
+            ForwardAction::Skip => {
+                self.counter = self.counter.wrapping_add(self.config.max_delta_counter)
+            }
+            ForwardAction::Protect | ForwardAction::CorruptCrc => {}
+        }
+        self.protect(data)?;
+        if action == ForwardAction::CorruptCrc {
+            let calculated_crc = self.read_crc(data);
+            self.write_crc(!calculated_crc, data);
+        }
+        Ok(())
+    }
+}
+
+impl E2EHeaderLayout for Profile6 {
+    fn header_offset(&self) -> usize {
+        self.config.offset as usize
+    }
+
+    fn header_length(&self) -> usize {
+        HEADER_LENGTH * BITS_PER_BYTE as usize
+    }
+}
+
+/// Zero-copy view of the E2E header of Profile 6 protected data
+///
+/// The view only reads the data, so frames can be inspected without a
+/// profile instance and without affecting any receiver state.
+#[derive(Debug, Clone, Copy)]
+pub struct Profile6Header<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Profile6Header<'a> {
+    /// Locate the header in `data` as configured
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+    pub fn new(config: &Profile6Config, data: &'a [u8]) -> E2EResult<Self> {
+        let header_end = (config.offset / BITS_PER_BYTE) as usize + HEADER_LENGTH;
+        if data.len() < header_end {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: header_end,
+                expected_max: usize::MAX,
+                actual: data.len(),
+            });
+        }
+        Ok(Self::at(config, data))
+    }
+    pub(crate) fn at(config: &Profile6Config, data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: (config.offset / BITS_PER_BYTE) as usize,
+        }
+    }
+    /// Length field
+    pub fn data_length(&self) -> u16 {
+        let offset = self.offset;
+        u16::from_be_bytes([self.data[offset + 2], self.data[offset + 3]])
+    }
+    /// Sequence counter
+    pub fn counter(&self) -> u8 {
+        let offset = self.offset;
+        self.data[offset + 4]
+    }
+    /// CRC as received
+    pub fn crc(&self) -> u16 {
+        let offset = self.offset;
+        u16::from_be_bytes([self.data[offset], self.data[offset + 1]])
+    }
+}
+
+pub(crate) fn compute_crc(config: &Profile6Config, data: &[u8]) -> u16 {
+    let crc: Crc<u16> = Crc::<u16>::new(&CRC_16_IBM_3740);
+    let mut digest = crc.digest();
+    let offset = (config.offset / BITS_PER_BYTE) as usize;
+    digest.update(&data[0..offset]); // crc calculation data before offset
+    digest.update(&data[(offset + 2)..]); // crc calculation data after offset
+    digest.update(&config.data_id.to_be_bytes());
+    digest.finalize()
+}
+
+/// Verify the CRC of Profile 6 protected data without a profile instance
+///
+/// # Errors
+/// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+pub fn verify_crc(config: &Profile6Config, data: &[u8]) -> E2EResult<bool> {
+    let header = Profile6Header::new(config, data)?;
+    Ok(header.crc() == compute_crc(config, data))
+}
+
+/// Fingerprint of the configuration, see `E2ESnapshot`
+fn fingerprint(config: &Profile6Config) -> u32 {
+    Fingerprint::new("Profile 6")
+        .field(&config.data_id.to_be_bytes())
+        .field(&config.offset.to_be_bytes())
+        .field(&config.min_data_length.to_be_bytes())
+        .field(&config.max_data_length.to_be_bytes())
+        .field(&config.max_delta_counter.to_be_bytes())
+        .field(&config.sync_policy.to_bytes())
+        .finalize()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::E2ECheckItem;
     #[test]
     fn test_profile6_basic_example() {
         let mut profile_tx = Profile6::new(Profile6Config::default()).unwrap();
@@ -244,6 +411,7 @@ mod tests {
     fn test_profile6_offset_example() {
         let config = Profile6Config {
             offset: 64,
+            min_data_length: 104,
             ..Default::default()
         };
 
@@ -291,4 +459,157 @@ mod tests {
         assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
         profile_tx.protect(&mut data).unwrap();
     }
+
+    #[test]
+    fn test_profile6_forward() {
+        let mut profile_tx = Profile6::new(Profile6Config::default()).unwrap();
+        let mut profile_rx = Profile6::new(Profile6Config::default()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.forward(&mut data, E2EStatus::Ok).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::Repeated).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+        profile_tx
+            .forward(&mut data, E2EStatus::OkSomeLost)
+            .unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::CrcError).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::CrcError);
+        profile_tx
+            .forward(&mut data, E2EStatus::WrongSequence)
+            .unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+        assert_eq!(
+            profile_tx.forward(&mut data, E2EStatus::NoNewData),
+            Err(E2EError::InvalidForwardStatus(E2EStatus::NoNewData))
+        );
+    }
+
+    #[test]
+    fn test_profile6_check_detailed() {
+        let mut profile_tx = Profile6::new(Profile6Config::default()).unwrap();
+        let mut profile_rx = Profile6::new(Profile6Config::default()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::Ok);
+        assert_eq!(report.failed_check, None);
+        assert_eq!(report.header.length, Some(8));
+        assert_eq!(report.header.data_id, None);
+        assert_eq!(report.header.received_crc, 0xb155);
+        assert_eq!(report.header.computed_crc, 0xb155);
+        // two frames lost
+        for _ in 0..3 {
+            profile_tx.protect(&mut data).unwrap();
+        }
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::WrongSequence);
+        assert_eq!(report.failed_check, Some(E2ECheckItem::Counter));
+        assert_eq!(report.previous_counter, 0);
+        assert_eq!(report.header.counter, 3);
+        assert_eq!(report.counter_delta, 3);
+        // the CRC is checked first
+        data[3] ^= 0x01;
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::CrcError);
+        assert_eq!(report.failed_check, Some(E2ECheckItem::Crc));
+        assert_ne!(report.header.received_crc, report.header.computed_crc);
+        assert_eq!(report.header.length, Some(9));
+    }
+
+    #[test]
+    fn test_profile6_header_view() {
+        let config = Profile6Config::default();
+        let mut profile_tx = Profile6::new(config.clone()).unwrap();
+        let mut profile_rx = Profile6::new(config.clone()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(Profile6Header::new(&config, &data).unwrap().crc(), 0xb155);
+        profile_tx.protect(&mut data).unwrap();
+        let header = Profile6Header::new(&config, &data).unwrap();
+        assert_eq!(header.data_length(), 8);
+        assert_eq!(header.counter(), 1);
+        assert!(verify_crc(&config, &data).unwrap());
+        // inspecting the frame leaves the receiver untouched
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.counter, 1);
+
+        data[6] ^= 0x01;
+        assert!(!verify_crc(&config, &data).unwrap());
+        assert_eq!(
+            Profile6Header::new(&config, &data[..4]).unwrap_err(),
+            E2EError::LengthOutOfRange {
+                expected_min: 5,
+                expected_max: usize::MAX,
+                actual: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn test_profile6_snapshot_restore() {
+        let config = Profile6Config::default();
+        let mut profile_tx = Profile6::new(config.clone()).unwrap();
+        let mut profile_rx = Profile6::new(config.clone()).unwrap();
+
+        let mut data = vec![0x00; 8];
+        for _ in 0..5 {
+            profile_tx.protect(&mut data).unwrap();
+            profile_rx.check(&data).unwrap();
+        }
+        let snapshot = profile_rx.snapshot();
+        assert_eq!(snapshot.counter, 4);
+        assert!(snapshot.initialized);
+
+        // a receiver restarting without its state flags the next frame
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_fresh = Profile6::new(config.clone()).unwrap();
+        assert_eq!(
+            profile_fresh.check(&data).unwrap(),
+            E2EStatus::WrongSequence
+        );
+        let mut profile_restored = Profile6::new(config.clone()).unwrap();
+        profile_restored.restore(&snapshot).unwrap();
+        assert_eq!(profile_restored.check(&data).unwrap(), E2EStatus::Ok);
+
+        let other_config = Profile6Config {
+            data_id: 0x4321,
+            ..config
+        };
+        let mut profile_other = Profile6::new(other_config).unwrap();
+        assert!(matches!(
+            profile_other.restore(&snapshot),
+            Err(E2EError::SnapshotConfigMismatch { .. })
+        ));
+        assert_eq!(profile_other.counter, 0);
+        let out_of_range = E2ESnapshot {
+            counter: 0x100,
+            ..snapshot
+        };
+        assert_eq!(
+            profile_restored.restore(&out_of_range),
+            Err(E2EError::SnapshotInvalid)
+        );
+    }
+
+    #[test]
+    fn test_profile6_first_message() {
+        // the first message is checked against the initial counter 0, so only
+        // a sender starting from it is accepted
+        let config = Profile6Config::default();
+        let mut profile_tx = Profile6::new(config.clone()).unwrap();
+        let mut data = vec![0x00; 8];
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile6::new(config.clone()).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile6::new(config).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+    }
 }
@@ -0,0 +1,365 @@
+//! # Shared implementation of E2E Profiles 7M and 8M
+//!
+//! Profiles 7M and 8M append the message metadata to the header of their
+//! base profile:
+//! [... | DATA ID(4B) | TYPE(2b) RESULT(2b) SOURCE ID(28b) | DATA ...]
+//!
+//! The types of this module are instantiated by `profile7m` and `profile8m`,
+//! whose configuration selects the base profile.
+use crate::profiles::check_header_fits;
+use crate::{
+    E2ECheckReport, E2EError, E2EForward, E2EHeaderFields, E2EHeaderLayout, E2EProfile, E2EResult,
+    E2ESnapshot, E2EStatus,
+};
+use core::fmt;
+
+const BITS_PER_BYTE: usize = 8;
+/// Length of the message type, message result and source ID
+const METADATA_LENGTH: usize = 4;
+
+pub(crate) mod sealed {
+    use crate::{E2ECheckReport, E2EForward, E2EHeaderFields, E2EProfile, E2EResult};
+
+    /// Base profile and layout of a Profile 7M or 8M configuration
+    pub trait Layout: Clone + Sized {
+        /// Name of the base profile
+        const NAME: &'static str;
+        /// Name of the M variant of the profile
+        const NAME_M: &'static str;
+        /// Length of the E2E header of the base profile, in bytes
+        const HEADER_LENGTH: usize;
+        /// Base profile
+        type Profile: E2EProfile<Config = Self> + E2EForward + Clone;
+        /// Header view of the base profile
+        type Header<'a>: Copy + core::fmt::Debug;
+
+        fn offset(&self) -> usize;
+        fn min_data_length(&self) -> usize;
+        fn max_data_length(&self) -> usize;
+        fn set_min_data_length(&mut self, bits: usize);
+        fn fingerprint(&self, profile: &str) -> u32;
+        fn header<'a>(&self, data: &'a [u8]) -> Self::Header<'a>;
+        fn decode(profile: &Self::Profile, data: &[u8]) -> E2EResult<E2EHeaderFields>;
+        fn check_detailed(profile: &mut Self::Profile, data: &[u8]) -> E2EResult<E2ECheckReport>;
+    }
+}
+
+/// Configuration of Profile 7M or 8M, implemented by `Profile7Config` and
+/// `Profile8Config`
+pub trait Profile78Config: sealed::Layout {}
+
+impl<C: sealed::Layout> Profile78Config for C {}
+
+/// Check Item for Profiles 7M and 8M
+#[derive(Debug, Clone)]
+struct Profile78mCheck {
+    rx_source_id: u32,
+    rx_message_type: u8,
+    rx_message_result: u8,
+}
+
+/// E2E Profile 7M and 8M implementation, used as `Profile7m` and `Profile8m`
+///
+/// Identical to the base profile but includes additional fields in the CRC
+/// calculation: message_type, message_result, and source_id
+#[derive(Clone)]
+pub struct Profile78m<C: Profile78Config> {
+    base: C::Profile,
+    config: C,
+    pub message_type: u8,
+    pub message_result: u8,
+    pub source_id: u32,
+}
+
+impl<C: Profile78Config> Profile78m<C> {
+    /// Validate data length against min/max constraints
+    fn validate_length(&self, len: usize) -> E2EResult<()> {
+        let min_bytes = self.config.min_data_length() / BITS_PER_BYTE;
+        let max_bytes = self.config.max_data_length() / BITS_PER_BYTE;
+        if len < min_bytes || max_bytes < len {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: min_bytes,
+                expected_max: max_bytes,
+                actual: len,
+            });
+        }
+        Ok(())
+    }
+    fn metadata_offset(&self) -> usize {
+        self.config.offset() / BITS_PER_BYTE + C::HEADER_LENGTH
+    }
+    fn write_source_id(&self, data: &mut [u8]) {
+        let offset = self.metadata_offset();
+        data[offset..offset + 4].copy_from_slice(&self.source_id.to_be_bytes());
+    }
+    fn write_message_type(&self, data: &mut [u8]) {
+        let offset = self.metadata_offset();
+        data[offset] = (data[offset] & 0x3F) | ((self.message_type & 0x03) << 6);
+    }
+    fn write_message_result(&self, data: &mut [u8]) {
+        let offset = self.metadata_offset();
+        data[offset] = (data[offset] & 0xCF) | ((self.message_result & 0x03) << 4);
+    }
+    fn read_metadata(&self, data: &[u8]) -> Profile78mCheck {
+        let header = Profile78mHeader::at(&self.config, data);
+        Profile78mCheck {
+            rx_source_id: header.source_id(),
+            rx_message_result: header.message_result(),
+            rx_message_type: header.message_type(),
+        }
+    }
+    fn do_checks(&mut self, check_items: Profile78mCheck) -> E2EStatus {
+        if self.source_id != check_items.rx_source_id {
+            return E2EStatus::SourceIdError;
+        }
+        if self.message_result != check_items.rx_message_result {
+            return E2EStatus::MessageResultError;
+        }
+        if self.message_type != check_items.rx_message_type {
+            return E2EStatus::MessageTypeError;
+        }
+        E2EStatus::Ok
+    }
+    /// Check the metadata of data that passed the CRC, Data ID and length
+    /// checks of the base profile, before its counter check counts the data
+    ///
+    /// `base` is the base profile before its check, which is restored if the
+    /// metadata does not match, so data of another sender neither updates the
+    /// counter nor advances the synchronization.
+    fn check_metadata(
+        &mut self,
+        base: C::Profile,
+        check_items: Profile78mCheck,
+        status: E2EStatus,
+    ) -> E2EStatus {
+        match status {
+            E2EStatus::Ok
+            | E2EStatus::OkSomeLost
+            | E2EStatus::Sync
+            | E2EStatus::Repeated
+            | E2EStatus::WrongSequence => match self.do_checks(check_items) {
+                E2EStatus::Ok => status,
+                metadata_status => {
+                    self.base = base;
+                    metadata_status
+                }
+            },
+            _ => status,
+        }
+    }
+
+    /// Read the header fields of the protected data without checking them
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn decode(&self, data: &[u8]) -> E2EResult<E2EHeaderFields> {
+        self.validate_length(data.len())?;
+        let mut fields = C::decode(&self.base, data)?;
+        let metadata = self.read_metadata(data);
+        fields.source_id = Some(metadata.rx_source_id);
+        fields.message_type = Some(metadata.rx_message_type);
+        fields.message_result = Some(metadata.rx_message_result);
+        Ok(fields)
+    }
+
+    /// Check the protected data like `check` and report the decoded header,
+    /// including the metadata fields, the counter delta and the first failed check
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn check_detailed(&mut self, data: &[u8]) -> E2EResult<E2ECheckReport> {
+        self.validate_length(data.len())?;
+        let base = self.base.clone();
+        let mut report = C::check_detailed(&mut self.base, data)?;
+        let check_items = self.read_metadata(data);
+        report.header.source_id = Some(check_items.rx_source_id);
+        report.header.message_type = Some(check_items.rx_message_type);
+        report.header.message_result = Some(check_items.rx_message_result);
+        let status = self.check_metadata(base, check_items, report.status);
+        if status != report.status {
+            report = report.with_status(status);
+        }
+        Ok(report)
+    }
+}
+
+impl<C: Profile78Config + Default> Profile78m<C> {
+    /// Default configuration of the M variant
+    ///
+    /// The default configuration of the base profile leaves no room for the
+    /// metadata fields, so the minimum data length is raised to the header
+    /// length of the M variant.
+    pub fn default_config() -> C {
+        let mut config = C::default();
+        config.set_min_data_length((C::HEADER_LENGTH + METADATA_LENGTH) * BITS_PER_BYTE);
+        config
+    }
+}
+
+impl<C: Profile78Config> E2EProfile for Profile78m<C> {
+    type Config = C;
+
+    fn new(config: Self::Config) -> E2EResult<Self> {
+        // Validate using the validation of the base profile
+        let base = C::Profile::new(config.clone())?;
+
+        // The metadata fields extend the header, which has to fit into the minimal length
+        check_header_fits(
+            config.offset(),
+            C::HEADER_LENGTH + METADATA_LENGTH,
+            config.min_data_length(),
+            usize::MAX,
+        )?;
+        Ok(Self {
+            base,
+            config,
+            message_type: 0x00,
+            message_result: 0x00,
+            source_id: 0x0a0b0c0d,
+        })
+    }
+
+    fn protect(&mut self, data: &mut [u8]) -> E2EResult<()> {
+        self.validate_length(data.len())?;
+        // Write the metadata fields first, they are covered by the CRC
+        self.write_source_id(data);
+        self.write_message_result(data);
+        self.write_message_type(data);
+        self.base.protect(data)?;
+        Ok(())
+    }
+
+    fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus> {
+        self.validate_length(data.len())?;
+        let base = self.base.clone();
+        let status = self.base.check(data)?;
+        let check_items = self.read_metadata(data);
+        Ok(self.check_metadata(base, check_items, status))
+    }
+
+    fn protect_init(&mut self) {
+        self.base.protect_init();
+    }
+
+    fn check_init(&mut self) {
+        // The metadata fields are configured by the application and kept
+        self.base.check_init();
+    }
+
+    fn snapshot(&self) -> E2ESnapshot {
+        E2ESnapshot {
+            fingerprint: self.config.fingerprint(C::NAME_M),
+            message_type: self.message_type,
+            message_result: self.message_result,
+            source_id: self.source_id,
+            ..self.base.snapshot()
+        }
+    }
+
+    fn restore(&mut self, snapshot: &E2ESnapshot) -> E2EResult<()> {
+        snapshot.validate(self.config.fingerprint(C::NAME_M), u32::MAX)?;
+        // The counter state belongs to the base profile
+        self.base.restore(&E2ESnapshot {
+            fingerprint: self.config.fingerprint(C::NAME),
+            ..*snapshot
+        })?;
+        self.message_type = snapshot.message_type;
+        self.message_result = snapshot.message_result;
+        self.source_id = snapshot.source_id;
+        Ok(())
+    }
+}
+
+impl<C: Profile78Config> E2EForward for Profile78m<C> {
+    fn forward(&mut self, data: &mut [u8], status: E2EStatus) -> E2EResult<()> {
+        self.validate_length(data.len())?;
+        self.write_source_id(data);
+        self.write_message_result(data);
+        self.write_message_type(data);
+        self.base.forward(data, status)
+    }
+}
+
+impl<C: Profile78Config> E2EHeaderLayout for Profile78m<C> {
+    fn header_offset(&self) -> usize {
+        self.config.offset()
+    }
+
+    fn header_length(&self) -> usize {
+        (C::HEADER_LENGTH + METADATA_LENGTH) * BITS_PER_BYTE
+    }
+}
+
+/// Zero-copy view of the E2E header of Profile 7M or 8M protected data,
+/// including the metadata fields behind the base header, used as
+/// `Profile7mHeader` and `Profile8mHeader`
+pub struct Profile78mHeader<'a, C: Profile78Config> {
+    base: C::Header<'a>,
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a, C: Profile78Config> Profile78mHeader<'a, C> {
+    /// Locate the header in `data` as configured
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+    pub fn new(config: &C, data: &'a [u8]) -> E2EResult<Self> {
+        let header_end = config.offset() / BITS_PER_BYTE + C::HEADER_LENGTH + METADATA_LENGTH;
+        if data.len() < header_end {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: header_end,
+                expected_max: usize::MAX,
+                actual: data.len(),
+            });
+        }
+        Ok(Self::at(config, data))
+    }
+    fn at(config: &C, data: &'a [u8]) -> Self {
+        Self {
+            base: config.header(data),
+            data,
+            offset: config.offset() / BITS_PER_BYTE + C::HEADER_LENGTH,
+        }
+    }
+    /// Header fields shared with the base profile
+    pub fn base(&self) -> &C::Header<'a> {
+        &self.base
+    }
+    /// Source ID
+    pub fn source_id(&self) -> u32 {
+        let offset = self.offset;
+        u32::from_be_bytes([
+            self.data[offset],
+            self.data[offset + 1],
+            self.data[offset + 2],
+            self.data[offset + 3],
+        ]) & 0x0FFFFFFF
+    }
+    /// Message type
+    pub fn message_type(&self) -> u8 {
+        (self.data[self.offset] >> 6) & 0x03
+    }
+    /// Message result
+    pub fn message_result(&self) -> u8 {
+        (self.data[self.offset] >> 4) & 0x03
+    }
+}
+
+impl<C: Profile78Config> Clone for Profile78mHeader<'_, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C: Profile78Config> Copy for Profile78mHeader<'_, C> {}
+
+impl<C: Profile78Config> fmt::Debug for Profile78mHeader<'_, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Profile78mHeader")
+            .field("base", &self.base)
+            .field("data", &self.data)
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
@@ -2,115 +2,64 @@
 //!
 //! Profile 7M is identical to Profile 7 but includes additional fields
 //! in CRC calculation: message_type, message_result, and source_id
+//!
+//! The metadata fields extend the header to 24 bytes, which have to fit into
+//! the minimum data length behind the offset.
+//!
+//! The implementation is shared with Profile 8M, see `profile78m`.
 
-use crate::profile7::{Profile7, Profile7Config}; // Reuse Profile7Config
-use crate::{E2EProfile, E2EResult, E2EStatus};
-
-const BITS_PER_BYTE: u32 = 8;
+pub use crate::profile7::verify_crc; // The CRC covers the metadata fields as data
+use crate::profile7::{fingerprint_as, Profile7, Profile7Config, Profile7Header}; // Reuse Profile7Config
+use crate::profiles::profile78m::sealed::Layout;
+use crate::profiles::profile78m::{Profile78m, Profile78mHeader};
+use crate::{E2ECheckReport, E2EHeaderFields, E2EResult};
 
-/// Check Item for E2E Profile 7
-#[derive(Debug, Clone)]
-pub struct Profile7mCheck {
-    rx_source_id: u32,
-    rx_message_type: u8,
-    rx_message_result: u8,
-}
-
-/// E2E Profile 7m Implementation - minimal code by reusing Profile7 logic
-#[derive(Clone)]
-pub struct Profile7m {
-    base: Profile7,
-    config: Profile7Config,
-    pub message_type: u8,
-    pub message_result: u8,
-    pub source_id: u32,
-}
+impl Layout for Profile7Config {
+    const NAME: &'static str = "Profile 7";
+    const NAME_M: &'static str = "Profile 7M";
+    const HEADER_LENGTH: usize = 20;
+    type Profile = Profile7;
+    type Header<'a> = Profile7Header<'a>;
 
-impl Profile7m {
-    fn write_source_id(&self, data: &mut [u8]) {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        data[offset + 20..=offset + 23].copy_from_slice(&self.source_id.to_be_bytes());
+    fn offset(&self) -> usize {
+        self.offset as usize
     }
-    fn write_message_type(&self, data: &mut [u8]) {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        data[offset + 20] = (data[offset + 20] & 0x3F) | ((self.message_type & 0x03) << 6);
+    fn min_data_length(&self) -> usize {
+        self.min_data_length as usize
     }
-    fn write_message_result(&self, data: &mut [u8]) {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        data[offset + 20] = (data[offset + 20] & 0xCF) | ((self.message_result & 0x03) << 4);
+    fn max_data_length(&self) -> usize {
+        self.max_data_length as usize
     }
-    fn read_source_id(&self, data: &[u8]) -> u32 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        u32::from_be_bytes([
-            data[offset + 20],
-            data[offset + 21],
-            data[offset + 22],
-            data[offset + 23],
-        ]) & 0x0FFFFFFF
+    fn set_min_data_length(&mut self, bits: usize) {
+        self.min_data_length = bits as u32;
     }
-    fn read_message_type(&self, data: &[u8]) -> u8 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        (data[offset + 20] >> 6) & 0x03
+    fn fingerprint(&self, profile: &str) -> u32 {
+        fingerprint_as(self, profile)
     }
-    fn read_message_result(&self, data: &[u8]) -> u8 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        (data[offset + 20] >> 4) & 0x03
+    fn header<'a>(&self, data: &'a [u8]) -> Profile7Header<'a> {
+        Profile7Header::at(self, data)
     }
-    fn do_checks(&mut self, check_items: Profile7mCheck) -> E2EStatus {
-        if self.source_id != check_items.rx_source_id {
-            return E2EStatus::SourceIdError;
-        }
-        if self.message_result != check_items.rx_message_result {
-            return E2EStatus::MessageResultError;
-        }
-        if self.message_type != check_items.rx_message_type {
-            return E2EStatus::MessageTypeError;
-        }
-        E2EStatus::Ok
+    fn decode(profile: &Profile7, data: &[u8]) -> E2EResult<E2EHeaderFields> {
+        profile.decode(data)
     }
-}
-
-impl E2EProfile for Profile7m {
-    type Config = Profile7Config;
-
-    fn new(config: Self::Config) -> E2EResult<Self> {
-        // Validate using Profile7's validation
-        let base = crate::profile7::Profile7::new(config.clone())?; // This validates config
-        Ok(Self {
-            base,
-            config,
-            message_type: 0x00,
-            message_result: 0x00,
-            source_id: 0x0a0b0c0d,
-        })
+    fn check_detailed(profile: &mut Profile7, data: &[u8]) -> E2EResult<E2ECheckReport> {
+        profile.check_detailed(data)
     }
+}
 
-    fn protect(&mut self, data: &mut [u8]) -> E2EResult<()> {
-        // Write Profile7m specific fields first
-        self.write_source_id(data);
-        self.write_message_result(data);
-        self.write_message_type(data);
-        self.base.protect(data)?;
-        Ok(())
-    }
+/// E2E Profile 7m Implementation - minimal code by reusing Profile7 logic
+pub type Profile7m = Profile78m<Profile7Config>;
 
-    fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus> {
-        let mut status = self.base.check(data)?;
-        let check_items = Profile7mCheck {
-            rx_source_id: self.read_source_id(data),
-            rx_message_result: self.read_message_result(data),
-            rx_message_type: self.read_message_type(data),
-        };
-        if (status == E2EStatus::Ok) || (status == E2EStatus::OkSomeLost) {
-            status = self.do_checks(check_items);
-        }
-        Ok(status)
-    }
-}
+/// Zero-copy view of the E2E header of Profile 7M protected data, including the
+/// metadata fields behind the Profile 7 header
+pub type Profile7mHeader<'a> = Profile78mHeader<'a, Profile7Config>;
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::E2ECheckItem;
+    use crate::E2ESyncPolicy;
+    use crate::{E2EError, E2EProfile, E2ESnapshot, E2EStatus};
     #[test]
     fn test_profile7m_basic_request_example() {
         let config = Profile7Config {
@@ -269,4 +218,182 @@ mod tests {
         profile_rx.message_type = 1;
         assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
     }
+
+    #[test]
+    fn test_profile7m_check_detailed() {
+        let config = Profile7Config {
+            min_data_length: 192,
+            ..Default::default()
+        };
+        let mut profile_tx = Profile7m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile7m::new(config).unwrap();
+
+        let mut data = vec![0x00; 28];
+        profile_tx.source_id = 0x00123456;
+        profile_tx.message_result = 1;
+        profile_tx.message_type = 1;
+        profile_tx.protect(&mut data).unwrap();
+        profile_rx.source_id = 0x00123456;
+        profile_rx.message_result = 1;
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::MessageTypeError);
+        assert_eq!(report.failed_check, Some(E2ECheckItem::MessageType));
+        assert_eq!(report.header.source_id, Some(0x00123456));
+        assert_eq!(report.header.message_type, Some(1));
+        assert_eq!(report.header.message_result, Some(1));
+        assert_eq!(report.header.length, Some(28));
+        assert_eq!(report.counter_delta, 0);
+    }
+
+    #[test]
+    fn test_profile7m_header_view() {
+        let config = Profile7Config {
+            min_data_length: 192,
+            ..Default::default()
+        };
+        let mut profile_tx = Profile7m::new(config.clone()).unwrap();
+
+        let mut data = vec![0x00; 28];
+        profile_tx.source_id = 0x00123456;
+        profile_tx.message_result = 1;
+        profile_tx.message_type = 1;
+        profile_tx.protect(&mut data).unwrap();
+        let header = Profile7mHeader::new(&config, &data).unwrap();
+        assert_eq!(header.source_id(), 0x00123456);
+        assert_eq!(header.message_type(), 1);
+        assert_eq!(header.message_result(), 1);
+        assert_eq!(header.base().data_length(), 28);
+        assert_eq!(header.base().counter(), 0);
+        assert!(verify_crc(&config, &data).unwrap());
+        data[23] ^= 0x01;
+        assert!(!verify_crc(&config, &data).unwrap());
+        assert!(Profile7mHeader::new(&config, &data[..23]).is_err());
+    }
+
+    #[test]
+    fn test_profile7m_snapshot_restore() {
+        let config = Profile7Config {
+            min_data_length: 192,
+            ..Default::default()
+        };
+        let mut profile_tx = Profile7m::new(config.clone()).unwrap();
+        profile_tx.source_id = 0x00123456;
+        profile_tx.message_type = 1;
+        profile_tx.message_result = 1;
+        let mut data = vec![0x00; 28];
+        profile_tx.protect(&mut data).unwrap();
+
+        let snapshot = E2ESnapshot::from_bytes(&profile_tx.snapshot().to_bytes()).unwrap();
+        let mut profile_restored = Profile7m::new(config.clone()).unwrap();
+        profile_restored.restore(&snapshot).unwrap();
+        assert_eq!(profile_restored.source_id, 0x00123456);
+        assert_eq!(profile_restored.message_type, 1);
+        assert_eq!(profile_restored.message_result, 1);
+        let mut restored_data = data.clone();
+        profile_tx.protect(&mut data).unwrap();
+        profile_restored.protect(&mut restored_data).unwrap();
+        assert_eq!(restored_data, data);
+
+        // the snapshot of the base profile is not interchangeable
+        let mut profile_base = Profile7::new(config).unwrap();
+        assert!(matches!(
+            profile_base.restore(&snapshot),
+            Err(E2EError::SnapshotConfigMismatch { .. })
+        ));
+        assert!(matches!(
+            profile_restored.restore(&profile_base.snapshot()),
+            Err(E2EError::SnapshotConfigMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_profile7m_check_init_keeps_metadata() {
+        let config = Profile7Config {
+            min_data_length: 192,
+            ..Default::default()
+        };
+        let mut profile_tx = Profile7m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile7m::new(config.clone()).unwrap();
+        profile_tx.source_id = 0x00123456;
+        profile_tx.message_type = 1;
+        profile_rx.source_id = 0x00123456;
+        profile_rx.message_type = 1;
+
+        let mut data = vec![0x00; 28];
+        for _ in 0..3 {
+            profile_tx.protect(&mut data).unwrap();
+            assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        }
+        profile_tx.protect_init();
+        profile_rx.check_init();
+        assert_eq!(profile_tx.source_id, 0x00123456);
+        assert_eq!(profile_rx.message_type, 1);
+        profile_tx.protect(&mut data).unwrap();
+        let header = Profile7mHeader::new(&config, &data).unwrap();
+        assert_eq!(header.base().counter(), 0);
+        assert_eq!(header.source_id(), 0x00123456);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile7m_default_config() {
+        // the default of Profile 7 leaves no room for the metadata fields
+        assert!(Profile7m::new(Profile7Config::default()).is_err());
+        let config = Profile7m::default_config();
+        assert_eq!(config.min_data_length, 192);
+        let mut profile_tx = Profile7m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile7m::new(config).unwrap();
+
+        let mut data = vec![0x00; 24];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile7m_first_message() {
+        // the first message is checked against the initial counter 0, so only
+        // a sender starting from it is accepted
+        let config = Profile7m::default_config();
+        let mut profile_tx = Profile7m::new(config.clone()).unwrap();
+        let mut data = vec![0x00; 24];
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile7m::new(config.clone()).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile7m::new(config).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+    }
+
+    #[test]
+    fn test_profile7m_sync_ignores_other_source() {
+        let config = Profile7Config {
+            sync_policy: E2ESyncPolicy {
+                min_consecutive: 2,
+                ..Default::default()
+            },
+            ..Profile7m::default_config()
+        };
+        let mut profile_tx = Profile7m::new(config.clone()).unwrap();
+        let mut profile_other = Profile7m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile7m::new(config).unwrap();
+        profile_other.source_id = 0x00123456;
+
+        // data of another sender is rejected before its counter is evaluated,
+        // so it does not count towards the synchronization
+        let mut data = vec![0x00; 24];
+        profile_other.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::SourceIdError);
+        profile_other.protect(&mut data).unwrap();
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::SourceIdError);
+        assert_eq!(report.header.source_id, Some(0x00123456));
+
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Sync);
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
 }
@@ -9,16 +9,24 @@
 //!
 //! # Data layout
 //! [DATA ... | CRC(4B) | LENGTH(4B) | COUNTER(4B) | ID (4B) | DATA ...]
-use crate::{E2EError, E2EProfile, E2EResult, E2EStatus};
+use crate::profiles::{ForwardAction, SyncState};
+use crate::snapshot::Fingerprint;
+use crate::{
+    E2ECheckReport, E2EError, E2EForward, E2EHeaderFields, E2EHeaderLayout, E2EProfile, E2EResult,
+    E2ESnapshot, E2EStatus, E2ESyncPolicy,
+};
 use crc::{Crc, CRC_32_AUTOSAR};
 
 // Constants
 const BITS_PER_BYTE: u32 = 8;
+const HEADER_LENGTH: usize = 16;
 const COUNTER_MAX: u32 = 0xFFFFFFFF;
 const COUNTER_MODULO: u64 = 0x100000000;
 
 /// Configuration for E2E Profile 8
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(remote = "Self", default))]
 pub struct Profile8Config {
     /// data id
     pub data_id: u32,
@@ -30,6 +38,8 @@ pub struct Profile8Config {
     pub max_data_length: u32,
     /// Maximum allowed delta between consecutive counters
     pub max_delta_counter: u32,
+    /// Receiver behaviour at startup and after a loss of synchronization
+    pub sync_policy: E2ESyncPolicy,
 }
 
 /// Check Item for E2E Profile 8
@@ -51,10 +61,14 @@ impl Default for Profile8Config {
             min_data_length: 128,        // 16bytes
             max_data_length: 4294967295, // MAX(U32)
             max_delta_counter: 1,
+            sync_policy: E2ESyncPolicy::default(),
         }
     }
 }
 
+#[cfg(feature = "serde")]
+impl_validated_serde!(Profile8Config, Profile8::validate_config);
+
 /// E2E Profile 8 Implementation
 ///
 /// Implements AUTOSAR E2E Profile 8 protection mechanism
@@ -62,39 +76,47 @@ impl Default for Profile8Config {
 pub struct Profile8 {
     config: Profile8Config,
     counter: u32,
-    initialized: bool,
+    sync: SyncState,
 }
 
 impl Profile8 {
     /// Validate configuration parameters
     fn validate_config(config: &Profile8Config) -> E2EResult<()> {
         if config.min_data_length < 16 * BITS_PER_BYTE {
-            return Err(E2EError::InvalidConfiguration(
-                "Minimum Data length shall be larger than 16B".into(),
-            ));
+            return Err(E2EError::MinDataLengthOutOfRange {
+                min: 16,
+                max: usize::MAX,
+                actual: config.min_data_length as usize,
+            });
         }
         if config.max_data_length < config.min_data_length {
-            return Err(E2EError::InvalidConfiguration(
-                "Maximum Data length shall be larger than MinDataLength".into(),
-            ));
+            return Err(E2EError::MaxDataLengthOutOfRange {
+                min: (config.min_data_length / BITS_PER_BYTE) as usize,
+                max: usize::MAX,
+                actual: config.max_data_length as usize,
+            });
+        }
+        if config.min_data_length - 16 * BITS_PER_BYTE < config.offset {
+            return Err(E2EError::OffsetBeyondHeader {
+                offset: config.offset as usize,
+                header_length: HEADER_LENGTH,
+            });
         }
         if config.max_delta_counter == 0 || config.max_delta_counter == COUNTER_MAX {
-            return Err(E2EError::InvalidConfiguration(format!(
-                "Max delta counter must be between 1 and {}",
-                COUNTER_MAX
-            )));
+            return Err(E2EError::MaxDeltaCounterInvalid { max: COUNTER_MAX });
         }
         Ok(())
     }
     /// Validate data length against min/max constraints
-    fn validate_length(&self, len: u32) -> E2EResult<()> {
-        let min_bytes = self.config.min_data_length / BITS_PER_BYTE;
-        let max_bytes = self.config.max_data_length / BITS_PER_BYTE;
+    fn validate_length(&self, len: usize) -> E2EResult<()> {
+        let min_bytes = (self.config.min_data_length / BITS_PER_BYTE) as usize;
+        let max_bytes = (self.config.max_data_length / BITS_PER_BYTE) as usize;
         if len < min_bytes || max_bytes < len {
-            return Err(E2EError::InvalidDataFormat(format!(
-                "Expected {} - {} bytes, got {} bytes",
-                min_bytes, max_bytes, len
-            )));
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: min_bytes,
+                expected_max: max_bytes,
+                actual: len,
+            });
         }
         Ok(())
     }
@@ -112,12 +134,7 @@ impl Profile8 {
         data[offset + 12..=offset + 15].copy_from_slice(&self.config.data_id.to_be_bytes());
     }
     fn compute_crc(&self, data: &[u8]) -> u32 {
-        let crc: Crc<u32> = Crc::<u32>::new(&CRC_32_AUTOSAR);
-        let mut digest = crc.digest();
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        digest.update(&data[0..offset]); // crc calculation data before offset
-        digest.update(&data[(offset + 4)..]); // crc calculation data after offset
-        digest.finalize()
+        compute_crc(&self.config, data)
     }
     fn write_crc(&self, calculated_crc: u32, data: &mut [u8]) {
         let offset = (self.config.offset / BITS_PER_BYTE) as usize;
@@ -132,40 +149,16 @@ impl Profile8 {
     }
 
     fn read_data_length(&self, data: &[u8]) -> u32 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        u32::from_be_bytes([
-            data[offset + 4],
-            data[offset + 5],
-            data[offset + 6],
-            data[offset + 7],
-        ])
+        Profile8Header::at(&self.config, data).data_length()
     }
     fn read_counter(&self, data: &[u8]) -> u32 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        u32::from_be_bytes([
-            data[offset + 8],
-            data[offset + 9],
-            data[offset + 10],
-            data[offset + 11],
-        ])
+        Profile8Header::at(&self.config, data).counter()
     }
     fn read_data_id(&self, data: &[u8]) -> u32 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        u32::from_be_bytes([
-            data[offset + 12],
-            data[offset + 13],
-            data[offset + 14],
-            data[offset + 15],
-        ])
+        Profile8Header::at(&self.config, data).data_id()
     }
     fn read_crc(&self, data: &[u8]) -> u32 {
-        let offset = (self.config.offset / BITS_PER_BYTE) as usize;
-        u32::from_be_bytes([
-            data[offset],
-            data[offset + 1],
-            data[offset + 2],
-            data[offset + 3],
-        ])
+        Profile8Header::at(&self.config, data).crc()
     }
 
     fn do_checks(&mut self, check_items: Profile8Check) -> E2EStatus {
@@ -192,22 +185,44 @@ impl Profile8 {
                 as u32
         }
     }
-    fn validate_counter(&self, rx_counter: u32) -> E2EStatus {
+    fn validate_counter(&mut self, rx_counter: u32) -> E2EStatus {
         let delta = self.check_counter_delta(rx_counter);
-
-        if delta == 0 {
-            if self.initialized {
-                E2EStatus::Repeated
-            } else {
-                E2EStatus::Ok
-            }
-        } else if delta == 1 {
-            E2EStatus::Ok
-        } else if delta >= 2 && delta <= self.config.max_delta_counter {
-            E2EStatus::OkSomeLost
-        } else {
-            E2EStatus::WrongSequence
-        }
+        self.sync.check_delta(
+            &self.config.sync_policy,
+            delta,
+            self.config.max_delta_counter,
+        )
+    }
+    /// Read the header fields of the protected data without checking them
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn decode(&self, data: &[u8]) -> E2EResult<E2EHeaderFields> {
+        self.validate_length(data.len())?;
+        Ok(E2EHeaderFields {
+            counter: self.read_counter(data),
+            length: Some(self.read_data_length(data)),
+            data_id: Some(self.read_data_id(data)),
+            received_crc: self.read_crc(data) as u64,
+            computed_crc: self.compute_crc(data) as u64,
+            ..Default::default()
+        })
+    }
+    /// Check the protected data like `check` and report the decoded header,
+    /// the counter delta and the first failed check
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data length is invalid
+    pub fn check_detailed(&mut self, data: &[u8]) -> E2EResult<E2ECheckReport> {
+        let previous_counter = self.counter;
+        let header = self.decode(data)?;
+        let status = self.check(data)?;
+        Ok(E2ECheckReport::new(
+            status,
+            header,
+            previous_counter,
+            COUNTER_MODULO,
+        ))
     }
 }
 
@@ -220,12 +235,12 @@ impl E2EProfile for Profile8 {
         Ok(Self {
             config,
             counter: 0,
-            initialized: false,
+            sync: SyncState::default(),
         })
     }
 
     fn protect(&mut self, data: &mut [u8]) -> E2EResult<()> {
-        self.validate_length(data.len() as u32)?;
+        self.validate_length(data.len())?;
         self.write_data_length(data);
         self.write_counter(data);
         self.write_data_id(data);
@@ -237,7 +252,7 @@ impl E2EProfile for Profile8 {
 
     fn check(&mut self, data: &[u8]) -> E2EResult<E2EStatus> {
         // Check data length
-        self.validate_length(data.len() as u32)?;
+        self.validate_length(data.len())?;
         let check_items = Profile8Check {
             rx_data_length: self.read_data_length(data),
             rx_counter: self.read_counter(data),
@@ -247,16 +262,175 @@ impl E2EProfile for Profile8 {
             data_len: data.len() as u32,
         };
         let status = self.do_checks(check_items);
-        if !self.initialized && matches!(status, E2EStatus::Ok | E2EStatus::OkSomeLost) {
-            self.initialized = true;
-        }
         Ok(status)
     }
+
+    fn protect_init(&mut self) {
+        self.counter = 0;
+    }
+
+    fn check_init(&mut self) {
+        self.counter = 0;
+        self.sync = SyncState::default();
+    }
+
+    fn snapshot(&self) -> E2ESnapshot {
+        self.sync
+            .save(E2ESnapshot::new(fingerprint(&self.config), self.counter))
+    }
+
+    fn restore(&mut self, snapshot: &E2ESnapshot) -> E2EResult<()> {
+        snapshot.validate(fingerprint(&self.config), COUNTER_MAX)?;
+        self.sync = SyncState::load(&self.config.sync_policy, snapshot)?;
+        self.counter = snapshot.counter;
+        Ok(())
+    }
+}
+
+impl E2EForward for Profile8 {
+    fn forward(&mut self, data: &mut [u8], status: E2EStatus) -> E2EResult<()> {
+        self.validate_length(data.len())?;
+        let action = ForwardAction::from_status(status)?;
+        match action {
+            ForwardAction::Repeat => self.counter = self.counter.wrapping_sub(1),
+            ForwardAction::Skip => {
+                self.counter = self.counter.wrapping_add(self.config.max_delta_counter)
+            }
+            ForwardAction::Protect | ForwardAction::CorruptCrc => {}
+        }
+        self.protect(data)?;
+        if action == ForwardAction::CorruptCrc {
+            let calculated_crc = self.read_crc(data);
+            self.write_crc(!calculated_crc, data);
+        }
+        Ok(())
+    }
+}
+
+impl E2EHeaderLayout for Profile8 {
+    fn header_offset(&self) -> usize {
+        self.config.offset as usize
+    }
+
+    fn header_length(&self) -> usize {
+        HEADER_LENGTH * BITS_PER_BYTE as usize
+    }
+}
+
+/// Zero-copy view of the E2E header of Profile 8 protected data
+///
+/// The view only reads the data, so frames can be inspected without a
+/// profile instance and without affecting any receiver state.
+#[derive(Debug, Clone, Copy)]
+pub struct Profile8Header<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Profile8Header<'a> {
+    /// Locate the header in `data` as configured
+    ///
+    /// # Errors
+    /// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+    pub fn new(config: &Profile8Config, data: &'a [u8]) -> E2EResult<Self> {
+        let header_end = (config.offset / BITS_PER_BYTE) as usize + HEADER_LENGTH;
+        if data.len() < header_end {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: header_end,
+                expected_max: usize::MAX,
+                actual: data.len(),
+            });
+        }
+        Ok(Self::at(config, data))
+    }
+    pub(crate) fn at(config: &Profile8Config, data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: (config.offset / BITS_PER_BYTE) as usize,
+        }
+    }
+    /// Length field
+    pub fn data_length(&self) -> u32 {
+        let offset = self.offset;
+        u32::from_be_bytes([
+            self.data[offset + 4],
+            self.data[offset + 5],
+            self.data[offset + 6],
+            self.data[offset + 7],
+        ])
+    }
+    /// Sequence counter
+    pub fn counter(&self) -> u32 {
+        let offset = self.offset;
+        u32::from_be_bytes([
+            self.data[offset + 8],
+            self.data[offset + 9],
+            self.data[offset + 10],
+            self.data[offset + 11],
+        ])
+    }
+    /// Data ID
+    pub fn data_id(&self) -> u32 {
+        let offset = self.offset;
+        u32::from_be_bytes([
+            self.data[offset + 12],
+            self.data[offset + 13],
+            self.data[offset + 14],
+            self.data[offset + 15],
+        ])
+    }
+    /// CRC as received
+    pub fn crc(&self) -> u32 {
+        let offset = self.offset;
+        u32::from_be_bytes([
+            self.data[offset],
+            self.data[offset + 1],
+            self.data[offset + 2],
+            self.data[offset + 3],
+        ])
+    }
+}
+
+fn compute_crc(config: &Profile8Config, data: &[u8]) -> u32 {
+    let crc: Crc<u32> = Crc::<u32>::new(&CRC_32_AUTOSAR);
+    let mut digest = crc.digest();
+    let offset = (config.offset / BITS_PER_BYTE) as usize;
+    digest.update(&data[0..offset]); // crc calculation data before offset
+    digest.update(&data[(offset + 4)..]); // crc calculation data after offset
+    digest.finalize()
+}
+
+/// Verify the CRC of Profile 8 protected data without a profile instance
+///
+/// # Errors
+/// Returns `E2EError::LengthOutOfRange` if the data does not hold the header
+pub fn verify_crc(config: &Profile8Config, data: &[u8]) -> E2EResult<bool> {
+    let header = Profile8Header::new(config, data)?;
+    Ok(header.crc() == compute_crc(config, data))
+}
+
+/// Fingerprint of the configuration, see `E2ESnapshot`
+pub(crate) fn fingerprint(config: &Profile8Config) -> u32 {
+    fingerprint_as(config, "Profile 8")
+}
+
+/// Fingerprint of the configuration for the given profile name, which
+/// separates the snapshots of a variant from the ones of the base profile
+pub(crate) fn fingerprint_as(config: &Profile8Config, profile: &str) -> u32 {
+    Fingerprint::new(profile)
+        .field(&config.data_id.to_be_bytes())
+        .field(&config.offset.to_be_bytes())
+        .field(&config.min_data_length.to_be_bytes())
+        .field(&config.max_data_length.to_be_bytes())
+        .field(&config.max_delta_counter.to_be_bytes())
+        .field(&config.sync_policy.to_bytes())
+        .finalize()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::E2ECheckItem;
     #[test]
     fn test_profile8_basic_example() {
         let mut profile_tx = Profile8::new(Profile8Config::default()).unwrap();
@@ -294,6 +468,7 @@ mod tests {
     fn test_profile8_offset_example() {
         let config = Profile8Config {
             offset: 64,
+            min_data_length: 192,
             ..Default::default()
         };
 
@@ -331,6 +506,7 @@ mod tests {
     fn test_profile8_counter_wraparound() {
         let config = Profile8Config {
             offset: 64,
+            min_data_length: 192,
             ..Default::default()
         };
 
@@ -372,4 +548,162 @@ mod tests {
         assert_eq!(data[19], 0x00);
         assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
     }
+
+    #[test]
+    fn test_profile8_forward() {
+        let mut profile_tx = Profile8::new(Profile8Config::default()).unwrap();
+        let mut profile_rx = Profile8::new(Profile8Config::default()).unwrap();
+
+        let mut data = vec![0x00; 16];
+        profile_tx.forward(&mut data, E2EStatus::Ok).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::Repeated).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+        profile_tx
+            .forward(&mut data, E2EStatus::OkSomeLost)
+            .unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        profile_tx.forward(&mut data, E2EStatus::CrcError).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::CrcError);
+        profile_tx
+            .forward(&mut data, E2EStatus::WrongSequence)
+            .unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+        assert_eq!(
+            profile_tx.forward(&mut data, E2EStatus::NoNewData),
+            Err(E2EError::InvalidForwardStatus(E2EStatus::NoNewData))
+        );
+    }
+
+    #[test]
+    fn test_profile8_check_detailed() {
+        let mut profile_tx = Profile8::new(Profile8Config::default()).unwrap();
+        let mut profile_rx = Profile8::new(Profile8Config::default()).unwrap();
+
+        let mut data = vec![0x00; 20];
+        profile_tx.protect(&mut data).unwrap();
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::Ok);
+        assert_eq!(report.failed_check, None);
+        assert_eq!(report.header.length, Some(20));
+        assert_eq!(report.header.data_id, Some(0x0a0b0c0d));
+        assert_eq!(report.header.received_crc, 0x41494e52);
+        assert_eq!(report.header.computed_crc, 0x41494e52);
+        // two frames lost
+        for _ in 0..3 {
+            profile_tx.protect(&mut data).unwrap();
+        }
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::WrongSequence);
+        assert_eq!(report.failed_check, Some(E2ECheckItem::Counter));
+        assert_eq!(report.previous_counter, 0);
+        assert_eq!(report.header.counter, 3);
+        assert_eq!(report.counter_delta, 3);
+        // the CRC is checked first
+        data[12] ^= 0x01;
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::CrcError);
+        assert_eq!(report.failed_check, Some(E2ECheckItem::Crc));
+        assert_ne!(report.header.received_crc, report.header.computed_crc);
+        assert_eq!(report.header.data_id, Some(0x0b0b0c0d));
+    }
+
+    #[test]
+    fn test_profile8_header_view() {
+        let config = Profile8Config::default();
+        let mut profile_tx = Profile8::new(config.clone()).unwrap();
+        let mut profile_rx = Profile8::new(config.clone()).unwrap();
+
+        let mut data = vec![0x00; 20];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(
+            Profile8Header::new(&config, &data).unwrap().crc(),
+            0x41494e52
+        );
+        profile_tx.protect(&mut data).unwrap();
+        let header = Profile8Header::new(&config, &data).unwrap();
+        assert_eq!(header.data_length(), 20);
+        assert_eq!(header.counter(), 1);
+        assert_eq!(header.data_id(), 0x0a0b0c0d);
+        assert!(verify_crc(&config, &data).unwrap());
+        // inspecting the frame leaves the receiver untouched
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.counter, 1);
+
+        data[16] ^= 0x01;
+        assert!(!verify_crc(&config, &data).unwrap());
+        assert_eq!(
+            Profile8Header::new(&config, &data[..15]).unwrap_err(),
+            E2EError::LengthOutOfRange {
+                expected_min: 16,
+                expected_max: usize::MAX,
+                actual: 15,
+            }
+        );
+    }
+
+    #[test]
+    fn test_profile8_snapshot_restore() {
+        let config = Profile8Config::default();
+        let mut profile_tx = Profile8::new(config.clone()).unwrap();
+        let mut profile_rx = Profile8::new(config.clone()).unwrap();
+
+        let mut data = vec![0x00; 20];
+        for _ in 0..5 {
+            profile_tx.protect(&mut data).unwrap();
+            profile_rx.check(&data).unwrap();
+        }
+        let snapshot = profile_rx.snapshot();
+        assert_eq!(snapshot.counter, 4);
+        assert!(snapshot.initialized);
+
+        // a receiver restarting without its state flags the next frame
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_fresh = Profile8::new(config.clone()).unwrap();
+        assert_eq!(
+            profile_fresh.check(&data).unwrap(),
+            E2EStatus::WrongSequence
+        );
+        let mut profile_restored = Profile8::new(config.clone()).unwrap();
+        profile_restored.restore(&snapshot).unwrap();
+        assert_eq!(profile_restored.check(&data).unwrap(), E2EStatus::Ok);
+
+        let other_config = Profile8Config {
+            data_id: 0x01020304,
+            ..config
+        };
+        let mut profile_other = Profile8::new(other_config).unwrap();
+        assert!(matches!(
+            profile_other.restore(&snapshot),
+            Err(E2EError::SnapshotConfigMismatch { .. })
+        ));
+        assert_eq!(profile_other.counter, 0);
+        // more consecutive messages than the sync policy requires
+        let out_of_sync = E2ESnapshot {
+            sync_state: [1, 0],
+            ..snapshot
+        };
+        assert_eq!(
+            profile_restored.restore(&out_of_sync),
+            Err(E2EError::SnapshotInvalid)
+        );
+    }
+
+    #[test]
+    fn test_profile8_first_message() {
+        // the first message is checked against the initial counter 0, so only
+        // a sender starting from it is accepted
+        let config = Profile8Config::default();
+        let mut profile_tx = Profile8::new(config.clone()).unwrap();
+        let mut data = vec![0x00; 16];
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile8::new(config.clone()).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile8::new(config).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+    }
 }
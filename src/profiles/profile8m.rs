@@ -0,0 +1,287 @@
+//! # E2E Profile 8M Implementation
+//!
+//! Profile 8M is identical to Profile 8 but includes additional fields
+//! in CRC calculation: message_type, message_result, and source_id
+//!
+//! The metadata fields extend the header to 20 bytes, which have to fit into
+//! the minimum data length behind the offset.
+//!
+//! The implementation is shared with Profile 7M, see `profile78m`.
+
+pub use crate::profile8::verify_crc; // The CRC covers the metadata fields as data
+use crate::profile8::{fingerprint_as, Profile8, Profile8Config, Profile8Header}; // Reuse Profile8Config
+use crate::profiles::profile78m::sealed::Layout;
+use crate::profiles::profile78m::{Profile78m, Profile78mHeader};
+use crate::{E2ECheckReport, E2EHeaderFields, E2EResult};
+
+impl Layout for Profile8Config {
+    const NAME: &'static str = "Profile 8";
+    const NAME_M: &'static str = "Profile 8M";
+    const HEADER_LENGTH: usize = 16;
+    type Profile = Profile8;
+    type Header<'a> = Profile8Header<'a>;
+
+    fn offset(&self) -> usize {
+        self.offset as usize
+    }
+    fn min_data_length(&self) -> usize {
+        self.min_data_length as usize
+    }
+    fn max_data_length(&self) -> usize {
+        self.max_data_length as usize
+    }
+    fn set_min_data_length(&mut self, bits: usize) {
+        self.min_data_length = bits as u32;
+    }
+    fn fingerprint(&self, profile: &str) -> u32 {
+        fingerprint_as(self, profile)
+    }
+    fn header<'a>(&self, data: &'a [u8]) -> Profile8Header<'a> {
+        Profile8Header::at(self, data)
+    }
+    fn decode(profile: &Profile8, data: &[u8]) -> E2EResult<E2EHeaderFields> {
+        profile.decode(data)
+    }
+    fn check_detailed(profile: &mut Profile8, data: &[u8]) -> E2EResult<E2ECheckReport> {
+        profile.check_detailed(data)
+    }
+}
+
+/// E2E Profile 8m Implementation - minimal code by reusing Profile8 logic
+pub type Profile8m = Profile78m<Profile8Config>;
+
+/// Zero-copy view of the E2E header of Profile 8M protected data, including the
+/// metadata fields behind the Profile 8 header
+pub type Profile8mHeader<'a> = Profile78mHeader<'a, Profile8Config>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::E2ESyncPolicy;
+    use crate::{E2EProfile, E2EStatus};
+    #[test]
+    fn test_profile8m_basic_request_example() {
+        let config = Profile8Config {
+            min_data_length: 160,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile8m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile8m::new(config).unwrap();
+
+        let mut data = vec![
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        profile_tx.source_id = 0x00123456;
+        profile_tx.message_result = 0;
+        profile_tx.message_type = 0;
+        profile_tx.protect(&mut data).unwrap();
+        // CRC check
+        assert_eq!(data[0], 0x6d);
+        assert_eq!(data[1], 0xf0);
+        assert_eq!(data[2], 0x5e);
+        assert_eq!(data[3], 0xba);
+        // length check
+        assert_eq!(data[4], 0x00);
+        assert_eq!(data[5], 0x00);
+        assert_eq!(data[6], 0x00);
+        assert_eq!(data[7], 0x18);
+        // counter check
+        assert_eq!(data[8], 0x00);
+        assert_eq!(data[9], 0x00);
+        assert_eq!(data[10], 0x00);
+        assert_eq!(data[11], 0x00);
+        // data id check
+        assert_eq!(data[12], 0x0a);
+        assert_eq!(data[13], 0x0b);
+        assert_eq!(data[14], 0x0c);
+        assert_eq!(data[15], 0x0d);
+        // message type/result/source id check
+        assert_eq!(data[16], 0x00);
+        assert_eq!(data[17], 0x12);
+        assert_eq!(data[18], 0x34);
+        assert_eq!(data[19], 0x56);
+        profile_rx.source_id = 0x00123456;
+        profile_rx.message_result = 0;
+        profile_rx.message_type = 0;
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile8m_basic_response_example() {
+        let config = Profile8Config {
+            min_data_length: 160,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile8m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile8m::new(config).unwrap();
+
+        let mut data = vec![
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        profile_tx.source_id = 0x00123456;
+        profile_tx.message_result = 0;
+        profile_tx.message_type = 1;
+        profile_tx.protect(&mut data).unwrap();
+        // CRC check
+        assert_eq!(data[0], 0x46);
+        assert_eq!(data[1], 0xb2);
+        assert_eq!(data[2], 0x64);
+        assert_eq!(data[3], 0x03);
+        // length check
+        assert_eq!(data[4], 0x00);
+        assert_eq!(data[5], 0x00);
+        assert_eq!(data[6], 0x00);
+        assert_eq!(data[7], 0x18);
+        // counter check
+        assert_eq!(data[8], 0x00);
+        assert_eq!(data[9], 0x00);
+        assert_eq!(data[10], 0x00);
+        assert_eq!(data[11], 0x00);
+        // data id check
+        assert_eq!(data[12], 0x0a);
+        assert_eq!(data[13], 0x0b);
+        assert_eq!(data[14], 0x0c);
+        assert_eq!(data[15], 0x0d);
+        // message type/result/source id check
+        assert_eq!(data[16], 0x40);
+        assert_eq!(data[17], 0x12);
+        assert_eq!(data[18], 0x34);
+        assert_eq!(data[19], 0x56);
+        profile_rx.source_id = 0x00123456;
+        profile_rx.message_result = 0;
+        profile_rx.message_type = 1;
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile8m_basic_error_example() {
+        let config = Profile8Config {
+            min_data_length: 160,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile8m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile8m::new(config).unwrap();
+
+        let mut data = vec![
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        profile_tx.source_id = 0x00123456;
+        profile_tx.message_result = 1;
+        profile_tx.message_type = 1;
+        profile_tx.protect(&mut data).unwrap();
+        // CRC check
+        assert_eq!(data[0], 0xe0);
+        assert_eq!(data[1], 0xd2);
+        assert_eq!(data[2], 0x45);
+        assert_eq!(data[3], 0x15);
+        // length check
+        assert_eq!(data[4], 0x00);
+        assert_eq!(data[5], 0x00);
+        assert_eq!(data[6], 0x00);
+        assert_eq!(data[7], 0x18);
+        // counter check
+        assert_eq!(data[8], 0x00);
+        assert_eq!(data[9], 0x00);
+        assert_eq!(data[10], 0x00);
+        assert_eq!(data[11], 0x00);
+        // data id check
+        assert_eq!(data[12], 0x0a);
+        assert_eq!(data[13], 0x0b);
+        assert_eq!(data[14], 0x0c);
+        assert_eq!(data[15], 0x0d);
+        // message type/result/source id check
+        assert_eq!(data[16], 0x50);
+        assert_eq!(data[17], 0x12);
+        assert_eq!(data[18], 0x34);
+        assert_eq!(data[19], 0x56);
+        profile_rx.source_id = 0x00123456;
+        profile_rx.message_result = 1;
+        profile_rx.message_type = 1;
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile8m_source_id_error() {
+        let config = Profile8Config {
+            min_data_length: 160,
+            ..Default::default()
+        };
+
+        let mut profile_tx = Profile8m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile8m::new(config).unwrap();
+
+        let mut data = vec![0x00; 24];
+        profile_tx.source_id = 0x00123456;
+        profile_tx.protect(&mut data).unwrap();
+        profile_rx.source_id = 0x00654321;
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::SourceIdError);
+    }
+
+    #[test]
+    fn test_profile8m_default_config() {
+        // the default of Profile 8 leaves no room for the metadata fields
+        assert!(Profile8m::new(Profile8Config::default()).is_err());
+        let config = Profile8m::default_config();
+        assert_eq!(config.min_data_length, 160);
+        let mut profile_tx = Profile8m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile8m::new(config).unwrap();
+
+        let mut data = vec![0x00; 20];
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+
+    #[test]
+    fn test_profile8m_first_message() {
+        // the first message is checked against the initial counter 0, so only
+        // a sender starting from it is accepted
+        let config = Profile8m::default_config();
+        let mut profile_tx = Profile8m::new(config.clone()).unwrap();
+        let mut data = vec![0x00; 20];
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile8m::new(config.clone()).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Repeated);
+
+        profile_tx.protect(&mut data).unwrap();
+        profile_tx.protect(&mut data).unwrap();
+        let mut profile_rx = Profile8m::new(config).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::WrongSequence);
+    }
+
+    #[test]
+    fn test_profile8m_sync_ignores_other_source() {
+        let config = Profile8Config {
+            sync_policy: E2ESyncPolicy {
+                min_consecutive: 2,
+                ..Default::default()
+            },
+            ..Profile8m::default_config()
+        };
+        let mut profile_tx = Profile8m::new(config.clone()).unwrap();
+        let mut profile_other = Profile8m::new(config.clone()).unwrap();
+        let mut profile_rx = Profile8m::new(config).unwrap();
+        profile_other.source_id = 0x00123456;
+
+        // data of another sender is rejected before its counter is evaluated,
+        // so it does not count towards the synchronization
+        let mut data = vec![0x00; 20];
+        profile_other.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::SourceIdError);
+        profile_other.protect(&mut data).unwrap();
+        let report = profile_rx.check_detailed(&data).unwrap();
+        assert_eq!(report.status, E2EStatus::SourceIdError);
+        assert_eq!(report.header.source_id, Some(0x00123456));
+
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Sync);
+        profile_tx.protect(&mut data).unwrap();
+        assert_eq!(profile_rx.check(&data).unwrap(), E2EStatus::Ok);
+    }
+}
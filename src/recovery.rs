@@ -0,0 +1,363 @@
+//! # Data ID Recovery
+//!
+//! Recovers the Data ID a sender was configured with from captured frames.
+//! The given profile configuration provides the layout (offsets, lengths,
+//! mode); its Data ID is ignored.
+//!
+//! - Profile 1, 5, 6 and 11: the 16-bit Data ID only enters the CRC, so all
+//!   65536 values are tried on each frame, using the linearity of the CRC
+//!   described below to evaluate them without a CRC computation each.
+//! - Profile 2 and 22: the 8-bit entry of the Data ID list selected by the
+//!   counter is recovered per counter value from the frames with that counter.
+//! - Profile 4, 7, 8 (and their M and 44 variants): the 32-bit Data ID is
+//!   transmitted in the E2E header, so no search is needed. It is read from
+//!   the header and accepted if it is the same in all frames with a valid CRC.
+//!
+//! Data IDs not allowed by the configured mode are never proposed. With few
+//! frames, several Data IDs may match the CRCs by chance; all consistent
+//! candidates are returned, so more frames narrow the result down.
+//!
+//! More frames do not help where the Data ID is wider than the CRC: the CRC
+//! is linear, so for frames of equal length two Data IDs whose difference
+//! does not change the CRC match the same frames. In Profile 1/11 `Both` mode
+//! the 16-bit Data ID enters an 8-bit CRC and 256 candidates always remain;
+//! in `Nibble` mode the transmitted nibble reduces them to 16.
+
+use crate::any::{AnyProfile, AnyProfileConfig};
+use crate::{profile1, profile11, profile2, profile22, profile5, profile6};
+use crate::{E2EError, E2EProfile, E2EResult};
+
+// Constants
+const DATA_ID_NUMBER: usize = 16;
+const NIBBLE_DATA_ID_MASK: u32 = 0x0F00;
+
+/// Result of a Data ID recovery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataIdRecovery {
+    /// Data IDs consistent with all frames, in ascending order
+    DataId(Vec<u32>),
+    /// Data ID list entries consistent with the frames of each counter value
+    /// (indexed by counter), `None` for counter values without frames
+    DataIdList(Vec<Option<Vec<u8>>>),
+}
+
+/// Copy of the configuration with the given Data ID
+fn with_data_id(config: &AnyProfileConfig, data_id: u16) -> AnyProfileConfig {
+    let mut config = config.clone();
+    match &mut config {
+        AnyProfileConfig::Profile1(c) => c.data_id = data_id,
+        AnyProfileConfig::Profile5(c) => c.data_id = data_id,
+        AnyProfileConfig::Profile6(c) => c.data_id = data_id,
+        AnyProfileConfig::Profile11(c) => c.data_id = data_id,
+        _ => {}
+    }
+    config
+}
+
+/// Copy of the configuration with the given Data ID list entry
+fn with_data_id_list_entry(
+    config: &AnyProfileConfig,
+    counter: usize,
+    data_id: u8,
+) -> AnyProfileConfig {
+    let mut config = config.clone();
+    match &mut config {
+        AnyProfileConfig::Profile2(c) => c.data_id_list[counter] = data_id,
+        AnyProfileConfig::Profile22(c) => c.data_id_list[counter] = data_id,
+        _ => {}
+    }
+    config
+}
+
+/// CRC of `frame` computed with the given configuration
+///
+/// The configuration is not validated, so Data ID bits not allowed by the
+/// mode can be evaluated; the frame length must have been checked with
+/// `AnyProfile::decode`.
+fn compute_crc(config: &AnyProfileConfig, frame: &[u8]) -> u64 {
+    match config {
+        AnyProfileConfig::Profile1(c) => profile1::compute_crc(c, frame).into(),
+        AnyProfileConfig::Profile2(c) => profile2::compute_crc(c, frame).into(),
+        AnyProfileConfig::Profile5(c) => profile5::compute_crc(c, frame).into(),
+        AnyProfileConfig::Profile6(c) => profile6::compute_crc(c, frame).into(),
+        AnyProfileConfig::Profile11(c) => profile11::compute_crc(c, frame).into(),
+        AnyProfileConfig::Profile22(c) => profile22::compute_crc(c, frame).into(),
+        _ => unreachable!("the Data ID is transmitted in the header"),
+    }
+}
+
+/// CRC of one frame as a function of the Data ID
+///
+/// The CRC is affine in its input, so the CRC with a Data ID is the CRC with
+/// Data ID 0 XOR the contribution of each set Data ID bit. This takes one CRC
+/// computation per Data ID bit instead of one per candidate.
+struct FrameCrc {
+    base: u64,
+    contributions: Vec<u64>,
+}
+
+impl FrameCrc {
+    /// Evaluate `crc` for Data ID 0 and for each of the `bits` Data ID bits
+    fn new(bits: u32, crc: impl Fn(u32) -> u64) -> Self {
+        let base = crc(0);
+        Self {
+            base,
+            contributions: (0..bits).map(|bit| crc(1 << bit) ^ base).collect(),
+        }
+    }
+
+    fn crc(&self, data_id: u32) -> u64 {
+        self.contributions
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| data_id >> bit & 1 != 0)
+            .fold(self.base, |crc, (_, contribution)| crc ^ contribution)
+    }
+}
+
+/// Recover the 16-bit Data ID of Profile 1, 5, 6 or 11
+fn recover_16bit(config: &AnyProfileConfig, frames: &[&[u8]]) -> E2EResult<Vec<u32>> {
+    let profile = AnyProfile::new(with_data_id(config, 0))?;
+    let mut candidates: Vec<u16> = (0..=u16::MAX).collect();
+    for frame in frames {
+        let header = profile.decode(frame)?;
+        let frame_crc = FrameCrc::new(u16::BITS, |data_id| {
+            compute_crc(&with_data_id(config, data_id as u16), frame)
+        });
+        candidates.retain(|&data_id| {
+            // in Profile 1/11 Nibble mode a part of the Data ID is transmitted
+            let nibble_matches = match header.data_id {
+                Some(transmitted) => transmitted == u32::from(data_id) & NIBBLE_DATA_ID_MASK,
+                None => true,
+            };
+            nibble_matches && frame_crc.crc(data_id.into()) == header.received_crc
+        });
+    }
+    // a Data ID not allowed by the mode fails the validation
+    candidates.retain(|&data_id| AnyProfile::new(with_data_id(config, data_id)).is_ok());
+    Ok(candidates.into_iter().map(u32::from).collect())
+}
+
+/// Recover the Data ID list entries of Profile 2 or 22
+fn recover_data_id_list(
+    config: &AnyProfileConfig,
+    frames: &[&[u8]],
+) -> E2EResult<Vec<Option<Vec<u8>>>> {
+    let profile = AnyProfile::new(config.clone())?;
+    let mut list: Vec<Option<Vec<u8>>> = vec![None; DATA_ID_NUMBER];
+    for frame in frames {
+        let header = profile.decode(frame)?;
+        let counter = header.counter as usize;
+        let mut candidates = match list[counter].take() {
+            Some(candidates) => candidates,
+            None => (0..=u8::MAX).collect(),
+        };
+        let frame_crc = FrameCrc::new(u8::BITS, |data_id| {
+            compute_crc(
+                &with_data_id_list_entry(config, counter, data_id as u8),
+                frame,
+            )
+        });
+        candidates.retain(|&data_id| frame_crc.crc(data_id.into()) == header.received_crc);
+        list[counter] = Some(candidates);
+    }
+    Ok(list)
+}
+
+/// Read the transmitted Data ID of Profile 4, 7 or 8 and verify it with the CRC
+fn recover_transmitted(config: &AnyProfileConfig, frames: &[&[u8]]) -> E2EResult<Vec<u32>> {
+    let profile = AnyProfile::new(config.clone())?;
+    let mut data_id = None;
+    for frame in frames {
+        let header = profile.decode(frame)?;
+        if header.received_crc != header.computed_crc {
+            continue;
+        }
+        match (data_id, header.data_id) {
+            (None, Some(transmitted)) => data_id = Some(transmitted),
+            (Some(previous), Some(transmitted)) if previous == transmitted => {}
+            _ => return Ok(Vec::new()),
+        }
+    }
+    Ok(data_id.into_iter().collect())
+}
+
+/// Recover the Data ID from frames protected with the given profile layout
+///
+/// # Arguments
+/// * `config` - Configuration of the profile, its Data ID is ignored
+/// * `frames` - Captured frames of one sender
+///
+/// # Returns
+/// * `Ok(DataIdRecovery)` with the Data IDs consistent with all frames
+/// * `Err(E2EError)` if no frames are given, the configuration is invalid
+///   or a frame does not fit the configured length
+pub fn recover_data_id(config: &AnyProfileConfig, frames: &[&[u8]]) -> E2EResult<DataIdRecovery> {
+    if frames.is_empty() {
+        return Err(E2EError::MissingFrames);
+    }
+    match config {
+        AnyProfileConfig::Profile1(_)
+        | AnyProfileConfig::Profile5(_)
+        | AnyProfileConfig::Profile6(_)
+        | AnyProfileConfig::Profile11(_) => {
+            Ok(DataIdRecovery::DataId(recover_16bit(config, frames)?))
+        }
+        AnyProfileConfig::Profile2(_) | AnyProfileConfig::Profile22(_) => Ok(
+            DataIdRecovery::DataIdList(recover_data_id_list(config, frames)?),
+        ),
+        _ => Ok(DataIdRecovery::DataId(recover_transmitted(config, frames)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile1::{Profile1Config, Profile1IdMode};
+    use crate::profile11::{Profile11Config, Profile11IdMode};
+    use crate::profile22::Profile22Config;
+    use crate::profile5::Profile5Config;
+    use crate::profile8::Profile8Config;
+
+    /// Frames protected by a sender with the given configuration
+    fn frames(config: AnyProfileConfig, length: usize, count: u8) -> Vec<Vec<u8>> {
+        let mut sender = AnyProfile::new(config).unwrap();
+        (0..count)
+            .map(|i| {
+                let mut data = vec![i.wrapping_mul(37); length];
+                sender.protect(&mut data).unwrap();
+                data
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_recover_16bit_data_id() {
+        let config = AnyProfileConfig::Profile5(Profile5Config {
+            data_id: 0x4a5b,
+            data_length: 64,
+            ..Default::default()
+        });
+        let frames = frames(config.clone(), 8, 4);
+        let frames: Vec<&[u8]> = frames.iter().map(Vec::as_slice).collect();
+        let layout = with_data_id(&config, 0);
+        assert_eq!(
+            recover_data_id(&layout, &frames).unwrap(),
+            DataIdRecovery::DataId(vec![0x4a5b])
+        );
+
+        // a 16-bit Data ID in an 8-bit CRC cannot be recovered completely
+        let config = AnyProfileConfig::Profile11(Profile11Config {
+            mode: Profile11IdMode::Both,
+            data_id: 0x0123,
+            ..Default::default()
+        });
+        let frames = super::tests::frames(config.clone(), 8, 8);
+        let frames: Vec<&[u8]> = frames.iter().map(Vec::as_slice).collect();
+        let DataIdRecovery::DataId(candidates) = recover_data_id(&config, &frames).unwrap() else {
+            panic!("expected Data ID candidates");
+        };
+        assert_eq!(candidates.len(), 256);
+        assert!(candidates.contains(&0x0123));
+
+        // the transmitted nibble narrows the candidates down
+        let config = AnyProfileConfig::Profile11(Profile11Config {
+            data_id: 0x0123,
+            ..Default::default()
+        });
+        let frames = super::tests::frames(config.clone(), 8, 8);
+        let frames: Vec<&[u8]> = frames.iter().map(Vec::as_slice).collect();
+        let DataIdRecovery::DataId(candidates) = recover_data_id(&config, &frames).unwrap() else {
+            panic!("expected Data ID candidates");
+        };
+        assert!(candidates.len() <= 16);
+        assert!(candidates.contains(&0x0123));
+        assert!(candidates.iter().all(|id| id & 0x0f00 == 0x0100));
+
+        // in Low mode only Data IDs up to 0xFF are allowed
+        let config = AnyProfileConfig::Profile1(Profile1Config {
+            mode: Profile1IdMode::Low,
+            data_id: 0x23,
+            ..Default::default()
+        });
+        let frames = super::tests::frames(config.clone(), 8, 4);
+        let frames: Vec<&[u8]> = frames.iter().map(Vec::as_slice).collect();
+        assert_eq!(
+            recover_data_id(&config, &frames).unwrap(),
+            DataIdRecovery::DataId(vec![0x23])
+        );
+        assert!(recover_data_id(&config, &[]).is_err());
+
+        // in Alt mode the counter selects the Data ID byte entering the CRC
+        let config = AnyProfileConfig::Profile1(Profile1Config {
+            mode: Profile1IdMode::Alt,
+            data_id: 0x4a5b,
+            ..Default::default()
+        });
+        let frames = super::tests::frames(config.clone(), 8, 8);
+        let frames: Vec<&[u8]> = frames.iter().map(Vec::as_slice).collect();
+        let DataIdRecovery::DataId(candidates) = recover_data_id(&config, &frames).unwrap() else {
+            panic!("expected Data ID candidates");
+        };
+        assert!(candidates.contains(&0x4a5b));
+
+        // an invalid layout is reported instead of matching no Data ID
+        let config = AnyProfileConfig::Profile5(Profile5Config {
+            max_delta_counter: 0,
+            data_length: 64,
+            ..Default::default()
+        });
+        assert_eq!(
+            recover_data_id(&config, &frames),
+            Err(E2EError::MaxDeltaCounterInvalid { max: 0xFF })
+        );
+    }
+    #[test]
+    fn test_recover_data_id_list() {
+        let data_id_list = [
+            0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+            0xff, 0x00,
+        ];
+        let config = AnyProfileConfig::Profile22(Profile22Config {
+            data_id_list,
+            ..Default::default()
+        });
+        // counters 0..=4 twice, the other counter values are not received
+        let frames = frames(config, 8, 21);
+        let frames: Vec<&[u8]> = frames
+            .iter()
+            .filter(|f| f[1] & 0x0f <= 4)
+            .map(Vec::as_slice)
+            .collect();
+        let layout = AnyProfileConfig::Profile22(Profile22Config::default());
+        let DataIdRecovery::DataIdList(list) = recover_data_id(&layout, &frames).unwrap() else {
+            panic!("expected a Data ID list");
+        };
+        for (counter, entry) in list.iter().enumerate() {
+            match entry {
+                Some(candidates) => {
+                    assert!(counter <= 4);
+                    assert!(candidates.contains(&data_id_list[counter]));
+                }
+                None => assert!(counter > 4),
+            }
+        }
+    }
+
+    #[test]
+    fn test_recover_transmitted_data_id() {
+        let config = AnyProfileConfig::Profile8(Profile8Config {
+            data_id: 0xdeadbeef,
+            ..Default::default()
+        });
+        let mut frames = frames(config, 20, 3);
+        // frames with a corrupted CRC are ignored
+        frames[1][10] ^= 0xff;
+        let frames: Vec<&[u8]> = frames.iter().map(Vec::as_slice).collect();
+        let layout = AnyProfileConfig::Profile8(Profile8Config::default());
+        assert_eq!(
+            recover_data_id(&layout, &frames).unwrap(),
+            DataIdRecovery::DataId(vec![0xdeadbeef])
+        );
+    }
+}
@@ -0,0 +1,433 @@
+//! # E2E State Machine Implementation
+//!
+//! The E2E state machine (E2E_SM) evaluates the per-cycle `E2EStatus` returned
+//! by any profile's `check` over a sliding window and derives the overall
+//! communication state:
+//! - `NoData`: no valid data has been received yet
+//! - `Init`: data has been received, but not enough to judge its validity
+//! - `Valid`: enough valid data within the window
+//! - `Invalid`: too many errors or too few valid data within the window
+//!
+//! Each state uses its own window size and thresholds.
+
+use crate::{E2EError, E2EResult, E2EStatus};
+
+// Constants
+const MAX_WINDOW_SIZE: usize = 255;
+
+/// Communication state of the E2E state machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum E2ESMState {
+    /// No data received since initialization
+    NoData,
+    /// Data received, state is being established
+    Init,
+    /// Communication is valid
+    Valid,
+    /// Communication is invalid
+    Invalid,
+}
+
+/// Profile independent check status as evaluated by the state machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SMStatus {
+    Ok,
+    Repeated,
+    WrongSequence,
+    NoNewData,
+    Error,
+}
+
+impl From<E2EStatus> for SMStatus {
+    fn from(status: E2EStatus) -> Self {
+        match status {
+            E2EStatus::Ok | E2EStatus::OkSomeLost => SMStatus::Ok,
+            E2EStatus::Repeated => SMStatus::Repeated,
+            E2EStatus::WrongSequence | E2EStatus::Sync => SMStatus::WrongSequence,
+            E2EStatus::NoNewData => SMStatus::NoNewData,
+            E2EStatus::CrcError
+            | E2EStatus::DataIdError
+            | E2EStatus::DataLengthError
+            | E2EStatus::SourceIdError
+            | E2EStatus::MessageTypeError
+            | E2EStatus::MessageResultError => SMStatus::Error,
+        }
+    }
+}
+
+/// Configuration for the E2E state machine
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(remote = "Self", default))]
+pub struct E2EStateMachineConfig {
+    /// Size of the monitoring window in state `Init`
+    pub window_size_init: u8,
+    /// Size of the monitoring window in state `Valid`
+    pub window_size_valid: u8,
+    /// Size of the monitoring window in state `Invalid`
+    pub window_size_invalid: u8,
+    /// Minimal number of checks with `Ok` in the window to switch from `Init` to `Valid`
+    pub min_ok_state_init: u8,
+    /// Maximal number of checks with an error in the window to stay out of `Invalid` in state `Init`
+    pub max_error_state_init: u8,
+    /// Minimal number of checks with `Ok` in the window to stay in `Valid`
+    pub min_ok_state_valid: u8,
+    /// Maximal number of checks with an error in the window to stay in `Valid`
+    pub max_error_state_valid: u8,
+    /// Minimal number of checks with `Ok` in the window to switch from `Invalid` to `Valid`
+    pub min_ok_state_invalid: u8,
+    /// Maximal number of checks with an error in the window to switch from `Invalid` to `Valid`
+    pub max_error_state_invalid: u8,
+    /// Clear the monitoring window when switching to `Invalid`
+    pub clear_to_invalid: bool,
+}
+
+impl Default for E2EStateMachineConfig {
+    fn default() -> Self {
+        Self {
+            window_size_init: 3,
+            window_size_valid: 3,
+            window_size_invalid: 3,
+            min_ok_state_init: 2,
+            max_error_state_init: 1,
+            min_ok_state_valid: 1,
+            max_error_state_valid: 1,
+            min_ok_state_invalid: 2,
+            max_error_state_invalid: 0,
+            clear_to_invalid: false,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl_validated_serde!(E2EStateMachineConfig, E2EStateMachine::validate_config);
+
+/// E2E State Machine Implementation
+///
+/// Implements the AUTOSAR E2E state machine on top of the per-cycle `E2EStatus`
+#[derive(Clone)]
+pub struct E2EStateMachine {
+    config: E2EStateMachineConfig,
+    state: E2ESMState,
+    window: [SMStatus; MAX_WINDOW_SIZE],
+    window_top_index: usize,
+    window_len: usize,
+    ok_count: u8,
+    error_count: u8,
+}
+
+impl E2EStateMachine {
+    /// Validate configuration parameters
+    fn validate_config(config: &E2EStateMachineConfig) -> E2EResult<()> {
+        let windows = [
+            (
+                config.window_size_init,
+                config.min_ok_state_init,
+                config.max_error_state_init,
+            ),
+            (
+                config.window_size_valid,
+                config.min_ok_state_valid,
+                config.max_error_state_valid,
+            ),
+            (
+                config.window_size_invalid,
+                config.min_ok_state_invalid,
+                config.max_error_state_invalid,
+            ),
+        ];
+        for (window_size, min_ok, max_error) in windows {
+            if window_size == 0 {
+                return Err(E2EError::WindowSizeInvalid);
+            }
+            if min_ok > window_size || max_error > window_size {
+                return Err(E2EError::ThresholdExceedsWindowSize);
+            }
+        }
+        if config.min_ok_state_init == 0
+            || config.min_ok_state_valid == 0
+            || config.min_ok_state_invalid == 0
+        {
+            return Err(E2EError::MinOkThresholdInvalid);
+        }
+        Ok(())
+    }
+
+    /// Create a new state machine in state `NoData`
+    ///
+    /// # Errors
+    /// Returns an `E2EError` if the configuration is invalid
+    pub fn new(config: E2EStateMachineConfig) -> E2EResult<Self> {
+        Self::validate_config(&config)?;
+        Ok(Self {
+            config,
+            state: E2ESMState::NoData,
+            window: [SMStatus::Repeated; MAX_WINDOW_SIZE],
+            window_top_index: 0,
+            window_len: 0,
+            ok_count: 0,
+            error_count: 0,
+        })
+    }
+
+    /// Current communication state
+    pub fn state(&self) -> E2ESMState {
+        self.state
+    }
+
+    /// Number of `Ok` statuses in the current window
+    pub fn ok_count(&self) -> u8 {
+        self.ok_count
+    }
+
+    /// Number of error statuses in the current window
+    pub fn error_count(&self) -> u8 {
+        self.error_count
+    }
+
+    fn window_size(&self, state: E2ESMState) -> usize {
+        match state {
+            E2ESMState::NoData | E2ESMState::Init => self.config.window_size_init as usize,
+            E2ESMState::Valid => self.config.window_size_valid as usize,
+            E2ESMState::Invalid => self.config.window_size_invalid as usize,
+        }
+    }
+
+    /// Recount the statuses within the window of the given state
+    fn count_status(&mut self, state: E2ESMState) {
+        let window_size = self.window_size(state).min(self.window_len);
+        self.ok_count = 0;
+        self.error_count = 0;
+        for i in 1..=window_size {
+            let index = (self.window_top_index + MAX_WINDOW_SIZE - i) % MAX_WINDOW_SIZE;
+            match self.window[index] {
+                SMStatus::Ok => self.ok_count += 1,
+                SMStatus::Error => self.error_count += 1,
+                SMStatus::Repeated | SMStatus::WrongSequence | SMStatus::NoNewData => {}
+            }
+        }
+    }
+
+    fn add_status(&mut self, status: SMStatus) {
+        self.window[self.window_top_index] = status;
+        self.window_top_index = (self.window_top_index + 1) % MAX_WINDOW_SIZE;
+        self.window_len = (self.window_len + 1).min(MAX_WINDOW_SIZE);
+        self.count_status(self.state);
+    }
+
+    fn clear_status(&mut self) {
+        self.window_top_index = 0;
+        self.window_len = 0;
+        self.ok_count = 0;
+        self.error_count = 0;
+    }
+
+    fn enter_invalid(&mut self) {
+        self.state = E2ESMState::Invalid;
+        if self.config.clear_to_invalid {
+            self.clear_status();
+        } else {
+            self.count_status(self.state);
+        }
+    }
+
+    fn enter_valid(&mut self) {
+        self.state = E2ESMState::Valid;
+        self.count_status(self.state);
+    }
+
+    /// Evaluate the status of the current cycle and return the new communication state
+    ///
+    /// # Arguments
+    /// * `status` - Result of the profile's `check` in this cycle
+    pub fn check(&mut self, status: E2EStatus) -> E2ESMState {
+        let status = SMStatus::from(status);
+        match self.state {
+            E2ESMState::NoData => {
+                if status != SMStatus::Error && status != SMStatus::NoNewData {
+                    self.state = E2ESMState::Init;
+                }
+            }
+            E2ESMState::Init => {
+                self.add_status(status);
+                if self.error_count <= self.config.max_error_state_init
+                    && self.ok_count >= self.config.min_ok_state_init
+                {
+                    self.enter_valid();
+                } else if self.error_count > self.config.max_error_state_init {
+                    self.enter_invalid();
+                }
+            }
+            E2ESMState::Valid => {
+                self.add_status(status);
+                if self.error_count > self.config.max_error_state_valid
+                    || self.ok_count < self.config.min_ok_state_valid
+                {
+                    self.enter_invalid();
+                }
+            }
+            E2ESMState::Invalid => {
+                self.add_status(status);
+                if self.error_count <= self.config.max_error_state_invalid
+                    && self.ok_count >= self.config.min_ok_state_invalid
+                {
+                    self.enter_valid();
+                }
+            }
+        }
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(sm: &mut E2EStateMachine, statuses: &[E2EStatus]) -> E2ESMState {
+        let mut state = sm.state();
+        for status in statuses {
+            state = sm.check(*status);
+        }
+        state
+    }
+
+    #[test]
+    fn test_sm_nodata_transitions() {
+        let mut sm = E2EStateMachine::new(E2EStateMachineConfig::default()).unwrap();
+        assert_eq!(sm.state(), E2ESMState::NoData);
+        // errors keep the state machine in NODATA
+        assert_eq!(sm.check(E2EStatus::CrcError), E2ESMState::NoData);
+        assert_eq!(sm.check(E2EStatus::DataIdError), E2ESMState::NoData);
+        assert_eq!(sm.check(E2EStatus::NoNewData), E2ESMState::NoData);
+        // any non-error status switches to INIT
+        assert_eq!(sm.check(E2EStatus::Repeated), E2ESMState::Init);
+
+        let mut sm = E2EStateMachine::new(E2EStateMachineConfig::default()).unwrap();
+        assert_eq!(sm.check(E2EStatus::WrongSequence), E2ESMState::Init);
+        let mut sm = E2EStateMachine::new(E2EStateMachineConfig::default()).unwrap();
+        assert_eq!(sm.check(E2EStatus::Ok), E2ESMState::Init);
+    }
+
+    #[test]
+    fn test_sm_init_transitions() {
+        // INIT -> VALID with enough OK and few errors
+        let mut sm = E2EStateMachine::new(E2EStateMachineConfig::default()).unwrap();
+        sm.check(E2EStatus::Ok);
+        assert_eq!(sm.check(E2EStatus::Ok), E2ESMState::Init);
+        assert_eq!(sm.check(E2EStatus::OkSomeLost), E2ESMState::Valid);
+
+        // INIT stays INIT with too few OK
+        let mut sm = E2EStateMachine::new(E2EStateMachineConfig::default()).unwrap();
+        sm.check(E2EStatus::Ok);
+        assert_eq!(
+            run(
+                &mut sm,
+                &[E2EStatus::Ok, E2EStatus::Repeated, E2EStatus::WrongSequence]
+            ),
+            E2ESMState::Init
+        );
+
+        // INIT -> INVALID with too many errors
+        let mut sm = E2EStateMachine::new(E2EStateMachineConfig::default()).unwrap();
+        sm.check(E2EStatus::Ok);
+        assert_eq!(sm.check(E2EStatus::CrcError), E2ESMState::Init);
+        assert_eq!(sm.check(E2EStatus::CrcError), E2ESMState::Invalid);
+    }
+
+    #[test]
+    fn test_sm_valid_transitions() {
+        let mut sm = E2EStateMachine::new(E2EStateMachineConfig::default()).unwrap();
+        run(&mut sm, &[E2EStatus::Ok, E2EStatus::Ok, E2EStatus::Ok]);
+        assert_eq!(sm.state(), E2ESMState::Valid);
+        // VALID stays VALID with a single error in the window
+        assert_eq!(sm.check(E2EStatus::CrcError), E2ESMState::Valid);
+        assert_eq!(sm.check(E2EStatus::Repeated), E2ESMState::Valid);
+        // VALID -> INVALID with too many errors
+        assert_eq!(sm.check(E2EStatus::DataLengthError), E2ESMState::Invalid);
+
+        // VALID -> INVALID without any OK in the window
+        let mut sm = E2EStateMachine::new(E2EStateMachineConfig::default()).unwrap();
+        run(&mut sm, &[E2EStatus::Ok, E2EStatus::Ok, E2EStatus::Ok]);
+        assert_eq!(
+            run(
+                &mut sm,
+                &[
+                    E2EStatus::Repeated,
+                    E2EStatus::WrongSequence,
+                    E2EStatus::Repeated
+                ]
+            ),
+            E2ESMState::Invalid
+        );
+    }
+
+    #[test]
+    fn test_sm_invalid_transitions() {
+        let mut sm = E2EStateMachine::new(E2EStateMachineConfig::default()).unwrap();
+        run(
+            &mut sm,
+            &[E2EStatus::Ok, E2EStatus::CrcError, E2EStatus::CrcError],
+        );
+        assert_eq!(sm.state(), E2ESMState::Invalid);
+        // INVALID stays INVALID while an error is in the window
+        assert_eq!(sm.check(E2EStatus::Ok), E2ESMState::Invalid);
+        assert_eq!(sm.check(E2EStatus::Ok), E2ESMState::Invalid);
+        // INVALID -> VALID once the error left the window
+        assert_eq!(sm.check(E2EStatus::Ok), E2ESMState::Valid);
+    }
+
+    #[test]
+    fn test_sm_clear_to_invalid() {
+        let config = E2EStateMachineConfig {
+            clear_to_invalid: true,
+            max_error_state_invalid: 1,
+            ..Default::default()
+        };
+        let mut sm = E2EStateMachine::new(config).unwrap();
+        run(
+            &mut sm,
+            &[E2EStatus::Ok, E2EStatus::CrcError, E2EStatus::CrcError],
+        );
+        assert_eq!(sm.state(), E2ESMState::Invalid);
+        assert_eq!(sm.error_count(), 0);
+        // the errors were cleared, two OK are enough to recover
+        assert_eq!(sm.check(E2EStatus::Ok), E2ESMState::Invalid);
+        assert_eq!(sm.check(E2EStatus::Ok), E2ESMState::Valid);
+    }
+
+    #[test]
+    fn test_sm_state_specific_window_size() {
+        let config = E2EStateMachineConfig {
+            window_size_init: 2,
+            window_size_valid: 5,
+            min_ok_state_init: 2,
+            min_ok_state_valid: 3,
+            max_error_state_valid: 2,
+            ..Default::default()
+        };
+        let mut sm = E2EStateMachine::new(config).unwrap();
+        run(&mut sm, &[E2EStatus::Ok, E2EStatus::Ok, E2EStatus::Ok]);
+        assert_eq!(sm.state(), E2ESMState::Valid);
+        assert_eq!(sm.check(E2EStatus::Ok), E2ESMState::Valid);
+        // the larger window of VALID tolerates two errors
+        assert_eq!(sm.check(E2EStatus::CrcError), E2ESMState::Valid);
+        assert_eq!(sm.check(E2EStatus::CrcError), E2ESMState::Valid);
+        assert_eq!(sm.ok_count(), 3);
+        assert_eq!(sm.check(E2EStatus::CrcError), E2ESMState::Invalid);
+    }
+
+    #[test]
+    fn test_sm_invalid_config() {
+        assert!(E2EStateMachine::new(E2EStateMachineConfig {
+            window_size_valid: 0,
+            ..Default::default()
+        })
+        .is_err());
+        assert!(E2EStateMachine::new(E2EStateMachineConfig {
+            min_ok_state_init: 4,
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
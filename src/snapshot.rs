@@ -0,0 +1,201 @@
+//! # Counter state snapshots
+//!
+//! A snapshot captures the counter state of a profile instance, so that a
+//! sender or receiver can continue its sequence after a warm reset instead of
+//! restarting at counter 0.
+//!
+//! # Binary format (version 2)
+//! [VERSION(1B) | FINGERPRINT(4B) | COUNTER(4B) | FLAGS(1B) | MESSAGE TYPE(1B) |
+//!  MESSAGE RESULT(1B) | SOURCE ID(4B) | RECEIVER STATE(3B) | SYNC STATE(2B) | CRC(4B)]
+//! - FLAGS (bit 0) : initialized
+//! - CRC : CRC-32P4 over all preceding bytes
+//!
+//! All fields are big endian. Version 1 snapshots lack the SYNC STATE and are
+//! rejected, their receivers start over as after a cold reset.
+
+use crate::{E2EError, E2EResult};
+use crc::{Crc, Digest, CRC_32_AUTOSAR};
+
+static SNAPSHOT_CRC: Crc<u32> = Crc::<u32>::new(&CRC_32_AUTOSAR);
+const FLAG_INITIALIZED: u8 = 0x01;
+
+/// Counter state of a profile instance
+///
+/// Created by `E2EProfile::snapshot` and applied with `E2EProfile::restore`.
+/// The fingerprint identifies the profile and its configuration, so a
+/// snapshot is only accepted by an instance with the same configuration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct E2ESnapshot {
+    /// Fingerprint of the profile and its configuration
+    pub fingerprint: u32,
+    /// Counter of the last sent or received data
+    pub counter: u32,
+    /// Whether the receiver has accepted data since startup or the last
+    /// resynchronization
+    pub initialized: bool,
+    /// Message type, for M variants
+    pub message_type: u8,
+    /// Message result, for M variants
+    pub message_result: u8,
+    /// Source ID, for M variants
+    pub source_id: u32,
+    /// Additional receiver state of Profiles 1 and 2: the accepted counter delta,
+    /// the no new or repeated data counter and the sync counter
+    pub receiver_state: [u8; 3],
+    /// Progress of the `E2ESyncPolicy`: the consecutive messages with a valid
+    /// counter and the consecutive `WrongSequence` results
+    pub sync_state: [u8; 2],
+}
+
+impl E2ESnapshot {
+    /// Version of the binary format written by `to_bytes`
+    pub const VERSION: u8 = 2;
+    /// Length of the binary format in bytes
+    pub const LEN: usize = 25;
+
+    pub(crate) fn new(fingerprint: u32, counter: u32) -> Self {
+        Self {
+            fingerprint,
+            counter,
+            ..Default::default()
+        }
+    }
+
+    /// Encode the snapshot, e.g. to store it in NVM
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut bytes = [0u8; Self::LEN];
+        bytes[0] = Self::VERSION;
+        bytes[1..5].copy_from_slice(&self.fingerprint.to_be_bytes());
+        bytes[5..9].copy_from_slice(&self.counter.to_be_bytes());
+        bytes[9] = if self.initialized {
+            FLAG_INITIALIZED
+        } else {
+            0
+        };
+        bytes[10] = self.message_type;
+        bytes[11] = self.message_result;
+        bytes[12..16].copy_from_slice(&self.source_id.to_be_bytes());
+        bytes[16..19].copy_from_slice(&self.receiver_state);
+        bytes[19..21].copy_from_slice(&self.sync_state);
+        let crc = SNAPSHOT_CRC.checksum(&bytes[..21]);
+        bytes[21..].copy_from_slice(&crc.to_be_bytes());
+        bytes
+    }
+
+    /// Decode a snapshot encoded by `to_bytes`
+    ///
+    /// # Errors
+    /// Returns `E2EError::SnapshotVersionUnsupported` if the snapshot was
+    /// written in another format version, and `E2EError::SnapshotInvalid` if
+    /// its length, CRC or flags are invalid
+    pub fn from_bytes(bytes: &[u8]) -> E2EResult<Self> {
+        if bytes.len() != Self::LEN {
+            return Err(E2EError::SnapshotInvalid);
+        }
+        if bytes[0] != Self::VERSION {
+            return Err(E2EError::SnapshotVersionUnsupported { version: bytes[0] });
+        }
+        let crc = u32::from_be_bytes([bytes[21], bytes[22], bytes[23], bytes[24]]);
+        if crc != SNAPSHOT_CRC.checksum(&bytes[..21]) || bytes[9] & !FLAG_INITIALIZED != 0 {
+            return Err(E2EError::SnapshotInvalid);
+        }
+        Ok(Self {
+            fingerprint: u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]),
+            counter: u32::from_be_bytes([bytes[5], bytes[6], bytes[7], bytes[8]]),
+            initialized: bytes[9] & FLAG_INITIALIZED != 0,
+            message_type: bytes[10],
+            message_result: bytes[11],
+            source_id: u32::from_be_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+            receiver_state: [bytes[16], bytes[17], bytes[18]],
+            sync_state: [bytes[19], bytes[20]],
+        })
+    }
+
+    /// Check that the snapshot was taken under the given fingerprint and that
+    /// the counter is within the counter range
+    pub(crate) fn validate(&self, fingerprint: u32, counter_max: u32) -> E2EResult<()> {
+        if self.fingerprint != fingerprint {
+            return Err(E2EError::SnapshotConfigMismatch {
+                expected: fingerprint,
+                actual: self.fingerprint,
+            });
+        }
+        if self.counter > counter_max {
+            return Err(E2EError::SnapshotInvalid);
+        }
+        Ok(())
+    }
+}
+
+/// Fingerprint of a profile configuration, a CRC-32P4 over the profile name
+/// and the configuration fields in big endian
+pub(crate) struct Fingerprint(Digest<'static, u32>);
+
+impl Fingerprint {
+    pub(crate) fn new(profile: &str) -> Self {
+        let mut digest = SNAPSHOT_CRC.digest();
+        digest.update(profile.as_bytes());
+        Self(digest)
+    }
+
+    pub(crate) fn field(mut self, bytes: &[u8]) -> Self {
+        self.0.update(bytes);
+        self
+    }
+
+    pub(crate) fn finalize(self) -> u32 {
+        self.0.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_bytes_roundtrip() {
+        let snapshot = E2ESnapshot {
+            fingerprint: 0x12345678,
+            counter: 0xFFFFFFFE,
+            initialized: true,
+            message_type: 1,
+            message_result: 1,
+            source_id: 0x0ABCDEF0,
+            receiver_state: [1, 2, 3],
+            sync_state: [4, 5],
+        };
+        let bytes = snapshot.to_bytes();
+        assert_eq!(bytes[0], E2ESnapshot::VERSION);
+        assert_eq!(&bytes[1..5], &[0x12, 0x34, 0x56, 0x78]);
+        assert_eq!(E2ESnapshot::from_bytes(&bytes).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_snapshot_bytes_rejected() {
+        let bytes = E2ESnapshot::new(0x12345678, 3).to_bytes();
+        assert_eq!(
+            E2ESnapshot::from_bytes(&bytes[..24]),
+            Err(E2EError::SnapshotInvalid)
+        );
+        let mut corrupted = bytes;
+        corrupted[6] ^= 0x01;
+        assert_eq!(
+            E2ESnapshot::from_bytes(&corrupted),
+            Err(E2EError::SnapshotInvalid)
+        );
+        let mut future = bytes;
+        future[0] = 3;
+        assert_eq!(
+            E2ESnapshot::from_bytes(&future),
+            Err(E2EError::SnapshotVersionUnsupported { version: 3 })
+        );
+        // version 1 snapshots were written without the sync state
+        let mut version1 = bytes;
+        version1[0] = 1;
+        assert_eq!(
+            E2ESnapshot::from_bytes(&version1),
+            Err(E2EError::SnapshotVersionUnsupported { version: 1 })
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! # SOME/IP Message Reader
+//!
+//! Splits UDP payloads into SOME/IP messages, for the offline analysis of
+//! recorded SOME/IP traffic (see [`crate::analysis`]).
+//!
+//! # SOME/IP header layout
+//! [SERVICE ID(2B) | METHOD ID(2B) | LENGTH(4B) | CLIENT ID(2B) | SESSION ID(2B) |
+//!  PROTOCOL VERSION(1B) | INTERFACE VERSION(1B) | MESSAGE TYPE(1B) | RETURN CODE(1B)]
+
+// Constants
+const SOMEIP_HEADER_LENGTH: usize = 16;
+const SOMEIP_LENGTH_END: usize = 8;
+
+/// Identifier of a SOME/IP method or event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SomeIpMessageId {
+    /// Service ID
+    pub service_id: u16,
+    /// Method or event ID
+    pub method_id: u16,
+}
+
+/// SOME/IP message read from a UDP payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SomeIpMessage<'a> {
+    /// Service and method or event ID
+    pub message_id: SomeIpMessageId,
+    /// Client ID
+    pub client_id: u16,
+    /// Session ID
+    pub session_id: u16,
+    /// Interface version
+    pub interface_version: u8,
+    /// Message type, e.g. 0x02 for a notification
+    pub message_type: u8,
+    /// Return code
+    pub return_code: u8,
+    /// Complete message including the SOME/IP header
+    pub data: &'a [u8],
+}
+
+impl SomeIpMessage<'_> {
+    /// Whether the message is a response or an error
+    pub fn is_response(&self) -> bool {
+        self.message_type & 0x80 != 0
+    }
+
+    /// Whether the message is an error or carries a return code other than E_OK
+    pub fn is_error(&self) -> bool {
+        self.message_type & 0x81 == 0x81 || self.return_code != 0x00
+    }
+}
+
+/// Read the SOME/IP messages of a UDP payload
+///
+/// A UDP payload may carry several SOME/IP messages back to back. Reading
+/// stops at the first truncated message.
+pub fn read_messages(payload: &[u8]) -> Vec<SomeIpMessage<'_>> {
+    let mut messages = Vec::new();
+    let mut rest = payload;
+    while rest.len() >= SOMEIP_HEADER_LENGTH {
+        let length = u32::from_be_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+        let Some(end) = length
+            .checked_add(SOMEIP_LENGTH_END)
+            .filter(|&end| end >= SOMEIP_HEADER_LENGTH && end <= rest.len())
+        else {
+            break;
+        };
+        messages.push(SomeIpMessage {
+            message_id: SomeIpMessageId {
+                service_id: u16::from_be_bytes([rest[0], rest[1]]),
+                method_id: u16::from_be_bytes([rest[2], rest[3]]),
+            },
+            client_id: u16::from_be_bytes([rest[8], rest[9]]),
+            session_id: u16::from_be_bytes([rest[10], rest[11]]),
+            interface_version: rest[13],
+            message_type: rest[14],
+            return_code: rest[15],
+            data: &rest[..end],
+        });
+        rest = &rest[end..];
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_messages() {
+        let payload = [
+            0x12, 0x34, 0x80, 0x01, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x01, 0x00, 0x02, 0x01, 0x01,
+            0x02, 0x00, 0xaa, 0xbb, // notification with 2 bytes payload
+            0x12, 0x34, 0x00, 0x05, 0x00, 0x00, 0x00, 0x08, 0x00, 0x01, 0x00, 0x03, 0x01, 0x01,
+            0x81, 0x01, // error without payload
+            0x12, 0x34, 0x00, 0x05, 0x00, 0x00, 0x00, 0x10, // truncated
+        ];
+        let messages = read_messages(&payload);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[0].message_id,
+            SomeIpMessageId {
+                service_id: 0x1234,
+                method_id: 0x8001
+            }
+        );
+        assert_eq!(messages[0].session_id, 2);
+        assert_eq!(messages[0].data.len(), 18);
+        assert!(!messages[0].is_response());
+        assert!(!messages[0].is_error());
+        assert_eq!(messages[1].message_id.method_id, 0x0005);
+        assert!(messages[1].is_response());
+        assert!(messages[1].is_error());
+    }
+}
@@ -0,0 +1,516 @@
+//! # E2E Transformer Implementation
+//!
+//! The E2E transformer (E2EXf) wraps an E2E profile and takes care of the
+//! E2E header placement. On the sender side it inserts the header into a
+//! serialized payload at the configured offset and protects the result; on
+//! the receiver side it checks the header and strips it again.
+//!
+//! Both directions are available in-place (the header is inserted into or
+//! removed from the same buffer) and out-of-place (a separate output buffer
+//! is used).
+//!
+//! For SOME/IP messages the `*_someip_*` functions take the complete message
+//! including the 16-byte SOME/IP header. The last `upper_header_bits_to_shift`
+//! bits of the SOME/IP header (Request ID, Protocol Version, Interface Version,
+//! Message Type and Return Code for 64 bits) are included in the protected area,
+//! while Message ID and Length stay unprotected. The Length field is updated
+//! when the E2E header is inserted or removed.
+//!
+//! # Data layout
+//! [UPPER DATA (header_offset) | E2E HEADER (header_length) | DATA ...]
+//!
+//! # SOME/IP data layout
+//! [MESSAGE ID(4B) | LENGTH(4B) | REQUEST ID(4B) | VERSIONS/TYPE/CODE(4B) | E2E HEADER | DATA ...]
+
+use crate::{E2EError, E2EHeaderLayout, E2EProfile, E2EResult, E2EStatus, OffsetField};
+
+// Constants
+const BITS_PER_BYTE: usize = 8;
+const SOMEIP_HEADER_LENGTH: usize = 16;
+const SOMEIP_LENGTH_OFFSET: usize = 4;
+const SOMEIP_LENGTH_END: usize = 8;
+
+/// Configuration for the E2E transformer
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(remote = "Self", default))]
+pub struct E2ETransformerConfig {
+    /// Bit offset of the E2E header in the protected data (same as the profile's offset)
+    pub header_offset: usize,
+    /// Length of the E2E header in bits
+    pub header_length: usize,
+    /// Number of SOME/IP header bits in front of the E2E header that are protected
+    pub upper_header_bits_to_shift: usize,
+}
+
+impl Default for E2ETransformerConfig {
+    fn default() -> Self {
+        Self {
+            header_offset: 0,
+            header_length: 96, // bits, Profile 4
+            upper_header_bits_to_shift: 0,
+        }
+    }
+}
+
+/// Validate configuration parameters
+fn validate_config(config: &E2ETransformerConfig) -> E2EResult<()> {
+    if !config.header_offset.is_multiple_of(BITS_PER_BYTE) {
+        return Err(E2EError::UnalignedOffset {
+            field: OffsetField::Header,
+            alignment: BITS_PER_BYTE,
+        });
+    }
+    if config.header_length == 0 || !config.header_length.is_multiple_of(BITS_PER_BYTE) {
+        return Err(E2EError::HeaderLengthInvalid);
+    }
+    if !config
+        .upper_header_bits_to_shift
+        .is_multiple_of(BITS_PER_BYTE)
+        || config.upper_header_bits_to_shift > SOMEIP_HEADER_LENGTH * BITS_PER_BYTE
+    {
+        return Err(E2EError::UpperHeaderBitsInvalid {
+            max: SOMEIP_HEADER_LENGTH * BITS_PER_BYTE,
+        });
+    }
+    if config.header_offset < config.upper_header_bits_to_shift {
+        return Err(E2EError::HeaderOffsetBeforeUpperHeader {
+            offset: config.header_offset,
+            upper_header_bits: config.upper_header_bits_to_shift,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+impl_validated_serde!(E2ETransformerConfig, validate_config);
+
+/// E2E Transformer Implementation
+///
+/// Inserts and strips the E2E header of the wrapped profile
+#[derive(Clone)]
+pub struct E2ETransformer<P: E2EProfile + E2EHeaderLayout> {
+    profile: P,
+    config: E2ETransformerConfig,
+}
+
+impl<P: E2EProfile + E2EHeaderLayout> E2ETransformer<P> {
+    /// Create a new transformer around an already configured profile
+    ///
+    /// # Errors
+    /// Returns an `E2EError` if the configuration is invalid, and
+    /// `E2EError::HeaderMismatch` if the header offset or length differs
+    /// from the header of the profile
+    pub fn new(profile: P, config: E2ETransformerConfig) -> E2EResult<Self> {
+        validate_config(&config)?;
+        if config.header_offset != profile.header_offset()
+            || config.header_length != profile.header_length()
+        {
+            return Err(E2EError::HeaderMismatch {
+                expected_offset: profile.header_offset(),
+                expected_length: profile.header_length(),
+                actual_offset: config.header_offset,
+                actual_length: config.header_length,
+            });
+        }
+        Ok(Self { profile, config })
+    }
+
+    /// Wrapped profile
+    pub fn profile(&self) -> &P {
+        &self.profile
+    }
+
+    /// Mutable access to the wrapped profile, e.g. to set M variant metadata
+    pub fn profile_mut(&mut self) -> &mut P {
+        &mut self.profile
+    }
+
+    fn header_offset(&self) -> usize {
+        self.config.header_offset / BITS_PER_BYTE
+    }
+
+    fn header_length(&self) -> usize {
+        self.config.header_length / BITS_PER_BYTE
+    }
+
+    fn validate_input_length(&self, required: usize, len: usize) -> E2EResult<()> {
+        if len < required {
+            return Err(E2EError::LengthOutOfRange {
+                expected_min: required,
+                expected_max: usize::MAX,
+                actual: len,
+            });
+        }
+        Ok(())
+    }
+
+    fn validate_output_length(&self, required: usize, len: usize) -> E2EResult<()> {
+        if len < required {
+            return Err(E2EError::BufferTooSmall {
+                expected_min: required,
+                actual: len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Insert the E2E header into `buffer` and protect it
+    ///
+    /// The serialized data occupies `buffer[..length]`; the data behind the
+    /// header offset is shifted by the header length, so `buffer` has to
+    /// provide room for the header.
+    ///
+    /// # Returns
+    /// * `Ok(usize)` length of the protected data
+    /// * `Err(E2EError)` if the buffer is too small or protection failed
+    pub fn protect_in_place(&mut self, buffer: &mut [u8], length: usize) -> E2EResult<usize> {
+        self.validate_input_length(self.header_offset(), length)?;
+        let protected_length = length + self.header_length();
+        self.validate_output_length(protected_length, buffer.len())?;
+        let offset = self.header_offset();
+        buffer.copy_within(offset..length, offset + self.header_length());
+        buffer[offset..offset + self.header_length()].fill(0x00);
+        self.profile.protect(&mut buffer[..protected_length])?;
+        Ok(protected_length)
+    }
+
+    /// Protect `input` into `output` with the E2E header inserted
+    ///
+    /// # Returns
+    /// * `Ok(usize)` length of the protected data in `output`
+    /// * `Err(E2EError)` if the output buffer is too small or protection failed
+    pub fn protect_out_of_place(&mut self, input: &[u8], output: &mut [u8]) -> E2EResult<usize> {
+        self.validate_input_length(self.header_offset(), input.len())?;
+        let protected_length = input.len() + self.header_length();
+        self.validate_output_length(protected_length, output.len())?;
+        let offset = self.header_offset();
+        let header_end = offset + self.header_length();
+        output[..offset].copy_from_slice(&input[..offset]);
+        output[offset..header_end].fill(0x00);
+        output[header_end..protected_length].copy_from_slice(&input[offset..]);
+        self.profile.protect(&mut output[..protected_length])?;
+        Ok(protected_length)
+    }
+
+    /// Check the protected data in `buffer` and strip the E2E header
+    ///
+    /// The header is removed regardless of the check result.
+    ///
+    /// # Returns
+    /// * `Ok((E2EStatus, usize))` check result and length of the remaining data
+    /// * `Err(E2EError)` if the buffer is too short or checking failed
+    pub fn check_in_place(&mut self, buffer: &mut [u8]) -> E2EResult<(E2EStatus, usize)> {
+        let header_end = self.header_offset() + self.header_length();
+        self.validate_input_length(header_end, buffer.len())?;
+        let status = self.profile.check(buffer)?;
+        let length = buffer.len();
+        buffer.copy_within(header_end..length, self.header_offset());
+        Ok((status, length - self.header_length()))
+    }
+
+    /// Check the protected data in `input` and copy it without the E2E header into `output`
+    ///
+    /// # Returns
+    /// * `Ok((E2EStatus, usize))` check result and length of the data in `output`
+    /// * `Err(E2EError)` if a buffer is too small or checking failed
+    pub fn check_out_of_place(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> E2EResult<(E2EStatus, usize)> {
+        let offset = self.header_offset();
+        let header_end = offset + self.header_length();
+        self.validate_input_length(header_end, input.len())?;
+        let length = input.len() - self.header_length();
+        self.validate_output_length(length, output.len())?;
+        let status = self.profile.check(input)?;
+        output[..offset].copy_from_slice(&input[..offset]);
+        output[offset..length].copy_from_slice(&input[header_end..]);
+        Ok((status, length))
+    }
+
+    /// Start of the protected area within a SOME/IP message
+    fn someip_protected_start(&self) -> usize {
+        SOMEIP_HEADER_LENGTH - self.config.upper_header_bits_to_shift / BITS_PER_BYTE
+    }
+
+    fn write_someip_length(&self, buffer: &mut [u8], length: usize) {
+        let someip_length = (length - SOMEIP_LENGTH_END) as u32;
+        buffer[SOMEIP_LENGTH_OFFSET..SOMEIP_LENGTH_END]
+            .copy_from_slice(&someip_length.to_be_bytes());
+    }
+
+    /// Insert the E2E header into the SOME/IP message in `buffer` and protect it
+    ///
+    /// The SOME/IP message occupies `buffer[..length]`. The header offset is
+    /// relative to the start of the protected area, i.e. the shifted upper header.
+    ///
+    /// # Returns
+    /// * `Ok(usize)` length of the protected SOME/IP message
+    /// * `Err(E2EError)` if the buffer is too small or protection failed
+    pub fn protect_someip_in_place(
+        &mut self,
+        buffer: &mut [u8],
+        length: usize,
+    ) -> E2EResult<usize> {
+        let start = self.someip_protected_start();
+        let offset = start + self.header_offset();
+        self.validate_input_length(offset, length)?;
+        let protected_length = length + self.header_length();
+        self.validate_output_length(protected_length, buffer.len())?;
+        buffer.copy_within(offset..length, offset + self.header_length());
+        buffer[offset..offset + self.header_length()].fill(0x00);
+        self.write_someip_length(buffer, protected_length);
+        self.profile.protect(&mut buffer[start..protected_length])?;
+        Ok(protected_length)
+    }
+
+    /// Protect the SOME/IP message in `input` into `output` with the E2E header inserted
+    ///
+    /// # Returns
+    /// * `Ok(usize)` length of the protected SOME/IP message in `output`
+    /// * `Err(E2EError)` if the output buffer is too small or protection failed
+    pub fn protect_someip_out_of_place(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> E2EResult<usize> {
+        self.validate_output_length(input.len() + self.header_length(), output.len())?;
+        output[..input.len()].copy_from_slice(input);
+        self.protect_someip_in_place(output, input.len())
+    }
+
+    /// Check the protected SOME/IP message in `buffer` and strip the E2E header
+    ///
+    /// The header is removed regardless of the check result.
+    ///
+    /// # Returns
+    /// * `Ok((E2EStatus, usize))` check result and length of the remaining SOME/IP message
+    /// * `Err(E2EError)` if the buffer is too short or checking failed
+    pub fn check_someip_in_place(&mut self, buffer: &mut [u8]) -> E2EResult<(E2EStatus, usize)> {
+        let start = self.someip_protected_start();
+        let offset = start + self.header_offset();
+        let header_end = offset + self.header_length();
+        self.validate_input_length(header_end, buffer.len())?;
+        let status = self.profile.check(&buffer[start..])?;
+        let length = buffer.len() - self.header_length();
+        buffer.copy_within(header_end.., offset);
+        self.write_someip_length(buffer, length);
+        Ok((status, length))
+    }
+
+    /// Check the protected SOME/IP message in `input` and copy it without the E2E header into `output`
+    ///
+    /// # Returns
+    /// * `Ok((E2EStatus, usize))` check result and length of the SOME/IP message in `output`
+    /// * `Err(E2EError)` if a buffer is too small or checking failed
+    pub fn check_someip_out_of_place(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> E2EResult<(E2EStatus, usize)> {
+        let start = self.someip_protected_start();
+        let offset = start + self.header_offset();
+        let header_end = offset + self.header_length();
+        self.validate_input_length(header_end, input.len())?;
+        let length = input.len() - self.header_length();
+        self.validate_output_length(length, output.len())?;
+        let status = self.profile.check(&input[start..])?;
+        output[..offset].copy_from_slice(&input[..offset]);
+        output[offset..length].copy_from_slice(&input[header_end..]);
+        self.write_someip_length(output, length);
+        Ok((status, length))
+    }
+
+    /// Check cycle in which no new data has been received
+    pub fn check_no_data(&mut self) -> E2EResult<E2EStatus> {
+        self.profile.check_no_data()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile4::{Profile4, Profile4Config};
+    use crate::profile7::{Profile7, Profile7Config};
+
+    fn transformer(offset: u16) -> E2ETransformer<Profile4> {
+        let profile = Profile4::new(Profile4Config {
+            offset,
+            min_data_length: offset + 96,
+            ..Default::default()
+        })
+        .unwrap();
+        E2ETransformer::new(
+            profile,
+            E2ETransformerConfig {
+                header_offset: offset as usize,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_transformer_in_place() {
+        let mut transformer_tx = transformer(0);
+        let mut transformer_rx = transformer(0);
+
+        let mut buffer = [0u8; 32];
+        buffer[..4].copy_from_slice(&[0x11, 0x22, 0x33, 0x44]);
+        let length = transformer_tx.protect_in_place(&mut buffer, 4).unwrap();
+        assert_eq!(length, 16);
+        // same result as the Profile 4 example with the header reserved by hand
+        let mut expected = vec![0x00; 16];
+        expected[12..].copy_from_slice(&[0x11, 0x22, 0x33, 0x44]);
+        Profile4::new(Profile4Config::default())
+            .unwrap()
+            .protect(&mut expected)
+            .unwrap();
+        assert_eq!(&buffer[..length], &expected[..]);
+
+        let (status, length) = transformer_rx
+            .check_in_place(&mut buffer[..length])
+            .unwrap();
+        assert_eq!(status, E2EStatus::Ok);
+        assert_eq!(&buffer[..length], &[0x11, 0x22, 0x33, 0x44]);
+    }
+
+    #[test]
+    fn test_transformer_out_of_place_with_offset() {
+        let mut transformer_tx = transformer(64);
+        let mut transformer_rx = transformer(64);
+
+        let input = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0xaa, 0xbb];
+        let mut protected = [0u8; 22];
+        let length = transformer_tx
+            .protect_out_of_place(&input, &mut protected)
+            .unwrap();
+        assert_eq!(length, 22);
+        assert_eq!(&protected[..8], &input[..8]);
+        assert_eq!(&protected[20..], &input[8..]);
+        // length field
+        assert_eq!(protected[8], 0x00);
+        assert_eq!(protected[9], 0x16);
+
+        let mut output = [0u8; 10];
+        let (status, length) = transformer_rx
+            .check_out_of_place(&protected, &mut output)
+            .unwrap();
+        assert_eq!(status, E2EStatus::Ok);
+        assert_eq!(&output[..length], &input[..]);
+
+        protected[21] ^= 0x01;
+        let (status, _) = transformer_rx
+            .check_out_of_place(&protected, &mut output)
+            .unwrap();
+        assert_eq!(status, E2EStatus::CrcError);
+    }
+
+    #[test]
+    fn test_transformer_buffer_too_small() {
+        let mut transformer_tx = transformer(0);
+        let mut buffer = [0u8; 14];
+        assert!(transformer_tx.protect_in_place(&mut buffer, 4).is_err());
+        let mut output = [0u8; 15];
+        assert!(transformer_tx
+            .protect_out_of_place(&[0x00; 4], &mut output)
+            .is_err());
+        assert!(E2ETransformer::new(
+            Profile4::new(Profile4Config::default()).unwrap(),
+            E2ETransformerConfig {
+                header_offset: 4,
+                ..Default::default()
+            },
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_transformer_header_mismatch() {
+        let profile = Profile7::new(Profile7Config::default()).unwrap();
+        assert_eq!(
+            E2ETransformer::new(profile.clone(), E2ETransformerConfig::default()).err(),
+            Some(E2EError::HeaderMismatch {
+                expected_offset: 0,
+                expected_length: 160,
+                actual_offset: 0,
+                actual_length: 96,
+            })
+        );
+        assert!(E2ETransformer::new(
+            profile,
+            E2ETransformerConfig {
+                header_length: 160,
+                ..Default::default()
+            },
+        )
+        .is_ok());
+        assert_eq!(
+            E2ETransformer::new(transformer(64).profile, E2ETransformerConfig::default())
+                .err()
+                .map(|error| error.is_configuration_error()),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_transformer_someip() {
+        let config = E2ETransformerConfig {
+            header_offset: 64,
+            upper_header_bits_to_shift: 64,
+            ..Default::default()
+        };
+        let mut transformer_tx =
+            E2ETransformer::new(transformer(64).profile, config.clone()).unwrap();
+        let mut transformer_rx = E2ETransformer::new(transformer(64).profile, config).unwrap();
+
+        let message = [
+            0x12, 0x34, 0x80, 0x01, // message id
+            0x00, 0x00, 0x00, 0x0c, // length
+            0x00, 0x01, 0x00, 0x02, // request id
+            0x01, 0x01, 0x02, 0x00, // versions, message type, return code
+            0xaa, 0xbb, 0xcc, 0xdd, // payload
+        ];
+        let mut protected = [0u8; 32];
+        let length = transformer_tx
+            .protect_someip_out_of_place(&message, &mut protected)
+            .unwrap();
+        assert_eq!(length, 32);
+        // SOME/IP length covers the E2E header
+        assert_eq!(
+            &protected[..8],
+            &[0x12, 0x34, 0x80, 0x01, 0x00, 0x00, 0x00, 0x18]
+        );
+        assert_eq!(&protected[8..16], &message[8..16]);
+        // E2E length field covers the shifted upper header
+        assert_eq!(protected[16], 0x00);
+        assert_eq!(protected[17], 0x18);
+        assert_eq!(&protected[28..], &message[16..]);
+
+        let mut received = protected;
+        let (status, length) = transformer_rx.check_someip_in_place(&mut received).unwrap();
+        assert_eq!(status, E2EStatus::Ok);
+        assert_eq!(&received[..length], &message[..]);
+
+        // message id is not protected, request id is
+        let mut output = [0u8; 20];
+        transformer_tx
+            .protect_someip_out_of_place(&message, &mut protected)
+            .unwrap();
+        protected[0] ^= 0xff;
+        let (status, _) = transformer_rx
+            .check_someip_out_of_place(&protected, &mut output)
+            .unwrap();
+        assert_eq!(status, E2EStatus::Ok);
+        transformer_tx
+            .protect_someip_out_of_place(&message, &mut protected)
+            .unwrap();
+        protected[11] ^= 0x01;
+        let (status, _) = transformer_rx
+            .check_someip_out_of_place(&protected, &mut output)
+            .unwrap();
+        assert_eq!(status, E2EStatus::CrcError);
+    }
+}